@@ -1,55 +1,1024 @@
-use anyhow::{Ok, Result};
+use anyhow::{anyhow, Context, Ok, Result};
 use clap::Parser;
-use dialoguer::{theme::ColorfulTheme, Input, MultiSelect};
-use std::io::Write;
+use console::{style, Key, Term};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+use notify::Watcher;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Method,
+};
+use std::{collections::BTreeMap, io::Write, path::Path, str::FromStr};
+use tokio::io::AsyncWriteExt;
+use url::Url;
 use xdiff::{
-    cli::{Action, Args, RunArgs},
-    highlight_text, print_error, DiffConfig, DiffProfile, ExtraArgs, LoadConfig, RequestProfile,
-    ResponseProfile,
+    cli::{
+        generate_man_pages, render_man_page, Action, Args, EditArgs, ExportArgs, ExportFormat,
+        FilesArgs, ImportArgs, ImportFormat, ListArgs, ManArgs, ParseArgs, ReplayArgs, ReviewArgs,
+        RunArgs, ShowArgs, SnapshotArgs, ValidateArgs, WatchArgs,
+    },
+    diff_files, har_requests, highlight_text, print_error, print_paged, render_json,
+    render_markdown, render_tap, save_suppressions, set_color_choice, set_diff_colors,
+    set_rate_limit, set_theme, set_verbosity, use_color, ColorChoice, ConfigDocument, DiffConfig,
+    DiffContext, DiffHunk, DiffOptions, DiffProfile, DiffReport, ExtraArgs, JsonPath, LoadConfig,
+    OutputFormat, RequestProfile, ResponseBody, ResponseProfile, ResponseStatusError, Suppression,
+    TextRenderer,
 };
 
+// 发现差异时的退出码
+const EXIT_DIFF: i32 = 1;
+// 请求失败、配置错误等其他错误的退出码
+const EXIT_ERROR: i32 = 2;
+// 直接比较两个 URL 时 profile 的名称
+const ADHOC_PROFILE: &str = "adhoc";
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    let (color, verbose) = match &args.action {
+        Action::Run(args) => (args.color, args.verbose),
+        _ => (ColorChoice::Auto, 0),
+    };
+    set_color_choice(color);
+    set_verbosity(verbose);
 
     let result = match args.action {
         // 我需要 run函数出错的时候，打印出错误信息，并且给错误信息上色
-        Action::Run(args) => run(args).await,
-        Action::Parse => parse().await,
-        _ => panic!("Not implemented`没有该实现 "),
+        Action::Run(args) if args.watch_config => watch_config(*args).await,
+        Action::Run(args) => run(*args).await,
+        Action::Review(args) => review(args).await,
+        Action::Snapshot(args) => snapshot(args).await,
+        Action::Replay(args) => replay(args).await,
+        Action::Files(args) => files(args).await,
+        Action::List(args) => list(args),
+        Action::Edit(args) => edit(args).await,
+        Action::Import(args) => import(args),
+        Action::Export(args) => export(args),
+        Action::Watch(args) => watch(args).await,
+        Action::Validate(args) => validate(args),
+        Action::Show(args) => show(args),
+        Action::Man(args) => man(args),
+        Action::Parse(args) => parse(args).await.map(|_| 0),
+        _ => Err(anyhow!("Not implemented`没有该实现")),
     };
 
-    print_error(result)?;
+    // 非 2xx 响应导致的失败使用单独的退出码
+    let code = match &result {
+        Result::Ok(code) => *code,
+        Err(e) if e.downcast_ref::<ResponseStatusError>().is_some() => {
+            ResponseStatusError::EXIT_CODE
+        }
+        Err(_) => EXIT_ERROR,
+    };
+    print_error(result.map(|_| ()))?;
+    if code != 0 {
+        std::process::exit(code);
+    }
 
     Ok(())
 }
 
-pub async fn run(args: RunArgs) -> Result<()> {
-    let config_file = args.config.unwrap_or_else(|| "./xdiff.yml".to_string());
+/// 返回退出码：没有差异为 0，发现差异为 1(`--no-fail-on-diff` 时为 0)
+pub async fn run(args: RunArgs) -> Result<i32> {
+    let (config_file, config) = run_config(&args)?;
+    if let Some(theme) = args.theme.as_ref().or(config.settings.theme.as_ref()) {
+        set_theme(theme)?;
+    }
+    if let Some(colors) = &config.settings.colors {
+        set_diff_colors(colors)?;
+    }
+    // 只有 --all、--tag、--skip-tag 或者直接比较两个 URL 时，才按名称顺序从所有 profile 中选择，再按标签筛选
+    let select_all =
+        args.all || args.url1.is_some() || !args.tag.is_empty() || !args.skip_tag.is_empty();
+    let names: Vec<&String> = match (args.profile.is_empty(), select_all) {
+        (false, _) => args.profile.iter().collect(),
+        (true, true) => {
+            let mut names: Vec<&String> = config.profiles.keys().collect();
+            names.sort();
+            names
+        }
+        (true, false) => {
+            return Err(anyhow!(
+                "No profile given, use -p, --all, --tag or --skip-tag`没有指定要运行的 profile"
+            ))
+        }
+    };
+    // 先检查所有 profile 是否存在，再开始发送请求
+    let mut profiles = vec![];
+    for name in names {
+        let profile = get_profile(&config, name, &config_file)?;
+        let tagged = args.tag.is_empty() || profile.has_any_tag(&args.tag);
+        if tagged && !profile.has_any_tag(&args.skip_tag) {
+            profiles.push((name.clone(), profile.clone()));
+        }
+    }
+    if profiles.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No profiles to run in config file {}`配置文件中没有要运行的 profile",
+            config_file
+        ));
+    }
+
+    if let Some(rate) = args.rate {
+        set_rate_limit(rate)?;
+    }
+
+    let extra_args = args.extra_args();
+    let format = args.output_format();
+    let report_files = args.report_files()?;
+    let args1 = extra_args.merge(&args.extra_params1.clone().into());
+    let args2 = extra_args.merge(&args.extra_params2.clone().into());
+
+    if args.dry_run {
+        for (name, profile) in &profiles {
+            println!("{}", style(format!("== {} ==", name)).bold());
+            for (req, sent) in profile.dry_run(&args1, &args2)? {
+                println!("{}\n{}", style(format!("# {}", req)).dim(), sent.to_text());
+            }
+        }
+        return Ok(0);
+    }
+
+    // 所有 profile 同时运行
+    let count = profiles.len();
+    let mut tasks = tokio::task::JoinSet::new();
+    for (i, (name, profile)) in profiles.into_iter().enumerate() {
+        let options = args.diff_options(&name);
+        let (args1, args2) = (args1.clone(), args2.clone());
+        tasks.spawn(async move {
+            let result = profile.compare_all(&args1, &args2, &options).await;
+            let result = result.map(|mut reports| {
+                name_reports(&name, &profile, &mut reports);
+                reports
+            });
+            (i, name, result)
+        });
+    }
+
+    // 多个 profile 输出到终端时，每个 profile 完成后立即输出，不经过分页程序
+    let stream = count > 1
+        && args.output.is_none()
+        && args.output_dir.is_none()
+        && (args.quiet || matches!(format, OutputFormat::Text | OutputFormat::Patch));
+    let mut results: Vec<Option<_>> = (0..count).map(|_| None).collect();
+    let mut printed = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let (i, name, result) = joined?;
+        // 只有一个 profile 时直接返回错误，多个 profile 时其他 profile 继续运行
+        if count == 1 && result.is_err() {
+            return result.map(|_| 0);
+        }
+        results[i] = Some((name, result));
+        if !stream {
+            continue;
+        }
+        match args.ordered {
+            // 按顺序输出时，只输出前面的 profile 都已完成的部分
+            true => {
+                while let Some(Some((name, result))) = results.get(printed) {
+                    print_profile(name, result, format, args.quiet)?;
+                    printed += 1;
+                }
+            }
+            false => {
+                if let Some((name, result)) = &results[i] {
+                    print_profile(name, result, format, args.quiet)?;
+                }
+            }
+        }
+    }
+    let mut reports: Vec<(String, DiffReport)> = vec![];
+    let mut errors = vec![];
+    for (name, result) in results.into_iter().flatten() {
+        match result {
+            Result::Ok(r) => reports.extend(r),
+            Err(e) => errors.push((name, e)),
+        }
+    }
+
+    match (&args.output, &args.output_dir) {
+        (Some(file), _) => {
+            let output = render_output(format, args.quiet, &reports, &errors, false)?;
+            write_file(file, &output)?;
+        }
+        (None, Some(dir)) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create directory`创建目录失败: {}", dir))?;
+            for report in reports.iter() {
+                let output =
+                    render_output(format, args.quiet, std::slice::from_ref(report), &[], false)?;
+                let file =
+                    std::path::Path::new(dir).join(format!("{}.{}", report.0, format.extension()));
+                write_file(&file.to_string_lossy(), &output)?;
+            }
+        }
+        (None, None) if stream => {}
+        (None, None) => {
+            let output = render_output(format, args.quiet, &reports, &errors, true)?;
+            print_paged(&output, !args.no_pager)?;
+        }
+    }
+    for (format, file) in report_files {
+        write_file(&file, &format.render(&reports, &errors)?)?;
+    }
+
+    if stream && (args.quiet || format == OutputFormat::Text) {
+        print_summary(&reports, &errors, args.quiet);
+    } else {
+        for (name, e) in &errors {
+            eprintln!("{}: error`出错: {}", name, e);
+        }
+    }
+
+    let passed = reports.iter().all(|(_, report)| report.passed());
+    match (errors.is_empty(), passed || args.no_fail_on_diff) {
+        // 所有出错的 profile 都是非 2xx 响应时使用对应的退出码
+        (false, _)
+            if errors
+                .iter()
+                .all(|(_, e)| e.downcast_ref::<ResponseStatusError>().is_some()) =>
+        {
+            Ok(ResponseStatusError::EXIT_CODE)
+        }
+        (false, _) => Ok(EXIT_ERROR),
+        (true, true) => Ok(0),
+        (true, false) => Ok(EXIT_DIFF),
+    }
+}
+
+/// 每次保存配置文件后重新运行，配置有错误时输出错误并等待下一次保存；Ctrl-C 退出
+async fn watch_config(mut args: RunArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let path = Path::new(&config_file);
+    let file_name = path.file_name().map(|name| name.to_os_string());
+    // 很多编辑器保存时先写入临时文件再重命名，所以监视所在的目录而不是文件本身
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let changed = event.is_ok_and(|event| {
+            (event.kind.is_create() || event.kind.is_modify())
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name().map(|name| name.to_os_string()) == file_name)
+        });
+        if changed {
+            let _ = tx.send(());
+        }
+    })?;
+    watcher
+        .watch(dir, notify::RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch`监视失败: {}", config_file))?;
+
+    // 每次运行都要回到等待状态，不能停在分页程序中
+    args.no_pager = true;
+    loop {
+        if console::user_attended() {
+            console::Term::stdout().clear_screen()?;
+        }
+        let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        println!("{}", style(format!("[{}] {}", time, config_file)).bold());
+        let result = run(args.clone()).await;
+        print_error(result.map(|_| ()))?;
+        println!(
+            "{}",
+            style("Waiting for the config file to change, Ctrl-C to exit`等待配置文件变化").dim()
+        );
+
+        tokio::select! {
+            _ = rx.recv() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(0),
+        }
+        // 一次保存会产生多个事件，稍等之后一起处理
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        while rx.try_recv().is_ok() {}
+    }
+}
+
+/// 按间隔重复比较一个 profile，第一次和结果变化时输出，变化时执行 `--on-change` 命令；Ctrl-C 退出
+async fn watch(args: WatchArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
     let config = DiffConfig::load_yaml(&config_file)?;
-    let profile = config.get_profile(&args.profile).ok_or_else(|| {
+    if let Some(theme) = &config.settings.theme {
+        set_theme(theme)?;
+    }
+    if let Some(colors) = &config.settings.colors {
+        set_diff_colors(colors)?;
+    }
+    let profile = get_profile(&config, &args.profile, &config_file)?;
+    let options = args.diff_options();
+
+    let mut last: Option<String> = None;
+    loop {
+        let extra_args = args.extra_args();
+        let result = profile
+            .compare_all(&extra_args, &extra_args, &options)
+            .await
+            .map(|mut reports| {
+                name_reports(&args.profile, profile, &mut reports);
+                reports
+            });
+        // 响应时间每次都不同，只按结论、状态码、差异和断言判断是否变化
+        let (fingerprint, verdict, output) = match &result {
+            Result::Ok(reports) => {
+                let fingerprint = reports
+                    .iter()
+                    .map(|(name, r)| {
+                        format!(
+                            "{} {} {:?}\n{}{:?}",
+                            name,
+                            r.verdict(),
+                            r.status,
+                            r.plain_diff(),
+                            r.failures
+                        )
+                    })
+                    .collect::<String>();
+                let verdict = match reports.iter().all(|(_, r)| r.passed()) {
+                    true => "pass",
+                    false if reports.iter().any(|(_, r)| !r.failures.is_empty()) => "fail",
+                    false => "diff",
+                };
+                let output = render_output(OutputFormat::Text, false, reports, &[], false)?;
+                (fingerprint, verdict, output)
+            }
+            Err(e) => {
+                let message = format!("{}: error`出错: {}\n", args.profile, e);
+                (message.clone(), "error", message)
+            }
+        };
+
+        if last.as_ref() != Some(&fingerprint) {
+            let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+            println!(
+                "{}",
+                style(format!("[{}] {}: {}", time, args.profile, verdict)).bold()
+            );
+            match &result {
+                Result::Ok(reports) => {
+                    print!(
+                        "{}",
+                        render_output(OutputFormat::Text, false, reports, &[], true)?
+                    )
+                }
+                Err(_) => print!("{}", output),
+            }
+            std::io::stdout().flush()?;
+            if let (Some(_), Some(command)) = (&last, &args.on_change) {
+                run_hook(command, &args.profile, verdict, &output).await;
+            }
+            last = Some(fingerprint);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(args.interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(0),
+        }
+    }
+}
+
+// 执行结果变化时的命令，命令失败只输出警告，不影响继续监视
+async fn run_hook(command: &str, profile: &str, verdict: &str, output: &str) {
+    let mut cmd = match cfg!(windows) {
+        true => tokio::process::Command::new("cmd"),
+        false => tokio::process::Command::new("sh"),
+    };
+    cmd.arg(if cfg!(windows) { "/C" } else { "-c" })
+        .arg(command)
+        .env("XDIFF_PROFILE", profile)
+        .env("XDIFF_VERDICT", verdict)
+        .stdin(std::process::Stdio::piped());
+    let result = async {
+        let mut child = cmd.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // 命令不读取标准输入时忽略写入错误
+            let _ = stdin.write_all(output.as_bytes()).await;
+        }
+        child.wait().await
+    }
+    .await;
+    match result {
+        Result::Ok(status) if status.success() => {}
+        Result::Ok(status) => eprintln!(
+            "{} {}: {}",
+            style("on-change command failed`命令失败").yellow(),
+            command,
+            status
+        ),
+        Err(e) => eprintln!(
+            "{} {}: {}",
+            style("on-change command failed`命令失败").yellow(),
+            command,
+            e
+        ),
+    }
+}
+
+// 多个 profile 的汇总：每个 profile 一行结论(quiet 时已经输出过)，最后是总数
+fn print_summary(
+    reports: &[(String, DiffReport)],
+    errors: &[(String, anyhow::Error)],
+    quiet: bool,
+) {
+    if !quiet {
+        println!("\n{}", style("== summary`汇总 ==").bold());
+        for (name, report) in reports {
+            println!("{}", report.summary_line(name));
+        }
+        for (name, e) in errors {
+            println!("{}: error`出错: {}", name, e);
+        }
+    }
+    let passed = reports.iter().filter(|(_, r)| r.passed()).count();
+    println!(
+        "total`总计: {} passed, {} failed, {} error(s)",
+        passed,
+        reports.len() - passed,
+        errors.len()
+    );
+}
+
+// 输出一个 profile 的结果，文本格式在前面加上 profile 的名称；出错时输出错误信息
+fn print_profile(
+    name: &str,
+    result: &Result<Vec<(String, DiffReport)>>,
+    format: OutputFormat,
+    quiet: bool,
+) -> Result<()> {
+    let mut output = match result {
+        Result::Ok(reports) => render_output(format, quiet, reports, &[], true)?,
+        Err(e) => format!("{}: {} {}\n", name, style("error`出错:").red(), e),
+    };
+    if !quiet && format == OutputFormat::Text {
+        output = format!("{}\n{}", style(format!("== {} ==", name)).bold(), output);
+    }
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    write!(stdout, "{}", output)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+// 只有两个请求时直接使用 profile 的名称，否则加上比较的是哪两个请求
+fn name_reports(name: &str, profile: &DiffProfile, reports: &mut [(String, DiffReport)]) {
+    for (pair, _) in reports.iter_mut() {
+        *pair = match profile.reqs.is_empty() {
+            true => name.to_string(),
+            false => format!("{} ({})", name, pair),
+        };
+    }
+}
+
+/// 按输出格式渲染所有 profile 的结果，只有输出到终端时才高亮
+fn render_output(
+    format: OutputFormat,
+    quiet: bool,
+    reports: &[(String, DiffReport)],
+    errors: &[(String, anyhow::Error)],
+    highlight: bool,
+) -> Result<String> {
+    let output = match format {
+        // quiet 时每个 profile 只输出一行结论
+        _ if quiet => reports
+            .iter()
+            .map(|(name, report)| report.summary_line(name) + "\n")
+            .collect(),
+        OutputFormat::Text | OutputFormat::Patch => {
+            let mut output = String::new();
+            for (_, report) in reports {
+                output.push_str(&report.to_text(format)?);
+                // patch 之后不能有其他内容，否则无法用 patch 或 git apply 应用，警告和断言失败输出到 stderr
+                if format == OutputFormat::Patch {
+                    eprint!("{}", report.notes(format)?);
+                }
+            }
+            match highlight && format == OutputFormat::Text {
+                true => highlight_text(&output, "diff")?,
+                // 写入文件时去掉 diff 中的颜色
+                false => console::strip_ansi_codes(&output).into_owned(),
+            }
+        }
+        OutputFormat::Markdown => render_markdown(reports, errors)?,
+        OutputFormat::Json => render_json(reports, errors)?,
+        OutputFormat::Tap => render_tap(reports, errors)?,
+    };
+    Ok(output)
+}
+
+fn write_file(file: &str, content: &str) -> Result<()> {
+    std::fs::write(file, content).with_context(|| format!("failed to write`写入失败: {}", file))
+}
+
+/// 读取要运行的配置；命令行给出两个 URL 时不读取配置文件，而是用这两个 URL 构建一个 profile
+fn run_config(args: &RunArgs) -> Result<(String, DiffConfig)> {
+    if let (Some(url1), Some(url2)) = (&args.url1, &args.url2) {
+        let profile = DiffProfile::new(url1.parse()?, url2.parse()?, ResponseProfile::default());
+        let config = DiffConfig::new([(ADHOC_PROFILE.to_string(), profile)].into());
+        return Ok(("<command line>".to_string(), config));
+    }
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    Ok((config_file.clone(), DiffConfig::load_yaml(&config_file)?))
+}
+
+fn get_profile<'a>(
+    config: &'a DiffConfig,
+    name: &str,
+    config_file: &str,
+) -> Result<&'a DiffProfile> {
+    config.get_profile(name).ok_or_else(|| {
         anyhow::anyhow!(
             "Profile {} not found in config file {}`配置文件中未找到",
-            args.profile,
+            name,
             config_file
         )
-    })?;
+    })
+}
 
-    let extra_args = args.extar_params.into();
-    let output = profile.diff(&extra_args).await?;
+/// 更新快照，或者和快照比较；有差异时返回 1
+async fn snapshot(args: SnapshotArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let config = DiffConfig::load_yaml(&config_file)?;
+    let mut names: Vec<&String> = match args.profile.is_empty() {
+        true => config
+            .profiles
+            .iter()
+            .filter(|(_, profile)| profile.snapshot.is_some())
+            .map(|(name, _)| name)
+            .collect(),
+        false => args.profile.iter().collect(),
+    };
+    names.sort();
 
-    let stdout = std::io::stdout();
-    let mut stdout = stdout.lock();
-    write!(stdout, "{}", highlight_text(&output, "diff")?)?;
+    let extra_args = ExtraArgs::default();
+    let mut passed = true;
+    for name in names {
+        let profile = get_profile(&config, name, &config_file)?;
+        let Some(snapshot) = &profile.snapshot else {
+            return Err(anyhow::anyhow!(
+                "Profile {} has no snapshot`没有配置快照",
+                name
+            ));
+        };
+        if args.update {
+            profile.update_snapshot(&extra_args).await?;
+            println!("{}: updated {}", name, snapshot);
+            continue;
+        }
+        let report = profile
+            .compare(&extra_args, &extra_args, &DiffOptions::default())
+            .await?;
+        println!("{}", report.summary_line(name));
+        passed &= report.passed();
+    }
+    match passed {
+        true => Ok(0),
+        false => Ok(EXIT_DIFF),
+    }
+}
 
-    Ok(())
+/// 用录制的响应重新过滤和比较，有差异时返回 1
+async fn replay(args: ReplayArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let config = DiffConfig::load_yaml(&config_file)?;
+    if let Some(colors) = &config.settings.colors {
+        set_diff_colors(colors)?;
+    }
+    let dir = Path::new(&args.dir);
+    let mut names = args.profile.clone();
+    if names.is_empty() {
+        // 每个子目录是一个 profile 的录制
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("failed to read directory`读取目录失败: {}", args.dir))?
+        {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    names.sort();
+
+    let options = DiffOptions::default();
+    let mut reports = vec![];
+    for name in names {
+        let profile = get_profile(&config, &name, &config_file)?;
+        let mut replayed = profile.replay(&dir.join(&name), &options).await?;
+        name_reports(&name, profile, &mut replayed);
+        reports.extend(replayed);
+    }
+    let output = render_output(options.format, args.quiet, &reports, &[], true)?;
+    print_paged(&output, true)?;
+    match reports.iter().all(|(_, report)| report.passed()) {
+        true => Ok(0),
+        false => Ok(EXIT_DIFF),
+    }
+}
+
+/// 比较两个本地文件，有差异时返回 1
+async fn files(args: FilesArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let res = match &args.profile {
+        Some(name) => {
+            let config = DiffConfig::load_yaml(&config_file)?;
+            get_profile(&config, name, &config_file)?.res.clone()
+        }
+        None => ResponseProfile::default(),
+    };
+    let options = DiffOptions {
+        mode: args.diff_mode,
+        view: args.view,
+        granularity: args.granularity,
+        context: args.context,
+        ..DiffOptions::default()
+    };
+    let diff = diff_files(&args.file1, &args.file2, &res, &options).await?;
+    if diff.is_empty() {
+        return Ok(0);
+    }
+    print_paged(&highlight_text(&diff, "diff")?, true)?;
+    Ok(EXIT_DIFF)
+}
+
+/// 列出所有 profile，每个请求一行，profile 的名称和标签只在第一行输出
+fn list(args: ListArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let config = DiffConfig::load_yaml(&config_file)?;
+    let mut names: Vec<&String> = config.profiles.keys().collect();
+    names.sort();
+
+    let mut rows = vec![[
+        "PROFILE".to_string(),
+        "METHOD".to_string(),
+        "URL".to_string(),
+        "TAGS".to_string(),
+    ]];
+    for name in names {
+        let profile = &config.profiles[name];
+        for (i, req) in profile.requests().into_iter().enumerate() {
+            let (method, url) = match (i, &profile.snapshot) {
+                // 快照代替了 req2，req2 不会发送
+                (1, Some(snapshot)) => ("-".to_string(), format!("snapshot: {}", snapshot)),
+                // 模板或者环境变量无法展开时输出配置中的 URL
+                _ => {
+                    let url = req
+                        .get_url(&ExtraArgs::default())
+                        .unwrap_or_else(|_| req.url.to_string());
+                    (req.method.to_string(), url)
+                }
+            };
+            let (name, tags) = match i {
+                0 => (name.clone(), profile.tags.join(",")),
+                _ => (String::new(), String::new()),
+            };
+            rows.push([name, method, url, tags]);
+        }
+    }
+
+    let widths: Vec<usize> = (0..4)
+        .map(|col| {
+            rows.iter()
+                .map(|row| console::measure_text_width(&row[col]))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    for (i, row) in rows.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| console::pad_str(cell, *width, console::Alignment::Left, None))
+            .collect::<Vec<_>>()
+            .join("  ");
+        match i {
+            0 => println!("{}", style(line.trim_end()).bold()),
+            _ => println!("{}", line.trim_end()),
+        }
+    }
+    Ok(0)
+}
+
+/// 导出配置中的 profile，指定了 `-p` 时只导出这些 profile
+fn export(args: ExportArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let mut config = DiffConfig::load_yaml(&config_file)?;
+    for name in &args.profile {
+        get_profile(&config, name, &config_file)?;
+    }
+    if !args.profile.is_empty() {
+        config
+            .profiles
+            .retain(|name, _| args.profile.contains(name));
+    }
+    // 使用配置文件的名称作为 collection 的名称
+    let name = Path::new(&config_file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "xdiff".to_string());
+    let content = match args.format {
+        ExportFormat::Postman => serde_json::to_string_pretty(&config.to_postman(&name)?)? + "\n",
+    };
+    match &args.output {
+        Some(file) => write_file(file, &content)?,
+        None => print!("{}", content),
+    }
+    Ok(0)
+}
+
+/// 生成 man 手册，指定目录时写入所有的手册，否则输出 xdiff.1
+fn man(args: ManArgs) -> Result<i32> {
+    match &args.out_dir {
+        Some(dir) => {
+            generate_man_pages(Path::new(dir))
+                .with_context(|| format!("failed to write man pages`写入失败: {}", dir))?;
+            println!("man pages written to {}`已写入", dir);
+        }
+        None => render_man_page(&mut std::io::stdout())?,
+    }
+    Ok(0)
+}
+
+/// 输出实际会发送的 profile，终端中按 YAML 高亮
+fn show(args: ShowArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let config = DiffConfig::load_yaml(&config_file)?;
+    let profile = get_profile(&config, &args.profile, &config_file)?;
+    let extra_args: ExtraArgs = args.extra_params.into();
+    let args1 = extra_args.merge(&args.extra_params1.into());
+    let args2 = extra_args.merge(&args.extra_params2.into());
+    let resolved = profile.resolve_args(&args1, &args2)?;
+
+    let profiles = [(args.profile, resolved)].into_iter().collect();
+    let result = serde_yaml::to_string(&DiffConfig::new(profiles))?;
+    if use_color(atty::Stream::Stdout) {
+        print!("{}", highlight_text(&result, "yaml")?);
+    } else {
+        print!("{}", result);
+    }
+    Ok(0)
+}
+
+/// 验证配置而不发送请求，输出所有的错误以及出错的那一行；有错误时返回 2
+fn validate(args: ValidateArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let path = std::env::current_dir()?.join(&config_file);
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read`读取失败: {}", config_file))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let errors = DiffConfig::check_yaml(&content, base_dir);
+    if errors.is_empty() {
+        println!("{}: ok`配置有效", config_file);
+        return Ok(0);
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    for e in &errors {
+        let message = match &e.section {
+            Some(section) => format!("`{}`: {}", section, e.message),
+            None => e.message.clone(),
+        };
+        println!("{}: {}", style("error").red().bold(), message);
+        println!("  --> {}:{}:{}", config_file, e.line, e.column);
+        // 输出出错的那一行，并在出错的列下面标出 `^`
+        if let Some(line) = lines.get(e.line - 1) {
+            let width = e.line.to_string().len();
+            println!("{:width$} |", "");
+            println!("{} | {}", style(e.line).dim(), line);
+            println!(
+                "{:width$} | {:>column$}\n",
+                "",
+                style("^").red().bold(),
+                column = e.column
+            );
+        }
+    }
+    println!("{} error(s) in {}`个错误", errors.len(), config_file);
+    Ok(EXIT_ERROR)
+}
+
+/// review 中对一段差异的结论
+#[derive(Debug, Clone, PartialEq)]
+enum Verdict {
+    // 预期中的差异，保存为 suppress 规则，附带可选的原因
+    Expected(Option<String>),
+    Regression,
+}
+
+/// 所有 profile 的差异展开成的一段，可以前后切换并随时修改结论
+struct ReviewHunk<'a> {
+    profile: &'a str,
+    hunk: DiffHunk,
+    verdict: Option<Verdict>,
+}
+
+impl ReviewHunk<'_> {
+    fn mark(&self) -> String {
+        match &self.verdict {
+            Some(Verdict::Expected(_)) => style("expected").green().to_string(),
+            Some(Verdict::Regression) => style("regression").red().to_string(),
+            None => style("unmarked").dim().to_string(),
+        }
+    }
+
+    // 只保留修改的行，作为 hunk 规则匹配的内容
+    fn suppression(&self, reason: &Option<String>) -> Suppression {
+        let changed: Vec<&str> = self
+            .hunk
+            .lines
+            .iter()
+            .filter(|l| !l.starts_with(' '))
+            .map(String::as_str)
+            .collect();
+        Suppression {
+            hunk: Some(regex::escape(&changed.join("\n"))),
+            reason: reason.clone(),
+            ..Suppression::default()
+        }
+    }
+}
+
+/// 逐段查看差异，可以前后切换，标记为预期(保存为 suppress 规则)或者回归；有回归时返回 1
+async fn review(args: ReviewArgs) -> Result<i32> {
+    let term = Term::stdout();
+    if !term.is_term() {
+        return Err(anyhow!(
+            "review needs an interactive terminal`review 需要在终端中运行"
+        ));
+    }
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let config = DiffConfig::load_yaml(&config_file)?;
+    let mut names: Vec<&String> = match args.profile.is_empty() {
+        true => config.profiles.keys().collect(),
+        false => args.profile.iter().collect(),
+    };
+    names.sort();
+
+    // 每段差异只包含相邻的修改，和 hunk 规则匹配的范围一致
+    let options = DiffOptions {
+        context: Some(DiffContext::Lines(0)),
+        ..DiffOptions::default()
+    };
+    let mut hunks = vec![];
+    for name in names {
+        let profile = get_profile(&config, name, &config_file)?;
+        let report = profile
+            .compare(&ExtraArgs::default(), &ExtraArgs::default(), &options)
+            .await?;
+        hunks.extend(report.hunks.into_iter().map(|hunk| ReviewHunk {
+            profile: name,
+            hunk,
+            verdict: None,
+        }));
+    }
+    if hunks.is_empty() {
+        println!("no differences to review`没有差异");
+        return Ok(0);
+    }
+
+    let theme = ColorfulTheme::default();
+    let mut current = 0;
+    loop {
+        let hunk = &hunks[current];
+        term.clear_screen()?;
+        println!(
+            "{} [{}/{}] {} {}",
+            style(hunk.profile).bold(),
+            current + 1,
+            hunks.len(),
+            style(&hunk.hunk.header).cyan(),
+            hunk.mark()
+        );
+        for line in &hunk.hunk.lines {
+            match line.chars().next() {
+                Some('-') => println!("{}", style(line).red()),
+                Some('+') => println!("{}", style(line).green()),
+                _ => println!("{}", line),
+            }
+        }
+        let marked = hunks.iter().filter(|h| h.verdict.is_some()).count();
+        println!(
+            "\n{}",
+            style(format!(
+                "{}/{} marked  [e]xpected [r]egression [u]nmark  [n]ext [p]revious [l]ist  [q]uit and save",
+                marked,
+                hunks.len()
+            ))
+            .dim()
+        );
+        let next = (current + 1).min(hunks.len() - 1);
+        match term.read_key()? {
+            Key::Char('e') => {
+                let reason: String = Input::with_theme(&theme)
+                    .with_prompt("Reason (optional)")
+                    .allow_empty(true)
+                    .interact_text()?;
+                hunks[current].verdict =
+                    Some(Verdict::Expected(Some(reason).filter(|r| !r.is_empty())));
+                current = next;
+            }
+            Key::Char('r') => {
+                hunks[current].verdict = Some(Verdict::Regression);
+                current = next;
+            }
+            Key::Char('u') => hunks[current].verdict = None,
+            Key::Char('n') | Key::ArrowRight | Key::ArrowDown | Key::Enter => current = next,
+            Key::Char('p') | Key::ArrowLeft | Key::ArrowUp => current = current.saturating_sub(1),
+            Key::Char('l') => {
+                let items: Vec<String> = hunks
+                    .iter()
+                    .map(|h| format!("{} {} {}", h.profile, h.hunk.header, h.mark()))
+                    .collect();
+                if let Some(i) = Select::with_theme(&theme)
+                    .items(&items)
+                    .default(current)
+                    .interact_on_opt(&term)?
+                {
+                    current = i;
+                }
+            }
+            Key::Char('q') | Key::Escape => break,
+            _ => {}
+        }
+    }
+    term.clear_screen()?;
+
+    // 按 profile 保存预期中的差异
+    let mut expected: BTreeMap<&str, Vec<Suppression>> = BTreeMap::new();
+    let mut regressions = vec![];
+    for hunk in &hunks {
+        match &hunk.verdict {
+            Some(Verdict::Expected(reason)) => expected
+                .entry(hunk.profile)
+                .or_default()
+                .push(hunk.suppression(reason)),
+            Some(Verdict::Regression) => {
+                regressions.push(format!("{} {}", hunk.profile, hunk.hunk.header))
+            }
+            None => {}
+        }
+    }
+    for (name, suppressions) in &expected {
+        save_suppressions(&config_file, name, suppressions)?;
+        println!(
+            "saved {} suppress rule(s) for {} to {}`已保存",
+            suppressions.len(),
+            name,
+            config_file
+        );
+    }
+
+    if regressions.is_empty() {
+        return Ok(0);
+    }
+    println!("\nregressions`回归:");
+    for regression in &regressions {
+        println!("  {}", regression);
+    }
+    Ok(EXIT_DIFF)
 }
 
 pub async fn run2(content: &str) -> Result<()> {
     let config = DiffConfig::from_yaml(content)?;
     let profile = config.profiles.iter().next().unwrap().1;
 
-    let output = profile.diff(&ExtraArgs::default()).await?;
+    let output = profile
+        .diff(&ExtraArgs::default())
+        .await?
+        .render(&TextRenderer::default())?;
 
     let stdout = std::io::stdout();
     let mut stdout = stdout.lock();
@@ -58,54 +1027,494 @@ pub async fn run2(content: &str) -> Result<()> {
     Ok(())
 }
 
-async fn parse() -> Result<()> {
+async fn parse(args: ParseArgs) -> Result<()> {
     // 选择主题
     let theme = ColorfulTheme::default();
-    // 从控制台获取用户输入的url1
-    let url1: String = Input::with_theme(&theme)
-        .with_prompt("Enter Url1")
-        .interact_text()?;
-    // 从控制台获取用户输入的url2
-    let url2: String = Input::with_theme(&theme)
-        .with_prompt("Enter Url2")
-        .interact_text()?;
+    let profile = prompt_profile(&theme, args.from_curl).await?;
 
-    // 解析出url1和url2的请求配置
-    let req1: RequestProfile = url1.parse()?;
-    let req2: RequestProfile = url2.parse()?;
+    // 指定了配置文件时加入到配置中，重名时选择覆盖或者换一个名称
+    if let Some(config_file) = &args.config {
+        let mut doc = ConfigDocument::load(config_file)?;
+        let name = prompt_name_or_overwrite(&theme, &doc)?;
+        doc.set_profile(&name, &profile)?;
+        doc.save()?;
+        println!("Profile {} saved to {}`已保存", name, config_file);
+        return Ok(());
+    }
 
     // 从控制台获取用户输入的profile_name
     let profile_name: String = Input::with_theme(&theme)
         .with_prompt("Enter Profile")
         .interact_text()?;
 
-    // 发送一个请求，从响应中生成 headers 的可选项
+    let config = DiffConfig::new(vec![(profile_name, profile)].into_iter().collect());
+    // 将配置文件转换为yaml格式字符串
+    let result = serde_yaml::to_string(&config)?;
+
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    if use_color(atty::Stream::Stdout) {
+        writeln!(stdout, "---\n{}---", highlight_text(&result, "yaml")?)?;
+    } else {
+        writeln!(stdout, "{}", result)?;
+    }
+    // run2(&result).await?;
+    Ok(())
+}
+
+/// 输入两个 url 或者 curl 命令并发送一次请求，从响应中选择要跳过的 headers，生成一个 DiffProfile
+async fn prompt_profile(theme: &ColorfulTheme, from_curl: Option<String>) -> Result<DiffProfile> {
+    // 从控制台获取用户输入的url1，命令行指定了 curl 命令时不再输入
+    let url1: String = match from_curl {
+        Some(command) => command,
+        None => Input::with_theme(theme)
+            .with_prompt("Enter Url1 or curl command")
+            .interact_text()?,
+    };
+    // 从控制台获取用户输入的url2
+    let url2: String = Input::with_theme(theme)
+        .with_prompt("Enter Url2 or curl command")
+        .interact_text()?;
+
+    // 解析出url1和url2的请求配置
+    let mut req1 = parse_request(&url1)?;
+    let mut req2 = parse_request(&url2)?;
+    match (is_curl(&url1), is_curl(&url2)) {
+        // 两个请求使用相同的方法、headers 和 body
+        (false, false) => {
+            let (method, headers, body) = prompt_request(theme)?;
+            for req in [&mut req1, &mut req2] {
+                req.method = method.clone();
+                req.headers = headers.clone();
+                req.body = body.clone();
+            }
+        }
+        // 另一边只有 URL 时沿用 curl 命令中的方法、headers 和 body
+        (true, false) => req2 = with_url(&req1, req2),
+        (false, true) => req1 = with_url(&req2, req1),
+        (true, true) => {}
+    }
+    let method = req1.method.clone();
+
+    // 发送一个请求，从响应中生成 headers 的可选项；写操作需要确认后才发送
+    let safe = matches!(method, Method::GET | Method::HEAD | Method::OPTIONS);
+    if !safe
+        && !Confirm::with_theme(theme)
+            .with_prompt(format!(
+                "Send the {} request to sample the response headers?",
+                method
+            ))
+            .default(true)
+            .interact()?
+    {
+        return Ok(DiffProfile::new(req1, req2, ResponseProfile::default()));
+    }
     let res = req1.send(&ExtraArgs::default()).await?;
     let headers = res.get_headers_keys();
+    // JSON 响应体中所有字段的路径，作为 skip_body 的可选项
+    let fields: Vec<String> = match res.get_body(&ResponseProfile::default()).await? {
+        ResponseBody::Json(json) => JsonPath::fields(&json)
+            .iter()
+            .map(|path| path.to_string())
+            .collect(),
+        _ => vec![],
+    };
 
     // 获取用户chosen`选择(多选)的下标，可选项为headers
-    let chosen = MultiSelect::with_theme(&theme)
+    let chosen = MultiSelect::with_theme(theme)
         .with_prompt("Select headers to skip")
         .items(&headers)
         .interact()?;
     // 从headers中获取用户选择的headers选项字符串数组
     let skip_headers = chosen.iter().map(|i| headers[*i].to_string()).collect();
 
-    // 构建一个DiffProfile-start
-    let res = ResponseProfile::new(skip_headers, vec![]);
-    let profile = DiffProfile::new(req1, req2, res);
-    // 完成DiffProfile的构建-end
-    let config = DiffConfig::new(vec![(profile_name, profile)].into_iter().collect());
-    // 将配置文件转换为yaml格式字符串
-    let result = serde_yaml::to_string(&config)?;
+    let skip_body = match fields.is_empty() {
+        true => vec![],
+        false => MultiSelect::with_theme(theme)
+            .with_prompt("Select body fields to skip")
+            .items(&fields)
+            .interact()?
+            .iter()
+            .map(|i| fields[*i].clone())
+            .collect(),
+    };
 
-    let stdout = std::io::stdout();
-    let mut stdout = stdout.lock();
-    if atty::is(atty::Stream::Stdout) {
-        writeln!(stdout, "---\n{}---", highlight_text(&result, "yaml")?)?;
-    } else {
-        writeln!(stdout, "{}", result)?;
+    // 构建一个DiffProfile
+    let res = ResponseProfile::new(skip_headers, skip_body);
+    Ok(DiffProfile::new(req1, req2, res))
+}
+
+fn is_curl(input: &str) -> bool {
+    input.trim_start().starts_with("curl ")
+}
+
+// 解析 URL 或者 curl 命令
+fn parse_request(input: &str) -> Result<RequestProfile> {
+    match is_curl(input) {
+        true => RequestProfile::from_curl(input),
+        false => input.trim().parse(),
     }
-    // run2(&result).await?;
+}
+
+// 使用 url 中的地址和参数，其他的配置沿用 base
+fn with_url(base: &RequestProfile, url: RequestProfile) -> RequestProfile {
+    RequestProfile {
+        url: url.url,
+        params: url.params,
+        ..base.clone()
+    }
+}
+
+// 选择请求方法，输入请求头和可选的 JSON body
+fn prompt_request(theme: &ColorfulTheme) -> Result<(Method, HeaderMap, Option<serde_json::Value>)> {
+    let methods = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+    let chosen = Select::with_theme(theme)
+        .with_prompt("Select method")
+        .items(&methods)
+        .default(0)
+        .interact()?;
+    let method: Method = methods[chosen].parse()?;
+
+    // 每次输入一个 `name: value`，空行结束
+    let mut headers = HeaderMap::new();
+    loop {
+        let input: String = Input::with_theme(theme)
+            .with_prompt("Enter header (name: value), empty to finish")
+            .allow_empty(true)
+            .validate_with(|input: &String| match input.trim().is_empty() {
+                true => std::result::Result::Ok(()),
+                false => parse_header(input).map(|_| ()),
+            })
+            .interact_text()?;
+        if input.trim().is_empty() {
+            break;
+        }
+        let (name, value) = parse_header(&input).map_err(|e| anyhow!(e))?;
+        headers.append(name, value);
+    }
+
+    if matches!(method, Method::GET | Method::HEAD) {
+        return Ok((method, headers, None));
+    }
+    let input: String = Input::with_theme(theme)
+        .with_prompt("Enter JSON body, empty for none")
+        .allow_empty(true)
+        .validate_with(|input: &String| match input.trim().is_empty() {
+            true => std::result::Result::Ok(()),
+            false => serde_json::from_str::<serde_json::Value>(input)
+                .map(|_| ())
+                .map_err(|e| format!("invalid JSON`无效的 JSON: {}", e)),
+        })
+        .interact_text()?;
+    let body = match input.trim().is_empty() {
+        true => None,
+        false => Some(serde_json::from_str(&input)?),
+    };
+    Ok((method, headers, body))
+}
+
+fn parse_header(input: &str) -> std::result::Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = input
+        .split_once(':')
+        .ok_or_else(|| "expected name: value`格式应为 name: value".to_string())?;
+    let name = HeaderName::from_str(name.trim())
+        .map_err(|e| format!("invalid header name`无效的 header 名称: {}", e))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|e| format!("invalid header value`无效的 header 值: {}", e))?;
+    std::result::Result::Ok((name, value))
+}
+
+/// 交互式地编辑配置文件中的 profile，保存时没有改动的 profile 原样写回
+async fn edit(args: EditArgs) -> Result<i32> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xdiff.yml".to_string());
+    let mut doc = ConfigDocument::load(&config_file)?;
+    let theme = ColorfulTheme::default();
+    let actions = [
+        "Add a profile",
+        "Duplicate a profile",
+        "Modify a profile",
+        "Delete a profile",
+        "Save and exit",
+        "Exit without saving",
+    ];
+    let mut changed = false;
+    loop {
+        let names = doc.profile_names();
+        println!(
+            "{}",
+            style(format!("Profiles in {}`配置中的 profile:", doc.path())).bold()
+        );
+        if names.is_empty() {
+            println!("  (none)");
+        }
+        for name in &names {
+            println!("  {}", name);
+        }
+        let action = Select::with_theme(&theme)
+            .with_prompt("What do you want to do")
+            .items(&actions)
+            .default(0)
+            .interact()?;
+        match action {
+            0 => {
+                let profile = prompt_profile(&theme, None).await?;
+                let name = prompt_new_name(&theme, &doc, None)?;
+                doc.set_profile(&name, &profile)?;
+                changed = true;
+            }
+            1..=3 if names.is_empty() => println!("No profiles to edit`没有可以编辑的 profile"),
+            1..=3 => {
+                let name = &names[Select::with_theme(&theme)
+                    .with_prompt("Select a profile")
+                    .items(&names)
+                    .default(0)
+                    .interact()?];
+                match action {
+                    1 => {
+                        let new_name =
+                            prompt_new_name(&theme, &doc, Some(format!("{}-copy", name)))?;
+                        doc.duplicate(name, &new_name)?;
+                        changed = true;
+                    }
+                    2 => {
+                        let mut profile = doc.profile(name)?;
+                        modify_profile(&theme, &mut profile)?;
+                        doc.set_profile(name, &profile)?;
+                        changed = true;
+                    }
+                    _ => {
+                        if Confirm::with_theme(&theme)
+                            .with_prompt(format!("Delete profile {}?", name))
+                            .interact()?
+                        {
+                            changed |= doc.remove(name);
+                        }
+                    }
+                }
+            }
+            4 => {
+                if changed {
+                    doc.save()?;
+                    println!("Saved to {}`已保存", doc.path());
+                }
+                return Ok(0);
+            }
+            _ => {
+                if !changed
+                    || Confirm::with_theme(&theme)
+                        .with_prompt("Discard the changes?")
+                        .interact()?
+                {
+                    return Ok(0);
+                }
+            }
+        }
+    }
+}
+
+/// 从录制文件中选择请求生成 profile，指定了配置文件时追加到配置中，否则输出 YAML
+fn import(args: ImportArgs) -> Result<i32> {
+    let theme = ColorfulTheme::default();
+    let entries = match args.format {
+        ImportFormat::Har => har_requests(&args.file)?,
+    };
+    let mut labels = vec![];
+    let mut requests = vec![];
+    for (label, req) in entries {
+        match req {
+            std::result::Result::Ok(req) => {
+                labels.push(label);
+                requests.push(req);
+            }
+            Err(e) => eprintln!("{} {}: {}", style("skipped`跳过").yellow(), label, e),
+        }
+    }
+    if requests.is_empty() {
+        return Err(anyhow!(
+            "No requests to import in {}`没有可以导入的请求",
+            args.file
+        ));
+    }
+    // 没有终端时 dialoguer 读不到按键，会一直重绘选择框
+    if !Term::stderr().is_term() {
+        return Err(anyhow!(
+            "import needs an interactive terminal to select requests`import 需要在终端中选择请求"
+        ));
+    }
+
+    let chosen = MultiSelect::with_theme(&theme)
+        .with_prompt("Select requests to import")
+        .items(&labels)
+        .interact()?;
+    if chosen.is_empty() {
+        println!("No requests selected`没有选择请求");
+        return Ok(0);
+    }
+    // req2 默认和 req1 相同，也可以发送到另一个部署
+    let origin: String = Input::with_theme(&theme)
+        .with_prompt("Enter the origin to compare against (e.g. https://staging.example.com), empty for the same URL")
+        .allow_empty(true)
+        .validate_with(|input: &String| match input.trim().is_empty() {
+            true => std::result::Result::Ok(()),
+            false => Url::parse(input.trim())
+                .map(|_| ())
+                .map_err(|e| format!("invalid URL`无效的 URL: {}", e)),
+        })
+        .interact_text()?;
+    let origin = match origin.trim().is_empty() {
+        true => None,
+        false => Some(Url::parse(origin.trim())?),
+    };
+
+    let mut doc = match &args.config {
+        Some(config) => ConfigDocument::load(config)?,
+        None => ConfigDocument::default(),
+    };
+    for i in &chosen {
+        let req1 = requests[*i].clone();
+        let mut req2 = req1.clone();
+        if let Some(origin) = &origin {
+            req2.url = origin.clone();
+            req2.url.set_path(req1.url.path());
+        }
+        println!("{}", labels[*i]);
+        let name = prompt_new_name(&theme, &doc, Some(profile_name(&doc, &req1)))?;
+        let profile = DiffProfile::new(req1, req2, ResponseProfile::default());
+        doc.set_profile(&name, &profile)?;
+    }
+
+    match &args.config {
+        Some(config) => {
+            doc.save()?;
+            println!(
+                "Imported {} profile(s) into {}`已导入",
+                chosen.len(),
+                config
+            );
+        }
+        None => {
+            let result = doc.to_yaml()?;
+            if use_color(atty::Stream::Stdout) {
+                println!("---\n{}---", highlight_text(&result, "yaml")?);
+            } else {
+                println!("{}", result);
+            }
+        }
+    }
+    Ok(0)
+}
+
+// 根据请求方法和路径生成默认的 profile 名称，例如 `get-api-users`，和已有的重名时加上序号
+fn profile_name(doc: &ConfigDocument, req: &RequestProfile) -> String {
+    let path: String = req
+        .url
+        .path()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '-',
+        })
+        .collect();
+    let mut name = req.method.as_str().to_lowercase();
+    for part in path.split('-').filter(|part| !part.is_empty()) {
+        name.push('-');
+        name.push_str(part);
+    }
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while doc.contains(&candidate) {
+        candidate = format!("{}-{}", name, n);
+        n += 1;
+    }
+    candidate
+}
+
+// 输入 profile 名称，和配置中已有的重名时选择覆盖或者重新输入
+fn prompt_name_or_overwrite(theme: &ColorfulTheme, doc: &ConfigDocument) -> Result<String> {
+    let name: String = Input::with_theme(theme)
+        .with_prompt("Enter Profile")
+        .interact_text()?;
+    if !doc.contains(&name) {
+        return Ok(name);
+    }
+    let chosen = Select::with_theme(theme)
+        .with_prompt(format!("Profile {} already exists in {}", name, doc.path()))
+        .items(&["Overwrite it", "Use another name"])
+        .default(0)
+        .interact()?;
+    match chosen {
+        0 => Ok(name),
+        _ => prompt_new_name(theme, doc, None),
+    }
+}
+
+// 输入一个配置中还没有的 profile 名称
+fn prompt_new_name(
+    theme: &ColorfulTheme,
+    doc: &ConfigDocument,
+    default: Option<String>,
+) -> Result<String> {
+    let mut input = Input::<String>::with_theme(theme);
+    input.with_prompt("Enter Profile");
+    if let Some(default) = default {
+        input.default(default);
+    }
+    let name = input
+        .validate_with(|name: &String| match doc.contains(name) {
+            true => Err(format!("Profile {} already exists`已存在", name)),
+            false => std::result::Result::Ok(()),
+        })
+        .interact_text()?;
+    Ok(name)
+}
+
+// 修改 profile 的 url、要跳过的 headers 和标签，请求方法、headers 和 body 保持不变
+fn modify_profile(theme: &ColorfulTheme, profile: &mut DiffProfile) -> Result<()> {
+    prompt_url(theme, "Url1", &mut profile.req1)?;
+    // 快照代替了 req2
+    if profile.snapshot.is_none() {
+        prompt_url(theme, "Url2", &mut profile.req2)?;
+    }
+    profile.res.skip_headers = prompt_list(theme, "Headers to skip", &profile.res.skip_headers)?;
+    profile.tags = prompt_list(theme, "Tags", &profile.tags)?;
     Ok(())
 }
+
+fn prompt_url(theme: &ColorfulTheme, prompt: &str, req: &mut RequestProfile) -> Result<()> {
+    // 使用配置中原始的 params，不展开模板
+    let mut url = req.url.clone();
+    if let Some(params) = req
+        .params
+        .as_ref()
+        .filter(|p| p.as_object().is_some_and(|p| !p.is_empty()))
+    {
+        url.set_query(Some(&serde_qs::to_string(params)?));
+    }
+    let input: String = Input::with_theme(theme)
+        .with_prompt(prompt)
+        .with_initial_text(url.to_string())
+        .interact_text()?;
+    if input != url.as_str() {
+        let parsed: RequestProfile = input.parse()?;
+        req.url = parsed.url;
+        req.params = parsed.params;
+    }
+    Ok(())
+}
+
+// 以逗号分隔输入一组值
+fn prompt_list(theme: &ColorfulTheme, prompt: &str, values: &[String]) -> Result<Vec<String>> {
+    let input: String = Input::with_theme(theme)
+        .with_prompt(format!("{} (comma separated)", prompt))
+        .with_initial_text(values.join(", "))
+        .allow_empty(true)
+        .interact_text()?;
+    Ok(input
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect())
+}
@@ -1,24 +1,30 @@
 use anyhow::Result;
 use clap::Parser;
-use dialoguer::{theme::ColorfulTheme, Input};
+use dialoguer::{theme::ColorfulTheme, Input, Select};
 use std::{fmt::Write as _, io::Write};
 use xdiff::{
-    cli::{Action, Args, RunArgs},
-    get_body_text, get_heardes_text, get_status_text, highlight_text, print_error, LoadConfig,
-    RequestConfig, RequestProfile,
+    cli::{ParseArgs, XreqAction, XreqArgs, XreqRunArgs},
+    get_heardes_text, get_status_text, highlight_text, print_error, set_color_choice,
+    set_rate_limit, set_theme, set_verbosity, use_color, ColorChoice, ConfigDocument, LoadConfig,
+    RequestConfig, RequestProfile, ResponseProfile, ValidatorStore, Validators,
 };
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let args = XreqArgs::parse();
+    let (color, verbose) = match &args.action {
+        XreqAction::Run(args) => (args.color, args.verbose),
+        XreqAction::Parse(_) => (ColorChoice::Auto, 0),
+    };
+    set_color_choice(color);
+    set_verbosity(verbose);
 
     // tudo 1:02:01
     // 从Parse获取的yaml字符串，转换为DiffConfig,运行 run方法
 
     let result = match args.action {
-        Action::Run(args) => run(args).await,
-        Action::Parse => parse().await,
-        _ => panic!("Not implemented`没有该实现 "),
+        XreqAction::Run(args) => run(*args).await,
+        XreqAction::Parse(args) => parse(args).await,
     };
 
     print_error(result)?;
@@ -26,30 +32,71 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn run(args: RunArgs) -> Result<()> {
-    let config_file = args.config.unwrap_or_else(|| "./xreq.yml".to_string());
+async fn run(args: XreqRunArgs) -> Result<()> {
+    let config_file = args
+        .config
+        .clone()
+        .unwrap_or_else(|| "./xreq.yml".to_string());
     let config = RequestConfig::load_yaml(&config_file)?;
-    let profile = config.get_profile(&args.profile).ok_or_else(|| {
+    if let Some(theme) = args.theme.as_ref().or(config.settings.theme.as_ref()) {
+        set_theme(theme)?;
+    }
+    let name = &args.profile;
+    let profile = config.get_profile(name).ok_or_else(|| {
         anyhow::anyhow!(
             "Profile {} not found in config file {}`配置文件中未找到",
-            args.profile,
+            name,
             config_file
         )
     })?;
 
-    let extra_args = args.extar_params.into();
-    let res = profile.send(&extra_args).await?.into_inner();
+    if let Some(rate) = args.rate {
+        set_rate_limit(rate)?;
+    }
+
+    let mut extra_args = args.extra_args();
+    // 有上一次保存的校验器时，发送条件请求
+    let mut store = args
+        .validators
+        .as_ref()
+        .map(ValidatorStore::load)
+        .transpose()?;
+    let previous = store.as_ref().and_then(|store| store.get(name)).cloned();
+    if let Some(previous) = &previous {
+        extra_args.headers.extend(previous.to_headers());
+    }
+
+    if args.dry_run {
+        print!("{}", profile.dry_run(&extra_args)?.to_text());
+        return Ok(());
+    }
+
+    let res = profile.send(&extra_args).await?;
     let url = profile.get_url(&extra_args)?;
 
     // 获取响应字符串
     let mut output = String::new();
 
+    let mut conditional = None;
+    if let (Some(store), Some(path)) = (store.as_mut(), args.validators.as_ref()) {
+        let current = Validators::from_headers(res.headers());
+        if let Some(previous) = &previous {
+            conditional = Some(previous.report(res.status(), &current));
+        }
+        store.update(name, current);
+        store.save(path)?;
+    }
+
     let status = get_status_text(&res);
-    let header = get_heardes_text(&res, &[])?;
-    let body = get_body_text(res, &[]).await?;
+    let profile = ResponseProfile::default();
+    let header = get_heardes_text(&res, &profile)?;
+    let body = res.get_body_text(&profile).await?;
 
-    if atty::is(atty::Stream::Stdout) {
+    if use_color(atty::Stream::Stdout) {
         writeln!(&mut output, "Url: {}\n", url)?;
+        if let Some(conditional) = &conditional {
+            writeln!(&mut output, "Conditional: {}", conditional)?;
+        }
         writeln!(
             &mut output,
             "\n{}\n{}\n{}",
@@ -60,6 +107,9 @@ async fn run(args: RunArgs) -> Result<()> {
         )?;
     } else {
         writeln!(&mut output, "{}", body)?;
+        if let Some(conditional) = &conditional {
+            eprintln!("Conditional: {}", conditional);
+        }
     }
 
     let stdout = std::io::stdout();
@@ -69,24 +119,58 @@ async fn run(args: RunArgs) -> Result<()> {
     Ok(())
 }
 
-async fn parse() -> Result<()> {
+async fn parse(args: ParseArgs) -> Result<()> {
     let theme = ColorfulTheme::default();
-    // 从控制台获取输入的url
-    let url: String = Input::with_theme(&theme)
-        .with_prompt("Enter url")
-        .interact_text()?;
+    // 从控制台获取输入的url，也可以是 curl 命令
+    let url: String = match args.from_curl {
+        Some(command) => command,
+        None => Input::with_theme(&theme)
+            .with_prompt("Enter url or curl command")
+            .interact_text()?,
+    };
+    let profile = match url.trim_start().starts_with("curl ") {
+        true => RequestProfile::from_curl(&url)?,
+        false => url.trim().parse()?,
+    };
+
+    // 指定了配置文件时加入到配置中，重名时选择覆盖或者换一个名称
+    if let Some(config_file) = &args.config {
+        let mut doc = ConfigDocument::load(config_file)?;
+        let mut name: String = Input::with_theme(&theme)
+            .with_prompt("Enter name")
+            .interact_text()?;
+        if doc.contains(&name)
+            && Select::with_theme(&theme)
+                .with_prompt(format!("{} already exists in {}", name, config_file))
+                .items(&["Overwrite it", "Use another name"])
+                .default(0)
+                .interact()?
+                == 1
+        {
+            name = Input::with_theme(&theme)
+                .with_prompt("Enter name")
+                .validate_with(|name: &String| match doc.contains(name) {
+                    true => Err(format!("{} already exists`已存在", name)),
+                    false => Ok(()),
+                })
+                .interact_text()?;
+        }
+        doc.set_profile(&name, &profile)?;
+        doc.save()?;
+        println!("{} saved to {}`已保存", name, config_file);
+        return Ok(());
+    }
+
     // 从控制台获取输入的name
     let name: String = Input::with_theme(&theme)
         .with_prompt("Enter name")
         .interact_text()?;
-
-    let profile: RequestProfile = url.parse()?;
     let config = RequestConfig::new(vec![(name, profile)].into_iter().collect());
     let result = serde_yaml::to_string(&config)?;
 
     let stdout = std::io::stdout();
     let mut stdout = stdout.lock();
-    if atty::is(atty::Stream::Stdout) {
+    if use_color(atty::Stream::Stdout) {
         write!(stdout, "---\n{}", highlight_text(&result, "yaml")?)?;
     } else {
         write!(stdout, "{}", result)?;
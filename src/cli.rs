@@ -1,7 +1,11 @@
 /// `符号代表我要翻译它之前的内容
-use crate::ExtraArgs;
+use crate::{
+    ColorChoice, DiffContext, DiffMode, DiffOptions, DiffView, ExtraArgs, Granularity,
+    OutputFormat, ReportFormat, ResponsePart,
+};
 use anyhow::{anyhow, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::{path::Path, time::Duration};
 
 /// Diff two http requests and compare the diffrence of the responses
 /// 对比两个 HTTP 请求的差异，并比较响应的差异
@@ -16,19 +20,206 @@ pub struct Args {
 #[non_exhaustive]
 pub enum Action {
     #[clap(about = "Diff two http requests and compare the diffrence of the responses")]
-    Run(RunArgs),
+    Run(Box<RunArgs>),
+    /// 逐段查看所有 profile 的差异，可以前后切换，把预期中的差异保存为 suppress 规则
+    /// Step through every differing hunk and mark it as expected or regression
+    Review(ReviewArgs),
+    /// 检查或者更新 golden 快照
+    /// Check or update golden snapshots
+    Snapshot(SnapshotArgs),
+    /// 用 `run --record` 保存的响应重新过滤和比较，不发送请求
+    /// Re-run filtering and diff on responses saved by `run --record`
+    Replay(ReplayArgs),
+    /// 用同样的过滤规则比较两个本地文件
+    /// Diff two local files with the same normalization as responses
+    Files(FilesArgs),
+    /// 以表格列出配置中的 profile：名称、请求方法、URL 和标签
+    /// List the profiles in a config with their methods, URLs and tags
+    List(ListArgs),
+    /// 交互式地新增、复制、修改和删除配置中的 profile
+    /// Interactively add, duplicate, modify and delete the profiles in a config
+    Edit(EditArgs),
+    /// 从 HAR 等录制文件中选择请求，生成 profile
+    /// Import profiles from the requests in a capture such as a HAR file
+    Import(ImportArgs),
+    /// 把配置中的 profile 导出为其他工具的格式，例如 Postman collection
+    /// Export the profiles in a config to another tool's format, such as a Postman collection
+    Export(ExportArgs),
+    /// 按固定的间隔重复比较一个 profile，只在结果变化时输出
+    /// Re-run a profile on an interval and print only when the result changes
+    Watch(WatchArgs),
+    /// 解析并验证配置而不发送请求，输出所有的错误和所在的行
+    /// Parse and validate a config without sending requests, reporting every error with its line
+    Validate(ValidateArgs),
+    /// 输出应用默认值、认证、模板和命令行参数之后实际会发送的 profile
+    /// Print the fully resolved profile, as it will actually be sent
+    Show(ShowArgs),
     /// 解析URLs生成一个 Profile
     /// Parse URLs and generate a Profile
-    Parse,
+    Parse(ParseArgs),
+    /// 生成 xdiff 和 xreq 的 man 手册，用于打包
+    /// Generate man pages for xdiff and xreq, used for packaging
+    #[clap(hide = true)]
+    Man(ManArgs),
 }
 
+/// Send the request of a profile and print the response
+/// 发送 profile 中的请求并输出响应
 #[derive(Parser, Debug, Clone)]
-pub struct RunArgs {
+#[clap(name = "xreq", version, author, about, long_about = None)]
+pub struct XreqArgs {
+    #[clap(subcommand)]
+    pub action: XreqAction,
+}
+
+/// xreq 只支持发送请求和生成 profile，xdiff 的其他子命令不会出现在 xreq 中
+#[derive(Subcommand, Debug, Clone)]
+pub enum XreqAction {
+    /// 发送 profile 中的请求并输出响应
+    /// Send the request of a profile and print the response
+    Run(Box<XreqRunArgs>),
+    /// 解析 URL 生成一个 profile
+    /// Parse a URL and generate a profile
+    Parse(ParseArgs),
+}
+
+/// xreq run 的参数：只包含 xreq 会用到的参数，另外可以重放上一次的校验器
+#[derive(Parser, Debug, Clone)]
+pub struct XreqRunArgs {
     /// profile node name \
     /// 要使用配置中的节点名称 \
     /// `short: -p ,long: --profile`
     #[clap(short, long, value_parser)]
     pub profile: String,
+
+    /// Overrides args, same syntax as `xdiff run -e`: `-e key=value` for query params,
+    /// `-e %key=value` for headers and `-e @key=value` for the body\
+    /// 覆盖参数，语法与 `xdiff run -e` 相同：查询参数使用 `-e key=value`，
+    /// header 使用 `-e %key=value`，body 使用 `-e @key=value`\
+    /// `short: -e ,long: --extar-params`
+    #[clap(short,long,value_parser=parse_key_val,number_of_values=1)]
+    pub extar_params: Vec<KeyVal>,
+
+    /// Header in curl syntax, e.g. `-H "Authorization: Bearer x"`, same as `-e %key=value`\
+    /// 请求头，语法与 curl 相同，例如 `-H "Authorization: Bearer x"`，等同于 `-e %key=value`\
+    /// `short: -H ,long: --header`
+    #[clap(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header_arg, number_of_values = 1)]
+    pub header_params: Vec<KeyVal>,
+
+    /// Query param, e.g. `-q page=2`, same as `-e key=value`\
+    /// 查询参数，例如 `-q page=2`，等同于 `-e key=value`\
+    /// `short: -q ,long: --query`
+    #[clap(short = 'q', long = "query", value_name = "KEY=VALUE", value_parser = parse_query_arg, number_of_values = 1)]
+    pub query_params: Vec<KeyVal>,
+
+    /// Body field, e.g. `-d name=hello`, same as `-e @key=value`\
+    /// 请求体字段，例如 `-d name=hello`，等同于 `-e @key=value`\
+    /// `short: -d ,long: --body`
+    #[clap(short = 'd', long = "body", value_name = "KEY=VALUE", value_parser = parse_body_arg, number_of_values = 1)]
+    pub body_params: Vec<KeyVal>,
+
+    /// Configuration to use, defaults to `./xreq.yml`\
+    /// 要使用的配置文件，默认为 `./xreq.yml`\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+
+    /// Max requests per second, 429 responses are retried after `Retry-After`\
+    /// 每秒最大请求数，收到 429 时会按 `Retry-After` 退避重试\
+    /// `long: --rate`
+    #[clap(long, value_parser = parse_rate)]
+    pub rate: Option<f64>,
+
+    /// User-Agent sent with the request, overrides the profile's `user_agent`\
+    /// 请求使用的 User-Agent，会覆盖配置中的 `user_agent`\
+    /// `long: --user-agent`
+    #[clap(long, value_parser)]
+    pub user_agent: Option<String>,
+
+    /// Seed for template functions like `{{ fake_name() }}`, random when omitted\
+    /// 模板函数(例如 `{{ fake_name() }}`)使用的随机数种子，不设置时随机生成\
+    /// `long: --seed`
+    #[clap(long, value_parser)]
+    pub seed: Option<u64>,
+
+    /// When to use colors: `auto` (terminal only, honors `NO_COLOR`), `always` or `never`\
+    /// 是否输出颜色：`auto` 只在终端中输出并遵循 `NO_COLOR`，`always` 总是输出，`never` 不输出\
+    /// `long: --color`
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Trace the request and response to stderr, `-vv` also prints the request body and DNS timing\
+    /// 把请求和响应的细节输出到 stderr，`-vv` 还输出请求体和 DNS 耗时\
+    /// `short: -v ,long: --verbose`
+    #[clap(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Syntax highlighting theme, a built-in name or a `.tmTheme` file, overrides `settings.theme` in the config\
+    /// 语法高亮主题，内置主题的名称或者 .tmTheme 文件，会覆盖配置中的 `settings.theme`\
+    /// `long: --theme`
+    #[clap(long, value_parser)]
+    pub theme: Option<String>,
+
+    /// Print the request that would be sent (method, URL, headers and body) without sending it\
+    /// 只输出会发送的请求(方法、URL、请求头和请求体)，不发送请求\
+    /// `long: --dry-run`
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// File storing ETag/Last-Modified from the previous run, replayed as conditional headers\
+    /// 保存上一次运行的 ETag/Last-Modified 的文件，再次运行时作为条件请求头发送\
+    /// `long: --validators`
+    #[clap(long, value_parser)]
+    pub validators: Option<String>,
+}
+
+// 输出到终端且没有指定 --max-diff-lines 时差异最多输出的行数
+const DEFAULT_MAX_DIFF_LINES: usize = 1000;
+
+#[derive(Parser, Debug, Clone)]
+pub struct RunArgs {
+    /// profile node name, repeat to run several profiles concurrently \
+    /// 要使用配置中的节点名称，可以指定多次同时运行多个 profile \
+    /// `short: -p ,long: --profile`
+    #[clap(
+        short,
+        long,
+        value_parser,
+        required_unless_present_any = ["all", "tag", "skip_tag", "url1"]
+    )]
+    pub profile: Vec<String>,
+
+    /// Compare this URL with URL2 directly without a config file, e.g. `xdiff run <url1> <url2> -e key=value`\
+    /// 不使用配置文件直接比较两个 URL，例如 `xdiff run <url1> <url2> -e key=value`
+    #[clap(
+        value_parser,
+        requires = "url2",
+        conflicts_with_all = ["profile", "all", "tag", "skip_tag", "config", "watch_config"]
+    )]
+    pub url1: Option<String>,
+
+    /// The URL to compare with URL1\
+    /// 和 URL1 比较的 URL
+    #[clap(value_parser)]
+    pub url2: Option<String>,
+
+    /// Run every profile in the config, then print a summary; exits non-zero if any profile differs or fails\
+    /// 运行配置中的所有 profile 并输出汇总，任何一个 profile 有差异或者出错时退出码不为 0\
+    /// `long: --all`
+    #[clap(long, conflicts_with = "profile")]
+    pub all: bool,
+
+    /// Only run profiles with this tag, can be repeated; selects from all profiles when `-p` is not given\
+    /// 只运行有这个标签的 profile，可以指定多次；没有指定 `-p` 时从所有 profile 中选择\
+    /// `long: --tag`
+    #[clap(long, value_parser)]
+    pub tag: Vec<String>,
+
+    /// Skip profiles with this tag, can be repeated\
+    /// 跳过有这个标签的 profile，可以指定多次\
+    /// `long: --skip-tag`
+    #[clap(long, value_parser)]
+    pub skip_tag: Vec<String>,
     /// Overrides args, Could be used to override the query, headers and boyd of the qeurst
     /// 覆盖参数，可用于覆盖请求的查询、header和body\
     /// 对于查询参数，请使用 `-e key=value`\
@@ -39,11 +230,486 @@ pub struct RunArgs {
     #[clap(short,long,value_parser=parse_key_val,number_of_values=1)]
     pub extar_params: Vec<KeyVal>,
 
+    /// Overrides args only for req1, same syntax as `-e`\
+    /// 只覆盖 req1 的参数，语法与 `-e` 相同\
+    /// `short: -1 ,long: --e1`
+    #[clap(short='1',long="e1",value_parser=parse_key_val,number_of_values=1)]
+    pub extra_params1: Vec<KeyVal>,
+
+    /// Overrides args only for req2, same syntax as `-e`\
+    /// 只覆盖 req2 的参数，语法与 `-e` 相同\
+    /// `short: -2 ,long: --e2`
+    #[clap(short='2',long="e2",value_parser=parse_key_val,number_of_values=1)]
+    pub extra_params2: Vec<KeyVal>,
+
+    /// Header for both requests in curl syntax, e.g. `-H "Authorization: Bearer x"`, same as `-e %key=value`\
+    /// 两个请求都使用的请求头，语法与 curl 相同，例如 `-H "Authorization: Bearer x"`，等同于 `-e %key=value`\
+    /// `short: -H ,long: --header`
+    #[clap(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header_arg, number_of_values = 1)]
+    pub header_params: Vec<KeyVal>,
+
+    /// Query param for both requests, e.g. `-q page=2`, same as `-e key=value`\
+    /// 两个请求都使用的查询参数，例如 `-q page=2`，等同于 `-e key=value`\
+    /// `short: -q ,long: --query`
+    #[clap(short = 'q', long = "query", value_name = "KEY=VALUE", value_parser = parse_query_arg, number_of_values = 1)]
+    pub query_params: Vec<KeyVal>,
+
+    /// Body field for both requests, e.g. `-d name=hello`, same as `-e @key=value`\
+    /// 两个请求都使用的请求体字段，例如 `-d name=hello`，等同于 `-e @key=value`\
+    /// `short: -d ,long: --body`
+    #[clap(short = 'd', long = "body", value_name = "KEY=VALUE", value_parser = parse_body_arg, number_of_values = 1)]
+    pub body_params: Vec<KeyVal>,
+
     /// COnfiguration to use \
     /// 要使用的配置文件\
     /// `short: -c ,long: --config`
     #[clap(short, long, value_parser)]
     pub config: Option<String>,
+
+    /// Max requests per second across all requests, 429 responses are retried after `Retry-After`\
+    /// 所有请求共享的每秒最大请求数，收到 429 时会按 `Retry-After` 退避重试\
+    /// `long: --rate`
+    #[clap(long, value_parser = parse_rate)]
+    pub rate: Option<f64>,
+
+    /// User-Agent sent with every request, overrides the profile's `user_agent`\
+    /// 所有请求使用的 User-Agent，会覆盖配置中的 `user_agent`\
+    /// `long: --user-agent`
+    #[clap(long, value_parser)]
+    pub user_agent: Option<String>,
+
+    /// Seed for template functions like `{{ fake_name() }}`, random when omitted\
+    /// 模板函数(例如 `{{ fake_name() }}`)使用的随机数种子，不设置时随机生成\
+    /// `long: --seed`
+    #[clap(long, value_parser)]
+    pub seed: Option<u64>,
+
+    /// How to compare responses: `text` diffs lines, `json` reports per-path changes of JSON bodies,
+    /// `json-patch` prints them as a JSON Patch (RFC 6902) document\
+    /// 响应的比较方式：`text` 按行比较，`json` 按字段输出 JSON 响应体的新增、删除和修改，
+    /// `json-patch` 输出 JSON Patch (RFC 6902) 文档\
+    /// `long: --diff-mode`
+    #[clap(long, value_enum, default_value_t = DiffMode::Text)]
+    pub diff_mode: DiffMode,
+
+    /// Compare only one part of the responses: `status`, `headers` or `body`\
+    /// 只比较响应的一部分：`status` 状态行，`headers` 响应头，`body` 响应体\
+    /// `long: --only`
+    #[clap(long, value_enum)]
+    pub only: Option<ResponsePart>,
+
+    /// Diff headers per name (added/removed/changed) instead of line by line, same as the profile's `semantic_headers`\
+    /// 按名称比较响应头(新增、删除和修改)而不是按行比较，和配置中的 `semantic_headers` 相同\
+    /// `long: --semantic-headers`
+    #[clap(long)]
+    pub semantic_headers: bool,
+
+    /// How text differences are displayed: `unified` or `side-by-side` columns\
+    /// 文本差异的显示方式：`unified` 上下排列，`side-by-side` 左右两栏\
+    /// `long: --view`
+    #[clap(long, value_enum, default_value_t = DiffView::Unified)]
+    pub view: DiffView,
+
+    /// Intra-line emphasis of the unified view: `line`, `word` or `char`\
+    /// 统一格式中行内差异的强调粒度：`line` 只标记整行，`word` 按单词，`char` 按字符\
+    /// `long: --granularity`
+    #[clap(long, value_enum, default_value_t = Granularity::Word)]
+    pub granularity: Granularity,
+
+    /// Context lines around each change, `full` shows the entire responses, overrides the profile's `context`\
+    /// 差异前后显示的上下文行数，`full` 显示完整的响应，会覆盖配置中的 `context`\
+    /// `long: --context`
+    #[clap(long, value_parser)]
+    pub context: Option<DiffContext>,
+
+    /// Output format: `text` is colored with line numbers, `patch` is a plain unified diff with `---`/`+++` headers,
+    /// `markdown` is a summary table plus fenced diff blocks, `json` is a machine-readable result,
+    /// `tap` is Test Anything Protocol\
+    /// 输出格式：`text` 带颜色和行号，`patch` 为不带颜色、带有 `---`/`+++` 文件头的标准 unified diff，
+    /// `markdown` 为汇总表加上 diff 代码块，`json` 为机器可读的结构化结果，`tap` 为 TAP 格式\
+    /// `long: --format`
+    #[clap(long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Write the output to this file instead of stdout, without colors,
+    /// the format is inferred from the extension (`.txt`, `.patch`, `.md`, `.json`, `.tap`) unless `--format` is given\
+    /// 把输出写入这个文件而不是标准输出(不带颜色)，没有指定 `--format` 时根据扩展名
+    /// (`.txt`、`.patch`、`.md`、`.json`、`.tap`)推断格式\
+    /// `short: -o ,long: --output`
+    #[clap(short, long, value_parser, conflicts_with = "output_dir")]
+    pub output: Option<String>,
+
+    /// Write one output file per profile into this directory, named `<profile>.<ext>`\
+    /// 在这个目录中为每个 profile 写入一个输出文件，文件名为 `<profile>.<扩展名>`\
+    /// `long: --output-dir`
+    #[clap(long, value_parser)]
+    pub output_dir: Option<String>,
+
+    /// Also write a report file, `junit` or `tap`, e.g. `--report junit results.xml`, can be repeated\
+    /// 额外写入报告文件，支持 `junit` 和 `tap`，例如 `--report junit results.xml`，可以指定多次\
+    /// `long: --report`
+    #[clap(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+    pub report: Vec<String>,
+
+    /// Pass when responses differ but their similarity ratio is at least this value, e.g. `0.98`\
+    /// 响应有差异但相似度不低于这个值(例如 `0.98`)时仍然算作通过\
+    /// `long: --min-similarity`
+    #[clap(long, value_parser = parse_ratio)]
+    pub min_similarity: Option<f32>,
+
+    /// Truncate diffs longer than this many lines, defaults to 1000 when printing to a terminal\
+    /// 差异超过这个行数时截断，输出到终端时默认为 1000 行，写入文件或管道时默认不截断\
+    /// `long: --max-diff-lines`
+    #[clap(long, value_parser)]
+    pub max_diff_lines: Option<usize>,
+
+    /// Never truncate diffs, however long they are\
+    /// 不截断差异，无论有多长\
+    /// `long: --full`
+    #[clap(long, conflicts_with = "max_diff_lines")]
+    pub full: bool,
+
+    /// Exit with 0 even when differences are found, by default a diff exits with 1\
+    /// 发现差异时仍然以 0 退出，默认发现差异时退出码为 1\
+    /// `long: --no-fail-on-diff`
+    #[clap(long)]
+    pub no_fail_on_diff: bool,
+
+    /// Print only a one-line verdict per profile instead of the diff, report files are still written\
+    /// 只为每个 profile 输出一行结论而不输出差异，报告文件仍然会写入\
+    /// `long: --quiet`
+    #[clap(long)]
+    pub quiet: bool,
+
+    /// When to use colors: `auto` (terminal only, honors `NO_COLOR`), `always` or `never`\
+    /// 是否输出颜色：`auto` 只在终端中输出并遵循 `NO_COLOR`，`always` 总是输出，`never` 不输出\
+    /// `long: --color`
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Trace each request and response to stderr: `-v` for the request line, headers and response metadata, `-vv` also for the request body and DNS timing\
+    /// 把每个请求和响应的细节输出到 stderr：`-v` 输出请求行、请求头和响应的元数据，`-vv` 还输出请求体和 DNS 耗时\
+    /// `short: -v ,long: --verbose`
+    #[clap(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Print the requests that would be sent (method, URL, headers and body) without sending them\
+    /// 只输出会发送的请求(方法、URL、请求头和请求体)，不发送请求\
+    /// `long: --dry-run`
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// With several profiles, print them in the order given instead of as soon as each one finishes\
+    /// 运行多个 profile 时按指定的顺序输出，而不是哪个先完成先输出\
+    /// `long: --ordered`
+    #[clap(long)]
+    pub ordered: bool,
+
+    /// Never pipe long output through `$PAGER`\
+    /// 输出超过一屏时不使用 `$PAGER` 分页\
+    /// `long: --no-pager`
+    #[clap(long)]
+    pub no_pager: bool,
+
+    /// Keep running and re-run the profiles every time the config file is saved\
+    /// 持续运行，每次保存配置文件后重新运行这些 profile\
+    /// `long: --watch-config`
+    #[clap(long, conflicts_with = "dry_run")]
+    pub watch_config: bool,
+
+    /// Save the raw responses (status, headers and body) into `DIR/<profile>/`, re-diff them later with `xdiff replay DIR`\
+    /// 把原始响应(状态、响应头和响应体)保存到 `DIR/<profile>/`，之后可以用 `xdiff replay DIR` 离线比较\
+    /// `long: --record`
+    #[clap(long, value_name = "DIR")]
+    pub record: Option<String>,
+
+    /// Syntax highlighting theme, a built-in name like `InspiredGitHub` or a `.tmTheme` file, overrides `settings.theme` in the config\
+    /// 语法高亮主题，内置主题的名称(例如 `InspiredGitHub`)或者 .tmTheme 文件，会覆盖配置中的 `settings.theme`\
+    /// `long: --theme`
+    #[clap(long, value_parser)]
+    pub theme: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ReviewArgs {
+    /// Profiles to review, all profiles when omitted\
+    /// 要查看的 profile，不指定时查看所有 profile\
+    /// `short: -p ,long: --profile`
+    #[clap(short, long, value_parser)]
+    pub profile: Vec<String>,
+
+    /// Configuration to review, expected differences are saved back into it\
+    /// 要查看的配置文件，预期中的差异会保存回这个文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SnapshotArgs {
+    /// Re-fetch the live side and overwrite the snapshot files, otherwise only compare against them\
+    /// 重新请求并覆盖快照文件，不指定时只和快照比较\
+    /// `long: --update`
+    #[clap(long)]
+    pub update: bool,
+
+    /// Profiles with a `snapshot`, all of them when omitted\
+    /// 配置了 `snapshot` 的 profile，不指定时为所有配置了快照的 profile\
+    /// `short: -p ,long: --profile`
+    #[clap(short, long, value_parser)]
+    pub profile: Vec<String>,
+
+    /// Configuration to use\
+    /// 要使用的配置文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ReplayArgs {
+    /// Directory passed to `run --record`\
+    /// `run --record` 使用的目录
+    pub dir: String,
+
+    /// Profiles to replay, all recorded profiles when omitted\
+    /// 要比较的 profile，不指定时为目录中录制的所有 profile\
+    /// `short: -p ,long: --profile`
+    #[clap(short, long, value_parser)]
+    pub profile: Vec<String>,
+
+    /// Configuration whose filters are applied, usually the one being tuned\
+    /// 使用这个配置文件中的过滤规则，通常是正在调整的配置\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+
+    /// Print only a one-line verdict per profile instead of the diff\
+    /// 只为每个 profile 输出一行结论而不输出差异\
+    /// `long: --quiet`
+    #[clap(long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ListArgs {
+    /// Configuration to list\
+    /// 要列出的配置文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct EditArgs {
+    /// Configuration to edit, created when it does not exist\
+    /// 要编辑的配置文件，不存在时自动创建\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ImportArgs {
+    /// Format of the file to import\
+    /// 要导入的文件格式
+    #[clap(value_enum)]
+    pub format: ImportFormat,
+    /// File to import\
+    /// 要导入的文件
+    pub file: String,
+    /// Append the profiles to this configuration instead of printing them\
+    /// 把 profile 追加到这个配置文件中，而不是输出\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+}
+
+/// 可以导入的文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// 浏览器开发者工具导出的 HTTP Archive
+    Har,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ExportArgs {
+    /// Format to export to\
+    /// 导出的格式
+    #[clap(value_enum)]
+    pub format: ExportFormat,
+
+    /// Profiles to export, all of them when omitted\
+    /// 要导出的 profile，不指定时导出所有的 profile\
+    /// `short: -p ,long: --profile`
+    #[clap(short, long, value_parser)]
+    pub profile: Vec<String>,
+
+    /// Configuration to export\
+    /// 要导出的配置文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+
+    /// Write the export to this file instead of stdout\
+    /// 写入这个文件而不是标准输出\
+    /// `short: -o ,long: --output`
+    #[clap(short, long, value_parser)]
+    pub output: Option<String>,
+}
+
+/// 可以导出的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Postman collection v2.1
+    Postman,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ValidateArgs {
+    /// Configuration to validate\
+    /// 要验证的配置文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ShowArgs {
+    /// Profile to show\
+    /// 要输出的 profile\
+    /// `short: -p ,long: --profile`
+    #[clap(short, long, value_parser)]
+    pub profile: String,
+
+    /// Configuration to use\
+    /// 要使用的配置文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+
+    /// Overrides args for both requests, same syntax as `run -e`\
+    /// 覆盖两个请求的参数，语法与 `run -e` 相同\
+    /// `short: -e ,long: --extra-params`
+    #[clap(short,long,value_parser=parse_key_val,number_of_values=1)]
+    pub extra_params: Vec<KeyVal>,
+
+    /// Overrides args only for req1\
+    /// 只覆盖 req1 的参数\
+    /// `short: -1 ,long: --e1`
+    #[clap(short='1',long="e1",value_parser=parse_key_val,number_of_values=1)]
+    pub extra_params1: Vec<KeyVal>,
+
+    /// Overrides args only for req2\
+    /// 只覆盖 req2 的参数\
+    /// `short: -2 ,long: --e2`
+    #[clap(short='2',long="e2",value_parser=parse_key_val,number_of_values=1)]
+    pub extra_params2: Vec<KeyVal>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct WatchArgs {
+    /// Profile to watch\
+    /// 要监视的 profile\
+    /// `short: -p ,long: --profile`
+    #[clap(short, long, value_parser)]
+    pub profile: String,
+
+    /// Configuration to use\
+    /// 要使用的配置文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+
+    /// Time between two runs, e.g. `500ms`, `30s`, `5m`, `1h`; a bare number is in seconds\
+    /// 两次运行之间的间隔，例如 `500ms`、`30s`、`5m`、`1h`，只有数字时单位为秒\
+    /// `long: --interval`
+    #[clap(long, value_parser = parse_duration, default_value = "60s")]
+    pub interval: Duration,
+
+    /// Shell command to run when the result changes, the new output is passed on stdin
+    /// and `XDIFF_PROFILE` / `XDIFF_VERDICT` are set\
+    /// 结果变化时执行的 shell 命令，新的输出通过标准输入传入，并设置 `XDIFF_PROFILE` 和 `XDIFF_VERDICT`\
+    /// `long: --on-change`
+    #[clap(long, value_parser)]
+    pub on_change: Option<String>,
+
+    /// Overrides args for both requests, same syntax as `run -e`\
+    /// 覆盖两个请求的参数，语法与 `run -e` 相同\
+    /// `short: -e ,long: --extra-params`
+    #[clap(short,long,value_parser=parse_key_val,number_of_values=1)]
+    pub extra_params: Vec<KeyVal>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ParseArgs {
+    /// Build the first request from a curl command instead of prompting for a URL\
+    /// 从 curl 命令生成第一个请求，不再输入 URL\
+    /// `long: --from-curl`
+    #[clap(long, value_parser)]
+    pub from_curl: Option<String>,
+
+    /// Add the generated profile to this configuration instead of printing it\
+    /// 把生成的 profile 加入这个配置文件，而不是输出\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ManArgs {
+    /// Write `xdiff.1`, `xreq.1` and one page per subcommand into this directory, otherwise print `xdiff.1`\
+    /// 把 `xdiff.1`、`xreq.1` 和每个子命令的手册写入这个目录，不指定时只输出 `xdiff.1`\
+    /// `long: --out-dir`
+    #[clap(long, value_parser)]
+    pub out_dir: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct FilesArgs {
+    /// The first file, its extension decides how it is parsed, e.g. `.json`, `.xml`, `.csv`\
+    /// 第一个文件，按扩展名解析，例如 `.json`、`.xml`、`.csv`
+    pub file1: String,
+
+    /// The second file\
+    /// 第二个文件
+    pub file2: String,
+
+    /// Apply the `res` rules (skip_body, mask...) of this profile\
+    /// 使用这个 profile 的 `res` 规则(skip_body、mask 等)\
+    /// `short: -p ,long: --profile`
+    #[clap(short, long, value_parser)]
+    pub profile: Option<String>,
+
+    /// Configuration containing the profile\
+    /// profile 所在的配置文件\
+    /// `short: -c ,long: --config`
+    #[clap(short, long, value_parser, requires = "profile")]
+    pub config: Option<String>,
+
+    /// How to compare JSON files: `text`, `json` or `json-patch`\
+    /// JSON 文件的比较方式：`text`、`json` 或 `json-patch`\
+    /// `long: --diff-mode`
+    #[clap(long, value_enum, default_value_t = DiffMode::Text)]
+    pub diff_mode: DiffMode,
+
+    /// How text differences are displayed: `unified` or `side-by-side`\
+    /// 文本差异的显示方式：`unified` 或 `side-by-side`\
+    /// `long: --view`
+    #[clap(long, value_enum, default_value_t = DiffView::Unified)]
+    pub view: DiffView,
+
+    /// Intra-line emphasis of the unified view: `line`, `word` or `char`\
+    /// 统一格式中行内差异的强调粒度：`line`、`word` 或 `char`\
+    /// `long: --granularity`
+    #[clap(long, value_enum, default_value_t = Granularity::Word)]
+    pub granularity: Granularity,
+
+    /// Context lines around each change, `full` shows the entire files\
+    /// 差异前后显示的上下文行数，`full` 显示完整的文件\
+    /// `long: --context`
+    #[clap(long, value_parser)]
+    pub context: Option<DiffContext>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -60,6 +726,46 @@ pub struct KeyVal {
     pub value: String,
 }
 
+fn parse_ratio(s: &str) -> Result<f32> {
+    match s.parse::<f32>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err(anyhow!(
+            "Ratio must be between 0 and 1`必须是 0 到 1 之间的数: {}",
+            s
+        )),
+    }
+}
+
+// 每秒请求数，必须能换算成请求间隔
+fn parse_rate(s: &str) -> Result<f64> {
+    let rate = s
+        .parse::<f64>()
+        .map_err(|_| anyhow!("Rate must be a positive number`速率必须是正数: {}", s))?;
+    crate::rate_interval(rate)?;
+    Ok(rate)
+}
+
+// 解析 `500ms`、`30s`、`5m`、`1h` 形式的时间间隔，只有数字时单位为秒
+fn parse_duration(s: &str) -> Result<Duration> {
+    let invalid = || anyhow!("Invalid interval`无效的时间间隔: {}", s);
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let n: u64 = s[..split].parse().map_err(|_| invalid())?;
+    let secs = |unit: u64| n.checked_mul(unit).map(Duration::from_secs);
+    let duration = match &s[split..] {
+        "ms" => Some(Duration::from_millis(n)),
+        "" | "s" => Some(Duration::from_secs(n)),
+        "m" => secs(60),
+        "h" => secs(3600),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    if duration.is_zero() {
+        return Err(invalid());
+    }
+    Ok(duration)
+}
+
 fn parse_key_val(s: &str) -> Result<KeyVal> {
     let mut parts = s.splitn(2, '=');
     let retrieve = |parts: Option<&str>| -> Result<String> {
@@ -85,6 +791,167 @@ fn parse_key_val(s: &str) -> Result<KeyVal> {
     })
 }
 
+// `-H` 的参数，和 curl 一样用冒号分隔名称和值
+fn parse_header_arg(s: &str) -> Result<KeyVal> {
+    let (key, value) = s.split_once(':').ok_or_else(|| {
+        anyhow!(
+            "Header must be `name: value``请求头的格式应为 name: value: {:?}",
+            s
+        )
+    })?;
+    new_key_val(KeyValType::Header, key, value, s)
+}
+
+// `-q` 的参数，`key=value`
+fn parse_query_arg(s: &str) -> Result<KeyVal> {
+    parse_field_arg(KeyValType::Query, s)
+}
+
+// `-d` 的参数，`key=value`
+fn parse_body_arg(s: &str) -> Result<KeyVal> {
+    parse_field_arg(KeyValType::Body, s)
+}
+
+// 和 `-e` 不同，不是合法 JSON 的值按字符串处理，`-d name=hello` 不需要写成 `-d 'name="hello"'`
+fn parse_field_arg(key_type: KeyValType, s: &str) -> Result<KeyVal> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid key value pair`无效的键值对: {:?}", s))?;
+    let mut kv = new_key_val(key_type, key, value, s)?;
+    if serde_json::from_str::<serde_json::Value>(&kv.value).is_err() {
+        kv.value = serde_json::Value::String(kv.value).to_string();
+    }
+    Ok(kv)
+}
+
+fn new_key_val(key_type: KeyValType, key: &str, value: &str, s: &str) -> Result<KeyVal> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(anyhow!("Key can not be empty`键不能为空: {:?}", s));
+    }
+    Ok(KeyVal {
+        key_type,
+        key: key.to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+// `-e`、`-H`、`-q`、`-d`、`--user-agent` 以及 `--seed` 合并成的覆盖参数
+fn extra_args(params: [&[KeyVal]; 4], seed: Option<u64>, user_agent: Option<&str>) -> ExtraArgs {
+    let mut args: ExtraArgs = params.concat().into();
+    // 一次运行中所有请求使用同一个种子，保证两边生成的数据一致
+    args.seed = Some(seed.unwrap_or_else(rand::random));
+    args.idempotency_key = Some(uuid::Uuid::new_v4().to_string());
+    if let Some(user_agent) = user_agent {
+        args.headers
+            .push(("user-agent".to_string(), user_agent.to_string()));
+    }
+    args
+}
+
+impl RunArgs {
+    /// 所有请求共用的覆盖参数，包含 `-e`、`-H`、`-q`、`-d`、`--user-agent` 以及 `--seed`
+    pub fn extra_args(&self) -> ExtraArgs {
+        let params = [
+            self.extar_params.as_slice(),
+            &self.header_params,
+            &self.query_params,
+            &self.body_params,
+        ];
+        extra_args(params, self.seed, self.user_agent.as_deref())
+    }
+
+    /// 需要写入的报告文件
+    pub fn report_files(&self) -> Result<Vec<(ReportFormat, String)>> {
+        self.report
+            .chunks(2)
+            .map(|pair| {
+                let format = ReportFormat::from_str(&pair[0], true).map_err(|_| {
+                    anyhow!("Unsupported report format`不支持的报告格式: {}", pair[0])
+                })?;
+                Ok((format, pair[1].clone()))
+            })
+            .collect()
+    }
+
+    /// 输出格式：`--format` 优先，其次根据 `--output` 的扩展名推断，默认为 `text`
+    pub fn output_format(&self) -> OutputFormat {
+        self.format
+            .or_else(|| self.output.as_deref().and_then(OutputFormat::from_path))
+            .unwrap_or_default()
+    }
+
+    /// 控制比较和输出的选项，录制目录为 `DIR/<profile>`
+    pub fn diff_options(&self, profile: &str) -> DiffOptions {
+        DiffOptions {
+            format: self.output_format(),
+            mode: self.diff_mode,
+            view: self.view,
+            granularity: self.granularity,
+            context: self.context,
+            min_similarity: self.min_similarity,
+            only: self.only,
+            semantic_headers: self.semantic_headers,
+            max_lines: match (self.full, self.max_diff_lines) {
+                (true, _) => None,
+                (false, Some(max)) => Some(max),
+                // 只有输出到终端时才默认截断，已有的脚本和 -o 文件仍然得到完整的差异
+                (false, None) => (self.output.is_none() && atty::is(atty::Stream::Stdout))
+                    .then_some(DEFAULT_MAX_DIFF_LINES),
+            },
+            record: self
+                .record
+                .as_ref()
+                .map(|dir| Path::new(dir).join(profile).display().to_string()),
+            labels: None,
+        }
+    }
+}
+
+impl XreqRunArgs {
+    /// 请求的覆盖参数，包含 `-e`、`-H`、`-q`、`-d`、`--user-agent` 以及 `--seed`
+    pub fn extra_args(&self) -> ExtraArgs {
+        let params = [
+            self.extar_params.as_slice(),
+            &self.header_params,
+            &self.query_params,
+            &self.body_params,
+        ];
+        extra_args(params, self.seed, self.user_agent.as_deref())
+    }
+}
+
+/// 生成 xdiff 和 xreq 以及它们的子命令的 man 手册；xreq 只支持 run 和 parse
+pub fn generate_man_pages(out_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(out_dir)?;
+    clap_mangen::generate_to(Args::command(), out_dir)?;
+    clap_mangen::generate_to(XreqArgs::command(), out_dir)?;
+    Ok(())
+}
+
+/// xdiff 的 man 手册，不包含子命令
+pub fn render_man_page(w: &mut dyn std::io::Write) -> Result<()> {
+    clap_mangen::Man::new(Args::command()).render(w)?;
+    Ok(())
+}
+
+impl WatchArgs {
+    /// 每次运行使用新的种子和 Idempotency-Key
+    pub fn extra_args(&self) -> ExtraArgs {
+        let mut args: ExtraArgs = self.extra_params.clone().into();
+        args.seed = Some(rand::random());
+        args.idempotency_key = Some(uuid::Uuid::new_v4().to_string());
+        args
+    }
+
+    pub fn diff_options(&self) -> DiffOptions {
+        DiffOptions {
+            max_lines: Some(DEFAULT_MAX_DIFF_LINES),
+            ..Default::default()
+        }
+    }
+}
+
 impl From<Vec<KeyVal>> for ExtraArgs {
     fn from(args: Vec<KeyVal>) -> Self {
         let mut headers = vec![];
@@ -103,6 +970,30 @@ impl From<Vec<KeyVal>> for ExtraArgs {
             headers,
             query,
             body,
+            seed: None,
+            idempotency_key: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_should_accept_units() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration(" 5m ").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn parse_duration_should_reject_invalid_values() {
+        for s in ["", "0", "0ms", "ms", "1.5s", "-1s", "10d", "5 m", "1sm"] {
+            assert!(parse_duration(s).is_err(), "{}", s);
         }
+        assert!(parse_duration(&format!("{}h", u64::MAX)).is_err());
     }
 }
@@ -0,0 +1,102 @@
+use super::{ResponseBody, ResponseParts};
+use crate::JsonPath;
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 比较之前对两个响应分别检查的断言，失败的断言和差异一起输出 \
+/// body 中的路径作用于过滤后的响应体
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Assertions {
+    // 允许的状态码，可以是一个或者多个
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        default,
+        deserialize_with = "one_or_many"
+    )]
+    pub status: Vec<u16>,
+    // 必须存在的响应头，值为 null 时只检查是否存在
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub headers: BTreeMap<String, Option<String>>,
+    // JSONPath 和期望的值，所有匹配的值都必须相等
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub body: BTreeMap<String, Value>,
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<u16>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(u16),
+        Many(Vec<u16>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(status) => vec![status],
+        OneOrMany::Many(status) => status,
+    })
+}
+
+impl Assertions {
+    pub(crate) fn validate(&self) -> Result<()> {
+        for path in self.body.keys() {
+            JsonPath::parse(path)?;
+        }
+        Ok(())
+    }
+
+    /// 检查一个响应，返回失败的断言
+    pub fn check(&self, name: &str, parts: &ResponseParts) -> Result<Vec<String>> {
+        let mut failures = vec![];
+        if !self.status.is_empty() && !self.status.contains(&parts.status_code) {
+            failures.push(format!(
+                "{}: status {} not in {:?}",
+                name, parts.status_code, self.status
+            ));
+        }
+        for (header, expected) in &self.headers {
+            let values: Vec<&str> = parts
+                .header_map
+                .get_all(header.as_str())
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .collect();
+            match expected {
+                _ if values.is_empty() => {
+                    failures.push(format!("{}: header {} is missing", name, header))
+                }
+                Some(expected) if !values.contains(&expected.as_str()) => failures.push(format!(
+                    "{}: header {} is {:?}, expected {:?}",
+                    name,
+                    header,
+                    values.join(", "),
+                    expected
+                )),
+                _ => {}
+            }
+        }
+        if self.body.is_empty() {
+            return Ok(failures);
+        }
+        let ResponseBody::Json(json) = &parts.body else {
+            failures.push(format!("{}: body is not JSON", name));
+            return Ok(failures);
+        };
+        for (path, expected) in &self.body {
+            let values = JsonPath::parse(path)?.select(json);
+            if values.is_empty() {
+                failures.push(format!("{}: {} not found", name, path));
+            }
+            for value in values.into_iter().filter(|v| *v != expected) {
+                failures.push(format!(
+                    "{}: {} is {}, expected {}",
+                    name, path, value, expected
+                ));
+            }
+        }
+        Ok(failures)
+    }
+}
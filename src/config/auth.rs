@@ -0,0 +1,490 @@
+use anyhow::{anyhow, Result};
+use md5::Md5;
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Method, Response, StatusCode, Url,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// 请求的认证方式，通过 `type` 区分
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+    /// RFC 7616 digest 认证，收到 401 质询后自动计算并重发请求
+    Digest { username: String, password: Secret },
+    /// API key，放在 header 或者 query 中
+    ApiKey {
+        #[serde(rename = "in")]
+        location: ApiKeyLocation,
+        name: String,
+        value: Secret,
+    },
+    /// 每次请求时签发一个 JWT，作为 Bearer token 发送
+    Jwt {
+        #[serde(default)]
+        algorithm: JwtAlgorithm,
+        // HS256 为密钥，RS256 为 PEM 格式的私钥
+        key: Secret,
+        // 自定义 claims，会自动加上 iat，设置了 expires_in 时加上 exp
+        #[serde(default)]
+        claims: serde_json::Map<String, serde_json::Value>,
+        // token 有效期(秒)
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        expires_in: Option<u64>,
+    },
+}
+
+/// JWT 签名算法
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JwtAlgorithm {
+    #[default]
+    HS256,
+    RS256,
+}
+
+/// API key 的位置
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+    Header,
+    Query,
+}
+
+/// 敏感值，可以直接写在配置中，也可以从环境变量、文件或系统钥匙串中读取 \
+/// `value: xxx` / `value: { env: API_KEY }` / `value: { file: ./key }` / `value: { keyring: { service: x, user: y } }` \
+/// 和 `curl -H @file` 一样，文件末尾的换行不属于值
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Secret {
+    Plain(String),
+    Env { env: String },
+    File { file: String },
+    Keyring { keyring: KeyringEntry },
+}
+
+/// 系统钥匙串中的条目
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct KeyringEntry {
+    pub service: String,
+    pub user: String,
+}
+
+impl Secret {
+    /// 读取真实的值
+    pub fn resolve(&self) -> Result<String> {
+        match self {
+            Secret::Plain(value) => Ok(value.clone()),
+            Secret::Env { env } => std::env::var(env)
+                .map_err(|_| anyhow!("Environment variable {} is not set`环境变量未设置", env)),
+            // `echo $KEY > key` 等方式写入的文件以换行结尾
+            Secret::File { file } => std::fs::read_to_string(file)
+                .map(|value| value.trim_end_matches(['\r', '\n']).to_string())
+                .map_err(|e| anyhow!("Failed to read {}`读取文件失败: {}", file, e)),
+            Secret::Keyring { keyring } => keyring::Entry::new(&keyring.service, &keyring.user)
+                .and_then(|entry| entry.get_password())
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to read keyring {}/{}`读取钥匙串失败: {}",
+                        keyring.service,
+                        keyring.user,
+                        e
+                    )
+                }),
+        }
+    }
+}
+
+impl Auth {
+    /// 在发送前把认证信息写入 headers 或 query
+    /// apply credentials that are known before sending
+    pub(crate) fn apply(
+        &self,
+        headers: &mut HeaderMap,
+        query: &mut serde_json::Value,
+    ) -> Result<()> {
+        match self {
+            Auth::ApiKey {
+                location,
+                name,
+                value,
+            } => {
+                let value = value.resolve()?;
+                match location {
+                    ApiKeyLocation::Header => {
+                        headers.insert(HeaderName::from_str(name)?, HeaderValue::from_str(&value)?);
+                    }
+                    ApiKeyLocation::Query => query[name] = serde_json::Value::String(value),
+                }
+            }
+            Auth::Jwt {
+                algorithm,
+                key,
+                claims,
+                expires_in,
+            } => {
+                let token = mint_jwt(*algorithm, &key.resolve()?, claims, *expires_in)?;
+                headers.insert(
+                    header::AUTHORIZATION,
+                    HeaderValue::from_str(&format!("Bearer {}", token))?,
+                );
+            }
+            Auth::Digest { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// 根据 401 响应中的质询生成 Authorization 头，不需要重发时返回 None
+    /// build the Authorization header from a 401 challenge, None if no retry is needed
+    pub(crate) fn challenge(
+        &self,
+        res: &Response,
+        method: &Method,
+        url: &Url,
+    ) -> Result<Option<HeaderValue>> {
+        if res.status() != StatusCode::UNAUTHORIZED {
+            return Ok(None);
+        }
+        match self {
+            Auth::ApiKey { .. } | Auth::Jwt { .. } => Ok(None),
+            Auth::Digest { username, password } => {
+                let challenge = res
+                    .headers()
+                    .get_all(header::WWW_AUTHENTICATE)
+                    .iter()
+                    .filter_map(|v| v.to_str().ok())
+                    .filter_map(DigestChallenge::parse)
+                    .find(|c| c.algorithm().is_some());
+                let Some(challenge) = challenge else {
+                    return Ok(None);
+                };
+                let password = password.resolve()?;
+                let value = challenge.authorization(username, &password, method, url)?;
+                Ok(Some(HeaderValue::from_str(&value)?))
+            }
+        }
+    }
+}
+
+// 签发 JWT，claims 中没有设置时自动加上 iat 和 exp
+fn mint_jwt(
+    algorithm: JwtAlgorithm,
+    key: &str,
+    claims: &serde_json::Map<String, serde_json::Value>,
+    expires_in: Option<u64>,
+) -> Result<String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut claims = claims.clone();
+    claims.entry("iat").or_insert_with(|| now.into());
+    if let Some(expires_in) = expires_in {
+        claims
+            .entry("exp")
+            .or_insert_with(|| (now + expires_in).into());
+    }
+    let (header, key) = match algorithm {
+        JwtAlgorithm::HS256 => (
+            jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+            jsonwebtoken::EncodingKey::from_secret(key.as_bytes()),
+        ),
+        JwtAlgorithm::RS256 => (
+            jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+            jsonwebtoken::EncodingKey::from_rsa_pem(key.as_bytes())?,
+        ),
+    };
+    Ok(jsonwebtoken::encode(&header, &claims, &key)?)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DigestAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    fn hash(&self, data: &str) -> String {
+        match self {
+            DigestAlgorithm::Md5 => format!("{:x}", Md5::digest(data.as_bytes())),
+            DigestAlgorithm::Sha256 => format!("{:x}", Sha256::digest(data.as_bytes())),
+        }
+    }
+}
+
+/// WWW-Authenticate 中的 Digest 质询参数
+#[derive(Debug)]
+struct DigestChallenge {
+    params: HashMap<String, String>,
+}
+
+impl DigestChallenge {
+    // 解析 `Digest realm="x", nonce="y", qop="auth"` 形式的质询
+    fn parse(value: &str) -> Option<Self> {
+        let (scheme, rest) = value.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+        let mut params = HashMap::new();
+        let mut rest = rest.trim();
+        while !rest.is_empty() {
+            let (key, after) = rest.split_once('=')?;
+            let after = after.trim_start();
+            let (value, after) = match after.strip_prefix('"') {
+                Some(quoted) => unquote(quoted)?,
+                None => {
+                    let (value, after) = after.split_once(',').unwrap_or((after, ""));
+                    (value.trim().to_string(), after)
+                }
+            };
+            params.insert(key.trim().to_ascii_lowercase(), value);
+            rest = after.trim_start_matches([',', ' ']);
+        }
+        Some(Self { params })
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.params.get(key).map(|v| v.as_str())
+    }
+
+    // 返回支持的算法以及是否为 -sess 变体
+    fn algorithm(&self) -> Option<(DigestAlgorithm, bool)> {
+        let algorithm = self.get("algorithm").unwrap_or("MD5").to_ascii_uppercase();
+        let (name, sess) = match algorithm.strip_suffix("-SESS") {
+            Some(name) => (name.to_string(), true),
+            None => (algorithm, false),
+        };
+        match name.as_str() {
+            "MD5" => Some((DigestAlgorithm::Md5, sess)),
+            "SHA-256" => Some((DigestAlgorithm::Sha256, sess)),
+            _ => None,
+        }
+    }
+
+    fn authorization(
+        &self,
+        username: &str,
+        password: &str,
+        method: &Method,
+        url: &Url,
+    ) -> Result<String> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+        let nonce = self.get("nonce").unwrap_or_default();
+        let cnonce = format!("{:x}", Md5::digest(format!("{}:{}", nonce, nanos)));
+        self.authorization_with(username, password, method, url, &cnonce[..16])
+    }
+
+    // cnonce 由调用方指定，方便使用 RFC 中的示例验证
+    fn authorization_with(
+        &self,
+        username: &str,
+        password: &str,
+        method: &Method,
+        url: &Url,
+        cnonce: &str,
+    ) -> Result<String> {
+        let (algorithm, sess) = self
+            .algorithm()
+            .ok_or_else(|| anyhow!("Unsupported digest algorithm`不支持的 digest 算法"))?;
+        let realm = self.get("realm").unwrap_or_default();
+        let nonce = self
+            .get("nonce")
+            .ok_or_else(|| anyhow!("Digest challenge without nonce`digest 质询中缺少 nonce"))?;
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let nc = "00000001";
+
+        let mut ha1 = algorithm.hash(&format!("{}:{}:{}", username, realm, password));
+        if sess {
+            ha1 = algorithm.hash(&format!("{}:{}:{}", ha1, nonce, cnonce));
+        }
+        let ha2 = algorithm.hash(&format!("{}:{}", method, uri));
+        // 只支持 qop=auth，服务端不要求 qop 时使用 RFC 2069 的兼容格式
+        let qop = self
+            .get("qop")
+            .and_then(|qop| qop.split(',').map(str::trim).find(|q| *q == "auth"));
+        let response = match qop {
+            Some(qop) => algorithm.hash(&format!(
+                "{}:{}:{}:{}:{}:{}",
+                ha1, nonce, nc, cnonce, qop, ha2
+            )),
+            None => algorithm.hash(&format!("{}:{}:{}", ha1, nonce, ha2)),
+        };
+
+        let mut value = format!(
+            r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", response="{}""#,
+            quote(username),
+            quote(realm),
+            quote(nonce),
+            quote(&uri),
+            response
+        );
+        if let Some(algorithm) = self.get("algorithm") {
+            value.push_str(&format!(", algorithm={}", algorithm));
+        }
+        if let Some(qop) = qop {
+            value.push_str(&format!(r#", qop={}, nc={}, cnonce="{}""#, qop, nc, cnonce));
+        }
+        if let Some(opaque) = self.get("opaque") {
+            value.push_str(&format!(r#", opaque="{}""#, quote(opaque)));
+        }
+        Ok(value)
+    }
+}
+
+// 读取 quoted-string 的内容(开头的引号已去掉)，`\` 转义下一个字符；返回内容和结束引号之后的部分
+fn unquote(quoted: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &quoted[i + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+// 写入 quoted-string 时转义引号和反斜杠
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 7616 3.9.1 中的示例
+    const RFC7616_CHALLENGE: &str = r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=ALGORITHM, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+    const RFC7616_CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn rfc7616_authorization(algorithm: &str) -> String {
+        let challenge =
+            DigestChallenge::parse(&RFC7616_CHALLENGE.replace("ALGORITHM", algorithm)).unwrap();
+        let url = Url::parse("http://www.example.org/dir/index.html").unwrap();
+        challenge
+            .authorization_with(
+                "Mufasa",
+                "Circle of Life",
+                &Method::GET,
+                &url,
+                RFC7616_CNONCE,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_should_read_quoted_and_token_params() {
+        let challenge =
+            DigestChallenge::parse(&RFC7616_CHALLENGE.replace("ALGORITHM", "MD5")).unwrap();
+        assert_eq!(challenge.get("realm"), Some("http-auth@example.org"));
+        assert_eq!(challenge.get("qop"), Some("auth, auth-int"));
+        assert_eq!(challenge.get("algorithm"), Some("MD5"));
+        assert_eq!(
+            challenge.get("opaque"),
+            Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS")
+        );
+    }
+
+    #[test]
+    fn parse_should_unescape_quoted_strings() {
+        let challenge =
+            DigestChallenge::parse(r#"Digest realm="say \"hi\", a\\b", nonce="n""#).unwrap();
+        assert_eq!(challenge.get("realm"), Some(r#"say "hi", a\b"#));
+        assert_eq!(challenge.get("nonce"), Some("n"));
+        assert_eq!(quote(r#"say "hi", a\b"#), r#"say \"hi\", a\\b"#);
+        assert!(DigestChallenge::parse(r#"Digest realm="open"#).is_none());
+    }
+
+    #[test]
+    fn parse_should_reject_other_schemes() {
+        assert!(DigestChallenge::parse(r#"Basic realm="x""#).is_none());
+        assert!(DigestChallenge::parse("Digest").is_none());
+    }
+
+    #[test]
+    fn algorithm_should_default_to_md5_and_detect_sess() {
+        let parse = |value: &str| DigestChallenge::parse(value).unwrap().algorithm();
+        assert!(matches!(
+            parse(r#"Digest nonce="n""#),
+            Some((DigestAlgorithm::Md5, false))
+        ));
+        assert!(matches!(
+            parse(r#"Digest nonce="n", algorithm=sha-256-sess"#),
+            Some((DigestAlgorithm::Sha256, true))
+        ));
+        assert!(parse(r#"Digest nonce="n", algorithm=SHA-512-256"#).is_none());
+    }
+
+    #[test]
+    fn authorization_should_match_rfc7616_md5() {
+        let value = rfc7616_authorization("MD5");
+        assert!(value.contains(r#"response="8ca523f5e9506fed4657c9700eebdbec""#));
+        assert!(value.contains(r#"uri="/dir/index.html""#));
+        assert!(value.contains(&format!(
+            r#"qop=auth, nc=00000001, cnonce="{}""#,
+            RFC7616_CNONCE
+        )));
+        assert!(value.contains(r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#));
+    }
+
+    #[test]
+    fn authorization_should_match_rfc7616_sha256() {
+        let value = rfc7616_authorization("SHA-256");
+        assert!(value.contains(
+            r#"response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#
+        ));
+        assert!(value.contains("algorithm=SHA-256"));
+    }
+
+    #[test]
+    fn authorization_should_match_rfc2617_example() {
+        let challenge = DigestChallenge::parse(
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        )
+        .unwrap();
+        let url = Url::parse("http://www.nowhere.org/dir/index.html").unwrap();
+        let value = challenge
+            .authorization_with("Mufasa", "Circle Of Life", &Method::GET, &url, "0a4f113b")
+            .unwrap();
+        assert!(value.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+    }
+
+    #[test]
+    fn authorization_should_include_the_query_in_uri() {
+        let challenge = DigestChallenge::parse(r#"Digest realm="r", nonce="n""#).unwrap();
+        let url = Url::parse("http://example.org/a?b=1").unwrap();
+        let value = challenge
+            .authorization_with("u", "p", &Method::GET, &url, "c")
+            .unwrap();
+        assert!(value.contains(r#"uri="/a?b=1""#));
+        // 没有 qop 时使用 RFC 2069 的格式
+        assert!(!value.contains("qop="));
+    }
+
+    #[test]
+    fn secret_file_should_not_include_the_trailing_newline() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"s3cr3t\r\n").unwrap();
+        let secret = Secret::File {
+            file: file.path().display().to_string(),
+        };
+        let value = secret.resolve().unwrap();
+        assert_eq!(value, "s3cr3t");
+
+        let auth = Auth::ApiKey {
+            location: ApiKeyLocation::Header,
+            name: "X-Api-Key".to_string(),
+            value: secret,
+        };
+        let mut headers = HeaderMap::new();
+        auth.apply(&mut headers, &mut serde_json::json!({}))
+            .unwrap();
+        assert_eq!(headers["x-api-key"], "s3cr3t");
+    }
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// 二进制响应的比较方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryMode {
+    /// 只比较大小和 sha256
+    #[default]
+    Summary,
+    /// 按 16 字节一行输出十六进制内容，差异可以定位到具体的偏移
+    Hexdump,
+}
+
+/// 是否为二进制的 content type
+pub(crate) fn is_binary(content_type: &str) -> bool {
+    let (kind, subtype) = content_type.split_once('/').unwrap_or((content_type, ""));
+    matches!(kind, "image" | "audio" | "video" | "font")
+        || matches!(
+            subtype,
+            "octet-stream" | "pdf" | "zip" | "gzip" | "x-tar" | "x-7z-compressed" | "wasm"
+        )
+}
+
+/// 按比较方式把二进制内容转换为文本
+pub(crate) fn format_binary(bytes: &[u8], mode: BinaryMode) -> String {
+    match mode {
+        BinaryMode::Summary => {
+            let hash = format!("{:x}", Sha256::digest(bytes));
+            format!(
+                "binary {} sha256 {}…\n",
+                human_size(bytes.len()),
+                &hash[..16]
+            )
+        }
+        BinaryMode::Hexdump => hexdump(bytes),
+    }
+}
+
+/// 格式化字节数，例如 `10.2KB`
+pub(crate) fn human_size(size: usize) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB"];
+    if size < 1024 {
+        return format!("{}B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
+
+fn hexdump(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|b| match b {
+                0x20..=0x7e => *b as char,
+                _ => '.',
+            })
+            .collect();
+        let _ = writeln!(output, "{:08x}  {:<47}  |{}|", i * 16, hex.join(" "), ascii);
+    }
+    output
+}
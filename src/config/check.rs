@@ -0,0 +1,338 @@
+use super::{DiffConfig, DiffProfile, RequestProfile};
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashMap, fmt, path::Path};
+
+/// 配置中的一个错误，line 和 column 从 1 开始
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    // 出错的顶层节点，例如 profile 的名称；YAML 语法错误时为 None
+    pub section: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.section {
+            Some(section) => write!(
+                f,
+                "{}:{}: `{}`: {}",
+                self.line, self.column, section, self.message
+            ),
+            None => write!(f, "{}:{}: {}", self.line, self.column, self.message),
+        }
+    }
+}
+
+impl DiffConfig {
+    /// 解析并验证配置而不发送请求，返回所有的错误而不是只返回第一个 \
+    /// 每个顶层节点单独解析，一个 profile 出错不影响检查其他的 profile
+    pub fn check_yaml(content: &str, base_dir: &Path) -> Vec<ConfigError> {
+        // 语法错误时无法继续检查
+        let document: Value = match serde_yaml::from_str(content) {
+            Ok(document) => document,
+            Err(e) => {
+                let (line, column) = e.location().map_or((1, 1), |l| (l.line(), l.column()));
+                return vec![ConfigError {
+                    section: None,
+                    line,
+                    column,
+                    message: message(&e),
+                }];
+            }
+        };
+        if !matches!(document, Value::Mapping(_) | Value::Null) {
+            return vec![ConfigError {
+                section: None,
+                line: 1,
+                column: 1,
+                message: "config must be a mapping of profiles`配置必须是 profile 的映射"
+                    .to_string(),
+            }];
+        }
+
+        let mut errors = vec![];
+        for (key, start, text) in sections(content) {
+            let error = |line: usize, column: usize, message: String| ConfigError {
+                section: Some(key.clone()),
+                line,
+                column,
+                message,
+            };
+            let parsed = match key.as_str() {
+                "settings" => serde_yaml::from_str::<DiffConfig>(&text),
+                _ => {
+                    serde_yaml::from_str::<HashMap<String, DiffProfile>>(&text).map(DiffConfig::new)
+                }
+            };
+            let mut config = match parsed {
+                Ok(config) => config,
+                // 引用了其他节点中定义的锚点，只能从整个文档中解析，无法定位到具体的行
+                Err(e) if e.to_string().contains("unknown anchor") => {
+                    let mut section = Mapping::new();
+                    let value = document.get(&key).cloned().unwrap_or_default();
+                    section.insert(Value::String(key.clone()), value);
+                    match serde_yaml::from_value::<DiffConfig>(Value::Mapping(section)) {
+                        Ok(config) => config,
+                        Err(e) => {
+                            errors.push(error(start + 1, 1, message(&e)));
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let (line, column) = e.location().map_or((1, 1), |l| (l.line(), l.column()));
+                    // 位置是相对于这个节点的
+                    errors.push(error(start + line, column, message(&e)));
+                    continue;
+                }
+            };
+            // 每个出错的字段单独报告，位置是字段在节点中的键
+            let mut fields = vec![];
+            if let Some(colors) = &config.settings.colors {
+                if let Err(e) = colors.validate() {
+                    fields.push((vec![Key::Name("colors")], e));
+                }
+            }
+            for profile in config.profiles.values_mut() {
+                fields.extend(profile_errors(profile, base_dir));
+            }
+            for (path, e) in fields {
+                let (line, column) = locate(&text, &path);
+                let field = path.iter().fold(String::new(), |field, key| match key {
+                    Key::Name(_) if !field.is_empty() => format!("{}.{}", field, key),
+                    _ => format!("{}{}", field, key),
+                });
+                errors.push(error(
+                    start + line + 1,
+                    column,
+                    format!("{}: {:#}", field, e),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// 字段的键路径中的一段：映射的键或者列表的下标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key<'a> {
+    Name(&'a str),
+    Index(usize),
+}
+
+impl fmt::Display for Key<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Name(name) => write!(f, "{}", name),
+            Key::Index(i) => write!(f, "[{}]", i),
+        }
+    }
+}
+
+// 逐个字段检查 profile：加载引用的文件并校验，返回出错字段的键路径和错误
+fn profile_errors<'a>(
+    profile: &mut DiffProfile,
+    base_dir: &Path,
+) -> Vec<(Vec<Key<'a>>, anyhow::Error)> {
+    let mut errors = vec![];
+    let requests = [
+        (vec![Key::Name("req1")], &mut profile.req1),
+        (vec![Key::Name("req2")], &mut profile.req2),
+    ]
+    .into_iter()
+    .chain(
+        profile
+            .reqs
+            .iter_mut()
+            .enumerate()
+            .map(|(i, req)| (vec![Key::Name("reqs"), Key::Index(i)], req)),
+    );
+    for (path, req) in requests {
+        let field = |name| [path.as_slice(), &[Key::Name(name)]].concat();
+        for (name, e) in request_errors(req, base_dir) {
+            errors.push((field(name), e));
+        }
+    }
+    for (name, e) in profile.res.field_errors() {
+        errors.push((vec![Key::Name("res"), Key::Name(name)], e));
+    }
+    if let Some(assert) = &profile.assert {
+        if let Err(e) = assert.validate() {
+            errors.push((vec![Key::Name("assert")], e));
+        }
+    }
+    for (i, rule) in profile.suppress.iter().enumerate() {
+        if let Err(e) = rule.validate() {
+            errors.push((vec![Key::Name("suppress"), Key::Index(i)], e));
+        }
+    }
+    errors
+}
+
+// 和 RequestProfile::load_files 以及 validate 相同的检查，按字段返回错误
+fn request_errors(req: &mut RequestProfile, base_dir: &Path) -> Vec<(&'static str, anyhow::Error)> {
+    let mut errors = vec![];
+    if let Some(protobuf) = req.protobuf.as_mut() {
+        if let Err(e) = protobuf.load(base_dir) {
+            errors.push(("protobuf", e));
+        }
+    }
+    if let Err(e) = req.validate_params() {
+        errors.push(("params", e));
+    }
+    match req.load_body_file(base_dir) {
+        Ok(()) => {
+            if let Err(e) = req.validate_body() {
+                errors.push(("body", e));
+            }
+        }
+        Err(e) => errors.push(("body_file", e)),
+    }
+    errors
+}
+
+// 在节点的文本中按键路径查找字段，返回行的下标(从 0 开始)和列(从 1 开始) \
+// 只识别块格式的 YAML，找不到时(例如使用了 `{}` 或 `[]` 的流格式)返回最后找到的上级字段的位置
+fn locate(text: &str, path: &[Key]) -> (usize, usize) {
+    let lines: Vec<&str> = text.lines().collect();
+    // 当前字段所在的行和列，列表项 `- key: value` 的第一个键和 `-` 在同一行
+    let (mut line, mut column) = (0, 0);
+    let mut inline = None;
+    for key in path {
+        let mut children = vec![];
+        if let Some(column) = inline {
+            children.push((line, column));
+        }
+        for (i, l) in lines.iter().enumerate().skip(line + 1) {
+            let trimmed = l.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = l.len() - trimmed.len();
+            // 列表可以和它的键缩进相同
+            let sequence = inline.is_none() && indent == column && trimmed.starts_with('-');
+            if indent <= column && !sequence {
+                break;
+            }
+            children.push((i, indent));
+        }
+        // 只在直接的子字段中查找，不进入更深的层级
+        let Some(&(_, indent)) = children.first() else {
+            break;
+        };
+        let mut children = children.into_iter().filter(|&(_, c)| c == indent);
+        let found = match key {
+            Key::Name(name) => children
+                .find(|&(i, c)| key_name(&lines[i][c..]) == Some(name))
+                .map(|(i, c)| (i, c, None)),
+            Key::Index(n) => children
+                .filter(|&(i, c)| lines[i][c..].starts_with('-'))
+                .nth(*n)
+                .map(|(i, c)| {
+                    let rest = &lines[i][c + 1..];
+                    let content = rest.trim_start();
+                    let first = (!content.is_empty() && !content.starts_with('#'))
+                        .then(|| c + 1 + rest.len() - content.len());
+                    (i, c, first)
+                }),
+        };
+        let Some((i, c, first)) = found else {
+            break;
+        };
+        (line, column, inline) = (i, c, first);
+    }
+    (line, column + 1)
+}
+
+// `key: value` 中的键，去掉引号
+fn key_name(text: &str) -> Option<&str> {
+    let (key, _) = text.split_once(':')?;
+    Some(key.trim().trim_matches(['"', '\'']))
+}
+
+// 去掉错误信息中的位置，位置单独输出，并且按节点解析时是相对于节点的
+fn message(e: &serde_yaml::Error) -> String {
+    let message = e.to_string();
+    match e.location() {
+        Some(l) => message.replace(&format!(" at line {} column {}", l.line(), l.column()), ""),
+        None => message,
+    }
+}
+
+// 按顶层的键把配置拆分为多个节点，返回键、起始行的下标(从 0 开始)和节点的文本
+pub(crate) fn sections(content: &str) -> Vec<(String, usize, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let starts: Vec<(usize, String)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let top_level = !line.starts_with([' ', '\t', '#', '-', '.']) && line.contains(':');
+            let key = line.split(':').next()?.trim().trim_matches(['"', '\'']);
+            top_level.then(|| (i, key.to_string()))
+        })
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, (start, key))| {
+            let end = starts.get(n + 1).map_or(lines.len(), |(next, _)| *next);
+            (key.clone(), *start, lines[*start..end].join("\n"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "todo:
+  req1:
+    url: https://example.com/a
+  reqs:
+    - url: https://example.com/b
+    -
+      url: https://example.com/c
+      params: x
+  suppress:
+  - path: $.id
+  - hunk: '('
+  req2: {url: https://example.com/d, params: 1}";
+
+    #[test]
+    fn locate_should_find_nested_keys_and_list_items() {
+        let name = Key::Name;
+        assert_eq!(locate(CONFIG, &[name("req1"), name("url")]), (2, 5));
+        assert_eq!(locate(CONFIG, &[name("reqs"), Key::Index(0)]), (4, 5));
+        assert_eq!(
+            locate(CONFIG, &[name("reqs"), Key::Index(0), name("url")]),
+            (4, 7)
+        );
+        assert_eq!(
+            locate(CONFIG, &[name("reqs"), Key::Index(1), name("params")]),
+            (7, 7)
+        );
+        assert_eq!(locate(CONFIG, &[name("suppress"), Key::Index(1)]), (10, 3));
+        // 流格式中的字段定位到上级字段
+        assert_eq!(locate(CONFIG, &[name("req2"), name("params")]), (11, 3));
+        assert_eq!(locate(CONFIG, &[name("res"), name("mask")]), (0, 1));
+    }
+
+    #[test]
+    fn check_yaml_should_report_every_invalid_field() {
+        let errors = DiffConfig::check_yaml(CONFIG, Path::new("."));
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (e.line, e.column, e.message.split(':').next().unwrap()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (12, 3, "req2.params"),
+                (8, 7, "reqs[1].params"),
+                (11, 3, "suppress[1]"),
+            ]
+        );
+    }
+}
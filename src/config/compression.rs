@@ -0,0 +1,46 @@
+use anyhow::{anyhow, Result};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use reqwest::{header, header::HeaderMap};
+use std::io::Read;
+
+/// 响应的 Content-Encoding，没有压缩或者为 identity 时返回 None
+pub(crate) fn content_encoding(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty() && v != "identity")
+}
+
+/// 按 Content-Encoding 包装解压的 reader，多个编码时按相反的顺序解压
+pub(crate) fn decoder<'a>(
+    encoding: &str,
+    reader: Box<dyn Read + 'a>,
+) -> Result<Box<dyn Read + 'a>> {
+    let mut reader = reader;
+    for encoding in encoding.rsplit(',').map(str::trim) {
+        reader = match encoding {
+            "gzip" | "x-gzip" => Box::new(GzDecoder::new(reader)),
+            "deflate" => Box::new(ZlibDecoder::new(reader)),
+            "br" => Box::new(brotli::Decompressor::new(reader, 4096)),
+            "identity" => reader,
+            _ => {
+                return Err(anyhow!(
+                    "Unsupported Content-Encoding`不支持的压缩格式: {}",
+                    encoding
+                ))
+            }
+        };
+    }
+    Ok(reader)
+}
+
+/// 去掉因为压缩而不同的响应头
+pub(crate) fn strip_compression_headers(headers: &str) -> String {
+    headers
+        .split_inclusive('\n')
+        .filter(|line| {
+            !line.starts_with("content-encoding:") && !line.starts_with("content-length:")
+        })
+        .collect()
+}
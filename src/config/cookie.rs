@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+/// set-cookie 响应头的比较配置 \
+/// 设置后每个 cookie 被解析为名称、值和属性，属性按名称排序，cookie 按名称排序
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CookieProfile {
+    // 不比较的属性，不区分大小写
+    #[serde(default = "default_ignore_attributes")]
+    pub ignore_attributes: Vec<String>,
+    // 只比较名称、不比较值的 cookie，例如 session
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub ignore_values: Vec<String>,
+}
+
+fn default_ignore_attributes() -> Vec<String> {
+    vec!["expires".to_string(), "max-age".to_string()]
+}
+
+impl Default for CookieProfile {
+    fn default() -> Self {
+        Self {
+            ignore_attributes: default_ignore_attributes(),
+            ignore_values: vec![],
+        }
+    }
+}
+
+impl CookieProfile {
+    /// 按配置规范化一个 set-cookie 的值，返回 cookie 名称和规范化后的文本
+    pub(crate) fn canonicalize(&self, cookie: &str) -> (String, String) {
+        let mut parts = cookie.split(';').map(str::trim);
+        let (name, value) = parts
+            .next()
+            .unwrap_or_default()
+            .split_once('=')
+            .unwrap_or((cookie, ""));
+        let value = match self.ignore_values.iter().any(|n| n == name) {
+            true => "<ignored>",
+            false => value,
+        };
+        let mut attrs: Vec<String> = parts
+            .filter(|attr| !attr.is_empty())
+            .filter_map(|attr| {
+                let (key, value) = attr.split_once('=').unwrap_or((attr, ""));
+                let key = key.trim().to_ascii_lowercase();
+                let ignored = self
+                    .ignore_attributes
+                    .iter()
+                    .any(|a| a.eq_ignore_ascii_case(&key));
+                match (ignored, value) {
+                    (true, _) => None,
+                    (false, "") => Some(key),
+                    (false, value) => Some(format!("{}={}", key, value.trim())),
+                }
+            })
+            .collect();
+        attrs.sort();
+        let text = std::iter::once(format!("{}={}", name, value))
+            .chain(attrs)
+            .collect::<Vec<_>>()
+            .join("; ");
+        (name.to_string(), text)
+    }
+}
@@ -0,0 +1,404 @@
+use super::{Auth, RequestProfile, Secret};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Method,
+};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub(super) const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+// 带参数的短选项，参数可以紧跟在选项后面，例如 `-XPOST`
+const SHORT_WITH_VALUE: [&str; 11] = [
+    "-X", "-H", "-d", "-u", "-A", "-b", "-e", "-o", "-m", "-w", "-x",
+];
+
+// 不影响请求内容的选项
+const IGNORED: [&str; 20] = [
+    "--compressed",
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-k",
+    "--insecure",
+    "-L",
+    "--location",
+    "-i",
+    "--include",
+    "-v",
+    "--verbose",
+    "-f",
+    "--fail",
+    "-g",
+    "--globoff",
+    "--http1.1",
+    "--http2",
+    "--basic",
+];
+
+// 不影响请求内容、带一个参数的选项
+const IGNORED_WITH_VALUE: [&str; 13] = [
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--retry",
+    "-w",
+    "--write-out",
+    "-x",
+    "--proxy",
+    "--cacert",
+    "--resolve",
+    "--max-redirs",
+];
+
+impl RequestProfile {
+    /// 从 curl 命令解析请求，支持浏览器 "Copy as cURL" 中常用的选项 \
+    /// 无法表示的选项(例如 `-F`、`@file`)返回错误，而不是静默地忽略
+    pub fn from_curl(command: &str) -> Result<Self> {
+        let words = shell_words::split(command)
+            .map_err(|e| anyhow!("invalid curl command`无效的 curl 命令: {}", e))?;
+        let mut words = words.into_iter();
+        if !words
+            .next()
+            .is_some_and(|w| w == "curl" || w.ends_with("/curl"))
+        {
+            return Err(anyhow!(
+                "curl command must start with curl`curl 命令必须以 curl 开头"
+            ));
+        }
+
+        let mut url = None;
+        let mut method = None;
+        let mut headers = HeaderMap::new();
+        let mut data: Vec<String> = vec![];
+        let mut is_json = false;
+        let mut get = false;
+        let mut user = None;
+        let mut digest = false;
+        let mut user_agent = None;
+        while let Some(word) = words.next() {
+            let (flag, inline) = match word.get(..2) {
+                Some(short) if word.len() > 2 && SHORT_WITH_VALUE.contains(&short) => {
+                    (short.to_string(), Some(word[2..].to_string()))
+                }
+                _ => (word.clone(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| words.next())
+                    .ok_or_else(|| anyhow!("{} requires a value`缺少参数: {}", flag, flag))
+            };
+            match flag.as_str() {
+                "-X" | "--request" => method = Some(Method::from_str(&value()?.to_uppercase())?),
+                "-H" | "--header" => {
+                    let (name, value) = parse_header(&value()?)?;
+                    headers.append(name, value);
+                }
+                "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => {
+                    let value = value()?;
+                    if value.starts_with('@') && flag != "--data-raw" {
+                        return Err(anyhow!(
+                            "Reading data from a file is not supported`不支持从文件读取数据: {}",
+                            value
+                        ));
+                    }
+                    data.push(value);
+                }
+                "--data-urlencode" => {
+                    let value = value()?;
+                    // `name=content` 只编码 content，`=content` 和 `content` 都只输出编码后的 content
+                    let encoded = match value.split_once('=') {
+                        Some((name, content)) if !name.is_empty() => {
+                            serde_urlencoded::to_string([(name, content)])?
+                        }
+                        Some((_, content)) => serde_urlencoded::to_string([("", content)])?
+                            .trim_start_matches('=')
+                            .to_string(),
+                        None => serde_urlencoded::to_string([("", value.as_str())])?
+                            .trim_start_matches('=')
+                            .to_string(),
+                    };
+                    data.push(encoded);
+                }
+                "--json" => {
+                    data.push(value()?);
+                    is_json = true;
+                }
+                "-G" | "--get" => get = true,
+                "-u" | "--user" => user = Some(value()?),
+                "--digest" => digest = true,
+                "-A" | "--user-agent" => user_agent = Some(value()?),
+                "-b" | "--cookie" => {
+                    let cookie = value()?;
+                    if !cookie.contains('=') {
+                        return Err(anyhow!(
+                            "Cookie files are not supported`不支持 cookie 文件: {}",
+                            cookie
+                        ));
+                    }
+                    headers.append(header::COOKIE, HeaderValue::from_str(&cookie)?);
+                }
+                "-e" | "--referer" => {
+                    headers.insert(header::REFERER, HeaderValue::from_str(&value()?)?);
+                }
+                "--url" => url = Some(value()?),
+                flag if IGNORED.contains(&flag) => {}
+                flag if IGNORED_WITH_VALUE.contains(&flag) => {
+                    value()?;
+                }
+                // 合并在一起的短选项，例如 `-sSL`
+                flag if flag.starts_with('-')
+                    && !flag.starts_with("--")
+                    && flag.len() > 2
+                    && flag[1..]
+                        .chars()
+                        .all(|c| IGNORED.contains(&format!("-{}", c).as_str())) => {}
+                flag if flag.starts_with('-') && flag.len() > 1 => {
+                    return Err(anyhow!(
+                        "Unsupported curl option`不支持的 curl 选项: {}",
+                        flag
+                    ))
+                }
+                _ if url.is_some() => {
+                    return Err(anyhow!(
+                        "Only one URL is supported`只支持一个 URL: {}",
+                        word
+                    ))
+                }
+                _ => url = Some(word),
+            }
+        }
+
+        // 和 curl 一样，没有协议时默认为 http
+        let url = url.ok_or_else(|| anyhow!("No URL in curl command`curl 命令中没有 URL"))?;
+        let mut profile: RequestProfile = match url.contains("://") {
+            true => url.parse()?,
+            false => format!("http://{}", url).parse()?,
+        };
+        profile.method = match method {
+            Some(method) => method,
+            None if !data.is_empty() && !get => Method::POST,
+            None => Method::GET,
+        };
+        profile.user_agent = user_agent;
+        if is_json {
+            let json = HeaderValue::from_static("application/json");
+            headers.entry(header::CONTENT_TYPE).or_insert(json.clone());
+            headers.entry(header::ACCEPT).or_insert(json);
+        }
+
+        // 多个 -d 和 curl 一样用 & 连接
+        let data = data.join("&");
+        if get {
+            let params = profile.params.get_or_insert_with(|| json!({}));
+            merge_form(params, &data)?;
+        } else if !data.is_empty() {
+            let content_type = headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| {
+                    v.split(';')
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .to_lowercase()
+                });
+            profile.body = Some(match content_type.as_deref() {
+                Some(ct) if ct.contains("json") => serde_json::from_str(&data)
+                    .map_err(|e| anyhow!("invalid JSON body`无效的 JSON: {}", e))?,
+                Some(FORM_CONTENT_TYPE) => form_body(&data)?,
+                Some(ct) => {
+                    return Err(anyhow!(
+                        "Unsupported content type for the body`不支持的 body 类型: {}",
+                        ct
+                    ))
+                }
+                // 没有 content-type 时 curl 按表单发送，不过 JSON 更可能是本来的意图
+                None => match serde_json::from_str(&data) {
+                    Ok(json) => json,
+                    Err(_) => {
+                        headers.insert(
+                            header::CONTENT_TYPE,
+                            HeaderValue::from_static(FORM_CONTENT_TYPE),
+                        );
+                        form_body(&data)?
+                    }
+                },
+            });
+        }
+
+        if let Some(user) = user {
+            let (username, password) = user
+                .split_once(':')
+                .ok_or_else(|| anyhow!("-u must be user:password`-u 的格式应为 user:password"))?;
+            match digest {
+                true => {
+                    profile.auth = Some(Auth::Digest {
+                        username: username.to_string(),
+                        password: Secret::Plain(password.to_string()),
+                    })
+                }
+                false => {
+                    let token = STANDARD.encode(format!("{}:{}", username, password));
+                    headers.insert(
+                        header::AUTHORIZATION,
+                        HeaderValue::from_str(&format!("Basic {}", token))?,
+                    );
+                }
+            }
+        }
+        profile.headers = headers;
+        Ok(profile)
+    }
+}
+
+fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue)> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid header`无效的 header: {}", header))?;
+    Ok((
+        HeaderName::from_str(name.trim())?,
+        HeaderValue::from_str(value.trim())?,
+    ))
+}
+
+pub(super) fn form_body(data: &str) -> Result<Value> {
+    let mut body = json!({});
+    merge_form(&mut body, data)?;
+    Ok(body)
+}
+
+// 把表单中的字段合并到 JSON 对象中，同名的字段合并为数组
+fn merge_form(target: &mut Value, data: &str) -> Result<()> {
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(data)
+        .map_err(|e| anyhow!("invalid form data`无效的表单数据: {}", e))?;
+    let map = target
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("params must be an object`params 必须是对象"))?;
+    for (k, v) in pairs {
+        let v = Value::String(v);
+        match map.get_mut(&k) {
+            Some(Value::Array(values)) => values.push(v),
+            Some(value) => *value = Value::Array(vec![value.clone(), v]),
+            None => {
+                map.insert(k, v);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(command: &str) -> RequestProfile {
+        RequestProfile::from_curl(command).unwrap()
+    }
+
+    #[test]
+    fn short_option_should_accept_inline_value() {
+        let profile = parse("curl -XPOST -HAccept:text/plain https://example.com/a");
+        assert_eq!(profile.method, Method::POST);
+        assert_eq!(profile.headers[header::ACCEPT], "text/plain");
+        assert_eq!(profile.url.as_str(), "https://example.com/a");
+    }
+
+    #[test]
+    fn combined_ignored_flags_should_be_skipped() {
+        let profile = parse("curl -sSL --compressed -o out.json https://example.com/");
+        assert_eq!(profile.method, Method::GET);
+        assert!(profile.body.is_none());
+    }
+
+    #[test]
+    fn combined_flags_with_unknown_option_should_fail() {
+        let err = RequestProfile::from_curl("curl -sF a=b https://example.com/").unwrap_err();
+        assert!(err.to_string().contains("-sF"));
+    }
+
+    #[test]
+    fn data_should_default_to_post_and_detect_json() {
+        let profile = parse(r#"curl https://example.com/ -d '{"a": 1}'"#);
+        assert_eq!(profile.method, Method::POST);
+        assert_eq!(profile.body, Some(json!({"a": 1})));
+        assert!(profile.headers.get(header::CONTENT_TYPE).is_none());
+    }
+
+    #[test]
+    fn data_should_fall_back_to_form() {
+        let profile = parse("curl https://example.com/ -d a=1 -d a=2 -d b=x%20y");
+        assert_eq!(profile.body, Some(json!({"a": ["1", "2"], "b": "x y"})));
+        assert_eq!(profile.headers[header::CONTENT_TYPE], FORM_CONTENT_TYPE);
+    }
+
+    #[test]
+    fn data_urlencode_should_encode_only_the_content() {
+        let profile = parse(
+            "curl https://example.com/ --data-urlencode 'q=a b&c' --data-urlencode '=x=y' -d n=1",
+        );
+        assert_eq!(
+            profile.body,
+            Some(json!({"q": "a b&c", "x=y": "", "n": "1"}))
+        );
+    }
+
+    #[test]
+    fn get_should_move_data_into_query() {
+        let profile =
+            parse("curl -G https://example.com/search -d q=rust --data-urlencode 'tag=a b'");
+        assert_eq!(profile.method, Method::GET);
+        assert!(profile.body.is_none());
+        let params = profile.params.unwrap();
+        assert_eq!(params["q"], "rust");
+        assert_eq!(params["tag"], "a b");
+    }
+
+    #[test]
+    fn json_should_set_content_type_and_accept() {
+        let profile = parse(r#"curl --json '{"a": [1]}' example.com/api"#);
+        assert_eq!(profile.url.as_str(), "http://example.com/api");
+        assert_eq!(profile.method, Method::POST);
+        assert_eq!(profile.body, Some(json!({"a": [1]})));
+        assert_eq!(profile.headers[header::CONTENT_TYPE], "application/json");
+        assert_eq!(profile.headers[header::ACCEPT], "application/json");
+    }
+
+    #[test]
+    fn user_should_become_basic_or_digest_auth() {
+        let profile = parse("curl -u alice:secret https://example.com/");
+        assert_eq!(
+            profile.headers[header::AUTHORIZATION],
+            "Basic YWxpY2U6c2VjcmV0"
+        );
+        let profile = parse("curl --digest -u alice:secret https://example.com/");
+        assert!(profile.headers.get(header::AUTHORIZATION).is_none());
+        assert_eq!(
+            profile.auth,
+            Some(Auth::Digest {
+                username: "alice".into(),
+                password: Secret::Plain("secret".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn unsupported_input_should_fail() {
+        for command in [
+            "wget https://example.com/",
+            "curl -d @body.json https://example.com/",
+            "curl -b cookies.txt https://example.com/",
+            "curl https://a.example.com/ https://b.example.com/",
+            "curl -X",
+            "curl -s",
+        ] {
+            assert!(RequestProfile::from_curl(command).is_err(), "{}", command);
+        }
+    }
+}
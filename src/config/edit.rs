@@ -0,0 +1,128 @@
+use super::DiffProfile;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::{fs, path::Path};
+
+// 配置中不是 profile 的顶层节点
+const RESERVED_KEYS: [&str; 1] = ["settings"];
+
+/// 以 YAML 文档的形式编辑配置文件 \
+/// 只有新增或修改的 profile 会重新序列化，其他节点按原来的内容写回
+#[derive(Debug, Clone, Default)]
+pub struct ConfigDocument {
+    // 为空时只在内存中编辑，用于输出生成的配置
+    path: String,
+    document: Mapping,
+}
+
+impl ConfigDocument {
+    /// 读取配置文件，文件不存在时从空的配置开始
+    pub fn load(path: &str) -> Result<Self> {
+        let document = match Path::new(path).exists() {
+            true => {
+                let content = fs::read_to_string(path)
+                    .with_context(|| format!("failed to read config`读取配置失败: {}", path))?;
+                match serde_yaml::from_str(&content)
+                    .with_context(|| format!("invalid config`无效的配置: {}", path))?
+                {
+                    Value::Mapping(document) => document,
+                    Value::Null => Mapping::new(),
+                    _ => {
+                        return Err(anyhow!(
+                            "config must be a mapping of profiles`配置必须是 profile 的映射: {}",
+                            path
+                        ))
+                    }
+                }
+            }
+            false => Mapping::new(),
+        };
+        Ok(Self {
+            path: path.to_string(),
+            document,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// 按文件中的顺序返回所有 profile 的名称
+    pub fn profile_names(&self) -> Vec<String> {
+        self.document
+            .keys()
+            .filter_map(|key| key.as_str())
+            .filter(|key| !RESERVED_KEYS.contains(key))
+            .map(|key| key.to_string())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        !RESERVED_KEYS.contains(&name) && self.document.contains_key(name)
+    }
+
+    /// 解析指定的 profile，不加载 body_file 等外部文件
+    pub fn profile(&self, name: &str) -> Result<DiffProfile> {
+        let value = self.get(name)?;
+        serde_yaml::from_value(value.clone())
+            .map_err(|e| anyhow!("Profile {} is invalid`profile 无效: {}", name, e))
+    }
+
+    /// 新增或替换指定的 profile，新的 profile 追加在文件末尾 \
+    /// xreq 的配置中 profile 为 RequestProfile
+    pub fn set_profile(&mut self, name: &str, profile: &impl Serialize) -> Result<()> {
+        check_name(name)?;
+        self.document.insert(
+            Value::String(name.to_string()),
+            serde_yaml::to_value(profile)?,
+        );
+        Ok(())
+    }
+
+    /// 复制一个 profile，保留原来的写法
+    pub fn duplicate(&mut self, name: &str, new_name: &str) -> Result<()> {
+        check_name(new_name)?;
+        let value = self.get(name)?.clone();
+        self.document
+            .insert(Value::String(new_name.to_string()), value);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.contains(name) && self.document.remove(name).is_some()
+    }
+
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(&self.document)?)
+    }
+
+    /// 写回配置文件
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, self.to_yaml()?)
+            .with_context(|| format!("failed to write config`写入配置失败: {}", self.path))
+    }
+
+    fn get(&self, name: &str) -> Result<&Value> {
+        self.document
+            .get(name)
+            .filter(|_| !RESERVED_KEYS.contains(&name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Profile {} not found in config file {}`配置文件中未找到",
+                    name,
+                    self.path
+                )
+            })
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || RESERVED_KEYS.contains(&name) {
+        return Err(anyhow!(
+            "{:?} can not be used as a profile name`不能作为 profile 的名称",
+            name
+        ));
+    }
+    Ok(())
+}
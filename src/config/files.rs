@@ -0,0 +1,90 @@
+use super::{get_body, ResponseBody, ResponseParts, ResponseProfile};
+use crate::{
+    json_changes, json_patch,
+    utils::{diff_json, diff_text_with},
+    DiffMode, DiffOptions, OutputFormat,
+};
+use anyhow::{Context, Result};
+use reqwest::{
+    header::{self, HeaderValue},
+    Response,
+};
+use std::{fs, path::Path, time::Duration};
+
+/// 比较两个本地文件，不发送请求 \
+/// 按扩展名推断 content type，经过和响应体相同的过滤后输出差异，没有差异时返回空字符串
+pub async fn diff_files(
+    file1: &str,
+    file2: &str,
+    profile: &ResponseProfile,
+    options: &DiffOptions,
+) -> Result<String> {
+    let mut parts1 = load_parts(file1, profile).await?;
+    let mut parts2 = load_parts(file2, profile).await?;
+    profile.compare_bodies(&mut parts1.body, &mut parts2.body)?;
+    // 和比较响应时一样，mask 和空白处理之后再比较
+    if options.mode != DiffMode::Text && options.format == OutputFormat::Text {
+        let json1 = parts1.json_body(profile)?;
+        let json2 = parts2.json_body(profile)?;
+        if let (Some(json1), Some(json2)) = (json1, json2) {
+            let changes = json_changes(&json1, &json2);
+            return match options.mode {
+                DiffMode::JsonPatch if changes.is_empty() => Ok(String::new()),
+                DiffMode::JsonPatch => {
+                    Ok(serde_json::to_string_pretty(&json_patch(&changes))? + "\n")
+                }
+                _ => diff_json(&changes),
+            };
+        }
+    }
+    let (text1, text2) = (parts1.to_text(profile)?, parts2.to_text(profile)?);
+    if text1 == text2 {
+        return Ok(String::new());
+    }
+    diff_text_with(&text1, &text2, options)
+}
+
+// 只有响应体的 ResponseParts，状态行和响应头为空，不参与比较
+async fn load_parts(file: &str, profile: &ResponseProfile) -> Result<ResponseParts> {
+    Ok(ResponseParts {
+        request: None,
+        status: String::new(),
+        status_code: 0,
+        header_map: Default::default(),
+        headers: String::new(),
+        body: load_body(file, profile).await?,
+        compressed: false,
+        elapsed: Duration::ZERO,
+        warnings: vec![],
+    })
+}
+
+async fn load_body(file: &str, profile: &ResponseProfile) -> Result<ResponseBody> {
+    let bytes = fs::read(file).with_context(|| format!("failed to read`读取失败: {}", file))?;
+    let mut res = http::Response::new(bytes);
+    if let Some(content_type) = content_type(file) {
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    get_body(Response::from(res), profile).await
+}
+
+// 根据扩展名推断 content type，无法识别时按文本比较
+fn content_type(file: &str) -> Option<&'static str> {
+    let ext = Path::new(file).extension()?.to_str()?.to_ascii_lowercase();
+    let content_type = match ext.as_str() {
+        "json" => "application/json",
+        "ndjson" | "jsonl" => "application/x-ndjson",
+        "xml" => "application/xml",
+        "html" | "htm" => "text/html",
+        "csv" => "text/csv",
+        "msgpack" => "application/msgpack",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        _ => return None,
+    };
+    Some(content_type)
+}
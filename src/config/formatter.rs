@@ -0,0 +1,134 @@
+use super::{
+    filter_json, filter_value, is_xml, xml, ResponseBody, ResponseProfile, NDJSON_CONTENT_TYPE,
+};
+use anyhow::{Context, Result};
+use std::sync::{Arc, RwLock};
+
+/// 按 content type 过滤并格式化响应体 \
+/// 内置 JSON、NDJSON、XML、HTML 和 CSV，库的使用者可以用 [`register_formatter`] 注册自定义的媒体类型
+pub trait BodyFormatter: Send + Sync {
+    /// 是否处理这个 content type(不包括 `;` 之后的参数)
+    fn matches(&self, content_type: &str) -> bool;
+
+    /// 过滤并格式化响应体
+    fn format(&self, text: &str, profile: &ResponseProfile) -> Result<ResponseBody>;
+}
+
+/// 注册的自定义格式化器，后注册的优先
+static FORMATTERS: RwLock<Vec<Arc<dyn BodyFormatter>>> = RwLock::new(Vec::new());
+
+/// 注册自定义的格式化器，优先于内置的格式化器
+/// register a formatter for custom media types
+pub fn register_formatter(formatter: impl BodyFormatter + 'static) {
+    FORMATTERS.write().unwrap().push(Arc::new(formatter));
+}
+
+/// 查找处理这个 content type 的格式化器
+pub(crate) fn find_formatter(content_type: &str) -> Option<Arc<dyn BodyFormatter>> {
+    let custom = FORMATTERS.read().unwrap();
+    let builtin: [Arc<dyn BodyFormatter>; 5] = [
+        Arc::new(JsonFormatter),
+        Arc::new(NdjsonFormatter),
+        Arc::new(XmlFormatter),
+        Arc::new(HtmlFormatter),
+        Arc::new(CsvFormatter),
+    ];
+    custom
+        .iter()
+        .rev()
+        .cloned()
+        .chain(builtin)
+        .find(|f| f.matches(content_type))
+}
+
+/// application/json：按 skip_body、select 等规则过滤
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormatter;
+
+impl BodyFormatter for JsonFormatter {
+    fn matches(&self, content_type: &str) -> bool {
+        content_type == "application/json"
+    }
+
+    fn format(&self, text: &str, profile: &ResponseProfile) -> Result<ResponseBody> {
+        Ok(ResponseBody::Json(filter_json(text, profile)?))
+    }
+}
+
+/// NDJSON：每行解析为一条记录，记录组成数组后按 JSON 的规则过滤 \
+/// skip_body 中的字段名作用于每条记录，`match_by` 可以按标识字段配对记录
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NdjsonFormatter;
+
+impl BodyFormatter for NdjsonFormatter {
+    fn matches(&self, content_type: &str) -> bool {
+        matches!(content_type, NDJSON_CONTENT_TYPE | "application/ndjson")
+    }
+
+    fn format(&self, text: &str, profile: &ResponseProfile) -> Result<ResponseBody> {
+        let records = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("invalid NDJSON line`无效的 NDJSON 行 {}", i + 1))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let json = filter_value(serde_json::Value::Array(records), profile)?;
+        Ok(ResponseBody::Json(json))
+    }
+}
+
+/// XML：规范化输出，按 skip_body 中的 XPath 删除节点
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XmlFormatter;
+
+impl BodyFormatter for XmlFormatter {
+    fn matches(&self, content_type: &str) -> bool {
+        is_xml(content_type)
+    }
+
+    fn format(&self, text: &str, profile: &ResponseProfile) -> Result<ResponseBody> {
+        Ok(ResponseBody::Text(xml::format_xml(
+            text,
+            &profile.skip_body,
+        )?))
+    }
+}
+
+/// text/html：配置了 html 时规范化输出，否则按原文比较
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlFormatter;
+
+impl BodyFormatter for HtmlFormatter {
+    fn matches(&self, content_type: &str) -> bool {
+        content_type == "text/html"
+    }
+
+    fn format(&self, text: &str, profile: &ResponseProfile) -> Result<ResponseBody> {
+        let text = match &profile.html {
+            Some(html) => html.canonicalize(text),
+            None => text.to_string(),
+        };
+        Ok(ResponseBody::Text(text))
+    }
+}
+
+/// text/csv：配置了 csv 时按单元格输出，否则按原文比较
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvFormatter;
+
+impl BodyFormatter for CsvFormatter {
+    fn matches(&self, content_type: &str) -> bool {
+        content_type == "text/csv"
+    }
+
+    fn format(&self, text: &str, profile: &ResponseProfile) -> Result<ResponseBody> {
+        let text = match &profile.csv {
+            Some(csv) => csv.format(text)?,
+            None => text.to_string(),
+        };
+        Ok(ResponseBody::Text(text))
+    }
+}
@@ -0,0 +1,355 @@
+use super::{curl, RequestProfile, SentRequest};
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Method, Response, StatusCode, Url, Version,
+};
+use serde::Deserialize;
+use std::{fs, time::Duration};
+
+#[derive(Debug, Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarLog {
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarEntry {
+    request: HarRequest,
+    response: HarResponse,
+    // 整个请求的耗时，单位为毫秒
+    #[serde(default)]
+    time: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarPostData {
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    // 表单的字段，没有 text 时使用
+    #[serde(default)]
+    params: Vec<HarHeader>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarResponse {
+    status: u16,
+    #[serde(default)]
+    http_version: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    #[serde(default)]
+    content: HarContent,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarHeader {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct HarContent {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    encoding: Option<String>,
+}
+
+// 导入请求时不保留的请求头，由发送时重新生成
+const SKIPPED_HEADERS: [&str; 3] = ["host", "content-length", "connection"];
+
+/// 读取 HAR 文件中所有的请求，按录制的顺序返回 `状态码 方法 URL` 形式的说明和转换后的请求 \
+/// 无法转换的请求(例如 multipart 的 body)返回错误，不影响其他的请求
+pub fn har_requests(file: &str) -> Result<Vec<(String, Result<RequestProfile>)>> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("failed to read HAR`读取 HAR 失败: {}", file))?;
+    let har: Har = serde_json::from_str(&content)
+        .with_context(|| format!("invalid HAR`无效的 HAR: {}", file))?;
+    Ok(har
+        .log
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let label = format!(
+                "{} {} {}",
+                entry.response.status, entry.request.method, entry.request.url
+            );
+            (label, to_request(entry.request))
+        })
+        .collect())
+}
+
+fn to_request(har: HarRequest) -> Result<RequestProfile> {
+    let mut profile: RequestProfile = har.url.parse()?;
+    profile.method = Method::from_bytes(har.method.as_bytes())?;
+    for h in har.headers {
+        let name = h.name.to_lowercase();
+        // HTTP/2 的伪头部(例如 `:authority`)不是真正的请求头
+        if name.starts_with(':') || SKIPPED_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        profile.headers.append(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(&h.value)?,
+        );
+    }
+    let Some(post) = har.post_data else {
+        return Ok(profile);
+    };
+    let mime_type = post.mime_type.to_lowercase();
+    profile.body = match post.text.filter(|text| !text.is_empty()) {
+        Some(text) if mime_type.contains("json") => Some(
+            serde_json::from_str(&text)
+                .map_err(|e| anyhow!("invalid JSON body`无效的 JSON: {}", e))?,
+        ),
+        Some(text) if mime_type.starts_with(curl::FORM_CONTENT_TYPE) => {
+            Some(curl::form_body(&text)?)
+        }
+        Some(_) => {
+            return Err(anyhow!(
+                "Unsupported content type for the body`不支持的 body 类型: {}",
+                post.mime_type
+            ))
+        }
+        None if !post.params.is_empty() => {
+            let map = post
+                .params
+                .into_iter()
+                .map(|p| (p.name, serde_json::Value::String(p.value)))
+                .collect();
+            Some(serde_json::Value::Object(map))
+        }
+        None => None,
+    };
+    Ok(profile)
+}
+
+/// 在 HAR 文件中查找方法和 URL 都相同的请求，返回录制的响应和耗时 \
+/// 同一个请求出现多次时使用最后一次
+pub(crate) fn find_response(file: &str, req: &SentRequest) -> Result<(Response, Duration)> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("failed to read HAR`读取 HAR 失败: {}", file))?;
+    let har: Har = serde_json::from_str(&content)
+        .with_context(|| format!("invalid HAR`无效的 HAR: {}", file))?;
+    let entry = har
+        .log
+        .entries
+        .into_iter()
+        .rev()
+        .filter(|entry| {
+            entry
+                .request
+                .method
+                .eq_ignore_ascii_case(req.method.as_str())
+        })
+        .find(|entry| Url::parse(&entry.request.url).is_ok_and(|url| url == req.url))
+        .ok_or_else(|| {
+            anyhow!(
+                "No entry for {} {} in HAR {}`HAR 中没有这个请求",
+                req.method,
+                req.url,
+                file
+            )
+        })?;
+    let elapsed = Duration::from_secs_f64(entry.time.max(0.0) / 1000.0);
+    Ok((build_response(entry.response)?, elapsed))
+}
+
+fn build_response(har: HarResponse) -> Result<Response> {
+    let body = match (har.content.text, har.content.encoding.as_deref()) {
+        (Some(text), Some("base64")) => STANDARD
+            .decode(text.trim())
+            .map_err(|e| anyhow!("invalid base64 content in HAR`HAR 中的 base64 无效: {}", e))?,
+        (Some(text), _) => text.into_bytes(),
+        (None, _) => vec![],
+    };
+    let mut headers = HeaderMap::new();
+    for h in har.headers {
+        // HTTP/2 的伪头部(例如 `:status`)不是真正的响应头
+        if h.name.starts_with(':') {
+            continue;
+        }
+        let name = HeaderName::from_bytes(h.name.to_lowercase().as_bytes())?;
+        headers.append(name, HeaderValue::from_str(&h.value)?);
+    }
+    // HAR 中保存的是解压后的内容
+    headers.remove(header::CONTENT_ENCODING);
+    headers.remove(header::CONTENT_LENGTH);
+
+    let mut res = http::Response::new(body);
+    *res.status_mut() = StatusCode::from_u16(har.status)?;
+    *res.version_mut() = match har.http_version.to_ascii_uppercase().as_str() {
+        "HTTP/1.0" => Version::HTTP_10,
+        "HTTP/2" | "HTTP/2.0" | "H2" => Version::HTTP_2,
+        "HTTP/3" | "HTTP/3.0" | "H3" => Version::HTTP_3,
+        _ => Version::HTTP_11,
+    };
+    *res.headers_mut() = headers;
+    Ok(Response::from(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn request(value: serde_json::Value) -> Result<RequestProfile> {
+        to_request(serde_json::from_value(value).unwrap())
+    }
+
+    fn har_file(entries: serde_json::Value) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let har = json!({ "log": { "entries": entries } });
+        file.write_all(har.to_string().as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn to_request_should_skip_pseudo_and_generated_headers() {
+        let profile = request(json!({
+            "method": "GET",
+            "url": "https://example.com/a",
+            "headers": [
+                { "name": ":authority", "value": "example.com" },
+                { "name": "Host", "value": "example.com" },
+                { "name": "Content-Length", "value": "0" },
+                { "name": "X-Trace", "value": "1" },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(profile.headers.len(), 1);
+        assert_eq!(profile.headers["x-trace"], "1");
+    }
+
+    #[test]
+    fn to_request_should_convert_json_and_form_bodies() {
+        let profile = request(json!({
+            "method": "POST",
+            "url": "https://example.com/a",
+            "postData": { "mimeType": "application/json; charset=utf-8", "text": "{\"a\":1}" },
+        }))
+        .unwrap();
+        assert_eq!(profile.method, Method::POST);
+        assert_eq!(profile.body, Some(json!({"a": 1})));
+
+        let profile = request(json!({
+            "method": "POST",
+            "url": "https://example.com/a",
+            "postData": { "mimeType": "application/x-www-form-urlencoded", "text": "a=1&b=x+y" },
+        }))
+        .unwrap();
+        assert_eq!(profile.body, Some(json!({"a": "1", "b": "x y"})));
+
+        let profile = request(json!({
+            "method": "POST",
+            "url": "https://example.com/a",
+            "postData": {
+                "mimeType": "application/x-www-form-urlencoded",
+                "params": [{ "name": "a", "value": "1" }],
+            },
+        }))
+        .unwrap();
+        assert_eq!(profile.body, Some(json!({"a": "1"})));
+    }
+
+    #[test]
+    fn to_request_should_reject_unsupported_bodies() {
+        let err = request(json!({
+            "method": "POST",
+            "url": "https://example.com/a",
+            "postData": { "mimeType": "multipart/form-data; boundary=x", "text": "--x" },
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("multipart/form-data"));
+    }
+
+    #[test]
+    fn har_requests_should_keep_failures_per_entry() {
+        let file = har_file(json!([
+            { "request": { "method": "GET", "url": "https://example.com/a" }, "response": { "status": 200 } },
+            {
+                "request": {
+                    "method": "POST",
+                    "url": "https://example.com/b",
+                    "postData": { "mimeType": "text/plain", "text": "x" },
+                },
+                "response": { "status": 201 },
+            },
+        ]));
+        let requests = har_requests(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "200 GET https://example.com/a");
+        assert!(requests[0].1.is_ok());
+        assert_eq!(requests[1].0, "201 POST https://example.com/b");
+        assert!(requests[1].1.is_err());
+    }
+
+    #[tokio::test]
+    async fn find_response_should_use_the_last_matching_entry() {
+        let entry = |status: u16, text: &str| {
+            json!({
+                "request": { "method": "GET", "url": "https://example.com/a?b=1" },
+                "response": {
+                    "status": status,
+                    "httpVersion": "h2",
+                    "headers": [
+                        { "name": ":status", "value": status.to_string() },
+                        { "name": "Content-Encoding", "value": "gzip" },
+                        { "name": "Content-Type", "value": "text/plain" },
+                    ],
+                    "content": { "text": STANDARD.encode(text), "encoding": "base64" },
+                },
+                "time": 12.5,
+            })
+        };
+        let file = har_file(json!([entry(200, "first"), entry(404, "second")]));
+        let req = SentRequest {
+            method: Method::GET,
+            url: Url::parse("https://example.com/a?b=1").unwrap(),
+            headers: HeaderMap::new(),
+            body: vec![],
+        };
+        let (res, elapsed) = find_response(file.path().to_str().unwrap(), &req).unwrap();
+        assert_eq!(elapsed, Duration::from_micros(12_500));
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.version(), Version::HTTP_2);
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(res.text().await.unwrap(), "second");
+    }
+
+    #[test]
+    fn find_response_should_fail_without_a_match() {
+        let file = har_file(json!([
+            { "request": { "method": "POST", "url": "https://example.com/a" }, "response": { "status": 200 } },
+        ]));
+        let req = SentRequest {
+            method: Method::GET,
+            url: Url::parse("https://example.com/a").unwrap(),
+            headers: HeaderMap::new(),
+            body: vec![],
+        };
+        assert!(find_response(file.path().to_str().unwrap(), &req).is_err());
+    }
+}
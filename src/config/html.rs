@@ -0,0 +1,103 @@
+use scraper::{ElementRef, Html, Node};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// text/html 响应的规范化配置，设置后 HTML 会被解析并按固定格式输出，每个节点占一行
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct HtmlProfile {
+    // 需要去掉的属性，例如 `nonce`、`data-csrf`，支持 `data-*` 通配符
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub strip_attributes: Vec<String>,
+    // 是否保留注释
+    #[serde(skip_serializing_if = "crate::is_default", default)]
+    pub keep_comments: bool,
+}
+
+// 没有结束标签的元素
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+// 内容中的空白有意义的元素
+const RAW_ELEMENTS: &[&str] = &["pre", "textarea", "script", "style"];
+
+impl HtmlProfile {
+    /// 解析 HTML 并输出规范化的文本：属性按名称排序，去掉多余的空白
+    pub(crate) fn canonicalize(&self, text: &str) -> String {
+        let html = Html::parse_document(text);
+        let mut output = String::new();
+        for node in html.tree.root().children() {
+            match node.value() {
+                Node::Doctype(doctype) => {
+                    let _ = writeln!(output, "<!DOCTYPE {}>", doctype.name());
+                }
+                Node::Element(_) => {
+                    if let Some(element) = ElementRef::wrap(node) {
+                        self.render(element, 0, &mut output);
+                    }
+                }
+                Node::Comment(comment) if self.keep_comments => {
+                    let _ = writeln!(output, "<!--{}-->", comment.trim());
+                }
+                _ => {}
+            }
+        }
+        output
+    }
+
+    fn render(&self, element: ElementRef, depth: usize, output: &mut String) {
+        let indent = "  ".repeat(depth);
+        let value = element.value();
+        let name = value.name();
+        let mut attrs: Vec<(&str, &str)> = value
+            .attrs()
+            .filter(|(attr, _)| !self.is_stripped(attr))
+            .collect();
+        attrs.sort();
+        let _ = write!(output, "{}<{}", indent, name);
+        for (attr, v) in attrs {
+            let _ = write!(output, " {}=\"{}\"", attr, v.replace('"', "&quot;"));
+        }
+        let _ = writeln!(output, ">");
+        if VOID_ELEMENTS.contains(&name) {
+            return;
+        }
+
+        let raw = RAW_ELEMENTS.contains(&name);
+        for child in element.children() {
+            match child.value() {
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.render(child, depth + 1, output);
+                    }
+                }
+                Node::Text(text) if raw => {
+                    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                        let _ = writeln!(output, "{}  {}", indent, line.trim_end());
+                    }
+                }
+                Node::Text(text) => {
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if !text.is_empty() {
+                        let _ = writeln!(output, "{}  {}", indent, text);
+                    }
+                }
+                Node::Comment(comment) if self.keep_comments => {
+                    let _ = writeln!(output, "{}  <!--{}-->", indent, comment.trim());
+                }
+                _ => {}
+            }
+        }
+        let _ = writeln!(output, "{}</{}>", indent, name);
+    }
+
+    fn is_stripped(&self, attr: &str) -> bool {
+        self.strip_attributes
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => attr.starts_with(prefix),
+                None => attr.eq_ignore_ascii_case(pattern),
+            })
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use image::{GenericImageView, ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// 图片响应的比较配置，设置后 image/* 响应会被解码，比较格式、尺寸和像素
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ImageProfile {
+    // 每个通道的差值不超过 threshold 时认为像素相同
+    #[serde(skip_serializing_if = "crate::is_default", default)]
+    pub threshold: u8,
+    // 把差异图写入这个文件，不同的像素标记为红色；不设置时不会生成或写入任何文件
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub diff_file: Option<String>,
+}
+
+impl ImageProfile {
+    /// 比较两张图片，返回两边用于比较的文本；像素不同时第二张图片的文本中会多出差异比例
+    pub(crate) fn compare(&self, bytes1: &[u8], bytes2: &[u8]) -> Result<(String, String)> {
+        let image1 =
+            image::load_from_memory(bytes1).context("failed to decode image`图片解码失败")?;
+        let image2 =
+            image::load_from_memory(bytes2).context("failed to decode image`图片解码失败")?;
+        let text1 = describe(bytes1, image1.dimensions());
+        let mut text2 = describe(bytes2, image2.dimensions());
+        // 尺寸不同时已经可以从描述中看出差异
+        if image1.dimensions() != image2.dimensions() {
+            return Ok((text1, text2));
+        }
+
+        let (image1, image2) = (image1.to_rgba8(), image2.to_rgba8());
+        // 只有配置了 diff_file 才生成差异图
+        let mut diff = self
+            .diff_file
+            .as_ref()
+            .map(|_| RgbaImage::new(image1.width(), image1.height()));
+        let mut changed = 0u64;
+        for (x, y, p1) in image1.enumerate_pixels() {
+            let p2 = image2.get_pixel(x, y);
+            let same =
+                p1.0.iter()
+                    .zip(p2.0.iter())
+                    .all(|(a, b)| a.abs_diff(*b) <= self.threshold);
+            if !same {
+                changed += 1;
+            }
+            if let Some(diff) = diff.as_mut() {
+                let pixel = if same {
+                    // 相同的像素淡化显示
+                    let gray = (p1[0] as u16 + p1[1] as u16 + p1[2] as u16) / 3;
+                    let gray = (gray / 4 + 191) as u8;
+                    Rgba([gray, gray, gray, 255])
+                } else {
+                    Rgba([255, 0, 0, 255])
+                };
+                diff.put_pixel(x, y, pixel);
+            }
+        }
+        if changed > 0 {
+            let total = image1.width() as u64 * image1.height() as u64;
+            let percent = changed as f64 * 100.0 / total as f64;
+            writeln!(
+                text2,
+                "pixel difference: {:.2}% ({} of {} pixels)",
+                percent, changed, total
+            )?;
+            if let (Some(file), Some(diff)) = (&self.diff_file, &diff) {
+                diff.save_with_format(file, ImageFormat::Png)
+                    .with_context(|| format!("failed to write`写入失败: {}", file))?;
+                writeln!(text2, "diff image written to {}", file)?;
+            }
+        }
+        Ok((text1, text2))
+    }
+}
+
+fn describe(bytes: &[u8], (width, height): (u32, u32)) -> String {
+    let format = image::guess_format(bytes)
+        .map(|f| format!("{:?}", f).to_lowercase())
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("image {} {}x{}\n", format, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png(pixels: &[[u8; 4]]) -> Vec<u8> {
+        let mut image = RgbaImage::new(pixels.len() as u32, 1);
+        for (x, p) in pixels.iter().enumerate() {
+            image.put_pixel(x as u32, 0, Rgba(*p));
+        }
+        let mut bytes = Cursor::new(vec![]);
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn compare_without_diff_file_should_only_report_the_difference() {
+        let image1 = png(&[[0, 0, 0, 255], [0, 0, 0, 255]]);
+        let image2 = png(&[[0, 0, 0, 255], [255, 255, 255, 255]]);
+        let (text1, text2) = ImageProfile::default().compare(&image1, &image2).unwrap();
+        assert_eq!(text1, "image png 2x1\n");
+        assert_eq!(
+            text2,
+            "image png 2x1\npixel difference: 50.00% (1 of 2 pixels)\n"
+        );
+    }
+}
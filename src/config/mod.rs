@@ -1,20 +1,84 @@
+mod assertion;
+mod auth;
+mod binary;
+mod check;
+mod compression;
+mod cookie;
+mod curl;
+mod edit;
+mod files;
+mod formatter;
+mod har;
+mod html;
+mod image_diff;
+mod normalize;
+mod postman;
+mod protobuf;
+mod rate;
+mod record;
+mod sent;
+mod snapshot;
+mod spool;
+mod sse;
+mod suppress;
+mod table;
+mod template;
+mod trace;
+mod transform;
 mod xdiff;
+mod xml;
 mod xreq;
 
 // 引入需要使用的依赖
+pub use assertion::Assertions;
+pub use auth::*;
+pub use binary::BinaryMode;
+pub use check::ConfigError;
+pub use cookie::CookieProfile;
+pub use edit::ConfigDocument;
+pub use files::diff_files;
+pub use formatter::{
+    register_formatter, BodyFormatter, CsvFormatter, HtmlFormatter, JsonFormatter, NdjsonFormatter,
+    XmlFormatter,
+};
+pub use har::har_requests;
+pub use html::HtmlProfile;
+pub use image_diff::ImageProfile;
+pub use normalize::{MaskRule, MatchBy, NormalizePreset, SortArrays, SortRule, TimestampRule};
+pub use protobuf::{ProtobufProfile, PROTOBUF_CONTENT_TYPE};
+pub use rate::set_rate_limit;
+pub use sent::{IncludeRequest, SentRequest};
+pub use sse::{SseProfile, SSE_CONTENT_TYPE};
+pub use suppress::{save_suppressions, Suppression};
+pub use table::CsvProfile;
+pub use template::Template;
+pub use trace::set_verbosity;
+pub use transform::Transform;
 pub use xdiff::*;
 pub use xreq::*;
 
+pub(crate) use rate::rate_interval;
+
 // 引入需要使用的库
-use crate::ExtraArgs;
-use anyhow::{Ok, Result};
+use crate::{is_default, utils::diff_header_map, ExtraArgs, JsonPath, ResponsePart};
+use anyhow::{Context, Ok, Result};
+use prost_reflect::MessageDescriptor;
 use reqwest::{
     header::{self, HeaderMap, HeaderName, HeaderValue},
     Client, Method, Response, Url,
 };
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use serde_json::json;
-use std::{fmt::Write, fs, ops::Deref, path::Path, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    ops::Deref,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 pub trait LoadConfig
 where
@@ -23,18 +87,38 @@ where
     /// load config from file
     /// 从文件加载配置
     fn load_yaml(path: impl AsRef<Path>) -> Result<Self> {
-        let absolute_path = std::env::current_dir().unwrap().join(path.as_ref());
-        let content = fs::read_to_string(absolute_path).unwrap();
-        Self::from_yaml(&content)
+        let absolute_path = std::env::current_dir()
+            .context("failed to get the current directory`获取当前目录失败")?
+            .join(path.as_ref());
+        let content = fs::read_to_string(&absolute_path).with_context(|| {
+            format!(
+                "failed to read config file`读取配置文件失败: {}",
+                path.as_ref().display()
+            )
+        })?;
+        // 配置中引用的文件相对于配置文件所在的目录
+        let base_dir = absolute_path.parent().unwrap_or_else(|| Path::new("."));
+        Self::from_yaml_in(&content, base_dir)
     }
 
     /// load config from string
     /// 从字符串加载配置
     fn from_yaml(content: &str) -> Result<Self> {
-        let config: Self = serde_yaml::from_str(content)?;
+        Self::from_yaml_in(content, &std::env::current_dir()?)
+    }
+
+    /// 从字符串加载配置，引用的文件相对于 base_dir
+    fn from_yaml_in(content: &str, base_dir: &Path) -> Result<Self> {
+        let mut config: Self = serde_yaml::from_str(content)?;
+        config.load_files(base_dir)?;
         config.validate()?;
         Ok(config)
     }
+
+    /// 加载配置中引用的外部文件，例如 `body_file`
+    fn load_files(&mut self, _base_dir: &Path) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub trait ConfigValidate {
@@ -62,23 +146,247 @@ pub struct RequestProfile {
     // 定义请求体，为JSON格式的数据
     #[serde(skip_serializing_if = "empty_json_value", default)]
     pub body: Option<serde_json::Value>,
+    // 覆盖默认的 User-Agent，headers 中显式设置的 user-agent 优先
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub user_agent: Option<String>,
+    // 认证方式
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub auth: Option<Auth>,
+    // 从 JSON/YAML 文件中加载 body，路径相对于配置文件，body 中的键会覆盖文件中的
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub body_file: Option<String>,
+    // 发送 Idempotency-Key 头，每次运行生成一个新的 UUID，两个请求使用同一个值
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub idempotency_key: bool,
+    // protobuf 编解码配置
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub protobuf: Option<ProtobufProfile>,
+    // 不发送请求，而是从这个 HAR 文件中取出方法和 URL 相同的响应，路径相对于配置文件
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub har: Option<String>,
 }
 
-// 如果返回结果为false, 将不会序列化该字段
+/// NDJSON 的 content type，body 为数组时每个元素序列化为一行
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// 默认发送的 User-Agent
+pub const DEFAULT_USER_AGENT: &str = concat!("xdiff/", env!("CARGO_PKG_VERSION"));
+
+// 如果返回结果为true, 将不会序列化该字段
 fn empty_json_value(v: &Option<serde_json::Value>) -> bool {
-    // 判断v是否为None，如果是则返回true，否则返回v.is_null()
-    v.as_ref().map_or(true, |v| v.is_null() || v.is_object())
+    // None、null 和空对象都不序列化
+    v.as_ref()
+        .is_none_or(|v| v.is_null() || v.as_object().is_some_and(|map| map.is_empty()))
+}
+
+/// 相对于另一个请求的增量配置，未设置的字段沿用基础请求 \
+/// A request expressed as a delta from a base request, unset fields are inherited
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RequestDelta {
+    // 覆盖请求方法
+    #[serde(deserialize_with = "deserialize_method", default)]
+    pub method: Option<Method>,
+    // 设置后视为完整的请求，不再继承基础请求
+    pub url: Option<Url>,
+    // 只替换基础请求 URL 的 path
+    pub path: Option<String>,
+    // 按键合并到基础请求的 params，值为 null 时删除该键
+    pub params: Option<serde_json::Value>,
+    // 按名称覆盖基础请求的 headers
+    #[serde(with = "http_serde::header_map", default)]
+    pub headers: HeaderMap,
+    // 按键合并到基础请求的 body，值为 null 时删除该键
+    pub body: Option<serde_json::Value>,
+    // 覆盖基础请求的 User-Agent
+    pub user_agent: Option<String>,
+    // 覆盖基础请求的认证方式
+    pub auth: Option<Auth>,
+    // 替换基础请求的 body 和 body_file
+    pub body_file: Option<String>,
+    // 覆盖基础请求的 idempotency_key
+    pub idempotency_key: Option<bool>,
+    // 覆盖基础请求的 protobuf 配置
+    pub protobuf: Option<ProtobufProfile>,
+    // 从 HAR 文件中读取响应
+    pub har: Option<String>,
+}
+
+fn deserialize_method<'de, D>(deserializer: D) -> std::result::Result<Option<Method>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    http_serde::method::deserialize(deserializer).map(Some)
+}
+
+impl RequestDelta {
+    /// 基于 base 解析出完整的请求配置
+    /// resolve the delta against `base` into a complete request
+    pub fn resolve(self, base: &RequestProfile) -> Result<RequestProfile> {
+        // 带有 url 的是完整请求，保持原有语义
+        if let Some(url) = self.url {
+            if self.path.is_some() {
+                return Err(anyhow::anyhow!(
+                    "`path` can only be used when `url` is omitted`只有省略 url 时才能使用 path"
+                ));
+            }
+            return Ok(RequestProfile {
+                user_agent: self.user_agent,
+                auth: self.auth,
+                body_file: self.body_file,
+                idempotency_key: self.idempotency_key.unwrap_or_default(),
+                protobuf: self.protobuf,
+                har: self.har,
+                ..RequestProfile::new(
+                    self.method.unwrap_or_default(),
+                    url,
+                    self.params,
+                    self.headers,
+                    self.body,
+                )
+            });
+        }
+
+        let mut req = base.clone();
+        if let Some(method) = self.method {
+            req.method = method;
+        }
+        if let Some(path) = self.path {
+            if !path.starts_with('/') {
+                return Err(anyhow::anyhow!(
+                    "`path` must start with '/' but got`path 必须以 / 开头: {}",
+                    path
+                ));
+            }
+            // path 中可以带上 query，会替换掉原来的 query
+            let (path, query) = match path.split_once('?') {
+                Some((path, query)) => (path, Some(query)),
+                None => (path.as_str(), None),
+            };
+            req.url.set_path(path);
+            if query.is_some() {
+                req.url.set_query(query);
+            }
+        }
+        if self.user_agent.is_some() {
+            req.user_agent = self.user_agent;
+        }
+        if self.auth.is_some() {
+            req.auth = self.auth;
+        }
+        if self.body_file.is_some() {
+            req.body_file = self.body_file;
+            req.body = None;
+        }
+        if let Some(idempotency_key) = self.idempotency_key {
+            req.idempotency_key = idempotency_key;
+        }
+        if self.protobuf.is_some() {
+            req.protobuf = self.protobuf;
+        }
+        if self.har.is_some() {
+            req.har = self.har;
+        }
+        req.params = merge_json(req.params, self.params);
+        req.body = merge_json(req.body, self.body);
+        // 同一个 header 第一个值之后的名字为 None，沿用前一个名字；
+        // 同名的 header 整体替换 base 中的所有值
+        let mut name = None;
+        for (k, v) in self.headers {
+            if let Some(k) = k {
+                req.headers.remove(&k);
+                name = Some(k);
+            }
+            if let Some(name) = &name {
+                req.headers.append(name, v);
+            }
+        }
+        Ok(req)
+    }
+}
+
+impl RequestDelta {
+    /// 以 host 作为基础 URL 解析出完整的请求配置，`path` 会拼接在 host 的 path 之后
+    /// resolve the delta against a base URL, `path` is appended to the host's path
+    pub fn resolve_on_host(mut self, host: &Url) -> Result<RequestProfile> {
+        if self.url.is_some() {
+            return Err(anyhow::anyhow!(
+                "`url` can not be used together with `hosts`, use `path` instead`使用 hosts 时请用 path 代替 url"
+            ));
+        }
+        if let Some(path) = self.path.take() {
+            let path = path.strip_prefix('/').unwrap_or(&path);
+            self.path = Some(format!("{}/{}", host.path().trim_end_matches('/'), path));
+        }
+        let base = RequestProfile::new(
+            Method::default(),
+            host.clone(),
+            None,
+            HeaderMap::new(),
+            None,
+        );
+        self.resolve(&base)
+    }
+}
+
+impl From<RequestProfile> for RequestDelta {
+    fn from(req: RequestProfile) -> Self {
+        Self {
+            method: Some(req.method),
+            url: Some(req.url),
+            path: None,
+            params: req.params,
+            headers: req.headers,
+            body: req.body,
+            user_agent: req.user_agent,
+            auth: req.auth,
+            body_file: req.body_file,
+            idempotency_key: Some(req.idempotency_key),
+            protobuf: req.protobuf,
+            har: req.har,
+        }
+    }
+}
+
+// 以 JSON merge patch 的方式把 patch 合并到 base
+fn merge_json(
+    base: Option<serde_json::Value>,
+    patch: Option<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    match (base, patch) {
+        (Some(serde_json::Value::Object(mut base)), Some(serde_json::Value::Object(patch))) => {
+            for (k, v) in patch {
+                if v.is_null() {
+                    base.shift_remove(&k);
+                } else {
+                    base.insert(k, v);
+                }
+            }
+            Some(serde_json::Value::Object(base))
+        }
+        (base, None) => base,
+        (_, patch) => patch,
+    }
 }
 
 // 定义一个响应的扩展结构体 ResponseExt，实现Deref trait，以支持引用ResponseExt时能够访问Response对象
 #[derive(Debug)]
-pub struct ResponseExt(Response);
+pub struct ResponseExt {
+    res: Response,
+    // 响应 body 的 protobuf 消息，设置后 body 会解码为 JSON
+    protobuf: Option<MessageDescriptor>,
+    // 实际发送的请求
+    request: SentRequest,
+    // 从发送请求到收到响应头的时间
+    elapsed: Duration,
+    // 录制时响应体超过 max_body_bytes 只保存了前面的部分，这里是实际的大小
+    body_size: Option<usize>,
+}
 
 impl Deref for ResponseExt {
     type Target = Response;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.res
     }
 }
 
@@ -98,26 +406,151 @@ impl RequestProfile {
             params,
             headers,
             body,
+            user_agent: None,
+            auth: None,
+            body_file: None,
+            idempotency_key: false,
+            protobuf: None,
+            har: None,
         }
     }
 
-    // 发送请求，并返回一个Result<ResponseExt>对象
-    pub async fn send(&self, args: &ExtraArgs) -> Result<ResponseExt> {
-        // 生成请求的HeaderMap、请求参数、请求体
+    /// 构造会发送的请求但不发送，需要质询的认证方式不包含 Authorization
+    pub fn dry_run(&self, args: &ExtraArgs) -> Result<SentRequest> {
         let (headers, query, body) = self.generate(args)?;
-        // 创建一个reqwest::Client对象
-        let client = Client::new();
-        // 根据请求的参数创建一个reqwest::Request对象
-        let req = client
+        let req = Client::new()
             .request(self.method.clone(), self.url.clone())
             .headers(headers)
             .query(&query)
             .body(body)
-            .build()
-            .unwrap();
-        // 发送请求并返回ResponseExt对象
-        let res = client.execute(req).await?;
-        Ok(ResponseExt(res))
+            .build()?;
+        Ok(SentRequest::from(&req))
+    }
+
+    // 发送请求，并返回一个Result<ResponseExt>对象
+    pub async fn send(&self, args: &ExtraArgs) -> Result<ResponseExt> {
+        // 生成请求的HeaderMap、请求参数、请求体
+        let (headers, query, body) = self.generate(args)?;
+        // 创建一个reqwest::Client对象，-vv 时记录 DNS 解析的耗时
+        let resolver = trace::TimedResolver::default();
+        let client = match trace::verbosity() > 1 {
+            true => Client::builder()
+                .dns_resolver(Arc::new(resolver.clone()))
+                .build()?,
+            false => Client::new(),
+        };
+        let mut attempt = 0;
+        let mut authorization = None;
+        loop {
+            // 根据请求的参数创建一个reqwest::Request对象
+            let mut req = client
+                .request(self.method.clone(), self.url.clone())
+                .headers(headers.clone())
+                .query(&query)
+                .body(body.clone())
+                .build()?;
+            if let Some(value) = &authorization {
+                req.headers_mut()
+                    .insert(header::AUTHORIZATION, HeaderValue::clone(value));
+            }
+            let url = req.url().clone();
+            let sent = SentRequest::from(&req);
+            if let Some(har) = &self.har {
+                let (res, elapsed) = har::find_response(har, &sent)?;
+                trace::trace(&sent, &res, elapsed, None);
+                return Ok(ResponseExt {
+                    res,
+                    protobuf: self.response_message()?,
+                    request: sent,
+                    elapsed,
+                    body_size: None,
+                });
+            }
+            // 发送请求并返回ResponseExt对象
+            rate::wait_rate_limit().await;
+            let start = Instant::now();
+            let res = client.execute(req).await?;
+            let elapsed = start.elapsed();
+            trace::trace(&sent, &res, elapsed, resolver.elapsed());
+            // 需要质询的认证方式，在收到 401 后带上 Authorization 重发一次
+            if let (None, Some(auth)) = (&authorization, &self.auth) {
+                if let Some(value) = auth.challenge(&res, &self.method, &url)? {
+                    authorization = Some(value);
+                    continue;
+                }
+            }
+            // 遇到 429 时按 Retry-After 退避后重试
+            match rate::retry_after(&res, attempt) {
+                Some(wait) => {
+                    attempt += 1;
+                    tokio::time::sleep(wait).await;
+                }
+                None => {
+                    return Ok(ResponseExt {
+                        res,
+                        protobuf: self.response_message()?,
+                        request: sent,
+                        elapsed,
+                        body_size: None,
+                    });
+                }
+            }
+        }
+    }
+
+    /// 响应消息的 protobuf 描述，没有配置时返回 None
+    pub(crate) fn response_message(&self) -> Result<Option<MessageDescriptor>> {
+        match &self.protobuf {
+            Some(protobuf) => protobuf.response_message(),
+            None => Ok(None),
+        }
+    }
+
+    /// 加载引用的外部文件：读取 body_file 并与 body 合并(body 中的键优先)，加载 protobuf descriptor
+    /// load files referenced relative to `base_dir`
+    pub fn load_files(&mut self, base_dir: &Path) -> Result<()> {
+        if let Some(protobuf) = self.protobuf.as_mut() {
+            protobuf.load(base_dir)?;
+        }
+        if let Some(har) = self.har.as_mut() {
+            *har = base_dir.join(&*har).display().to_string();
+        }
+        self.load_body_file(base_dir)
+    }
+
+    /// 读取 body_file，和配置中的 body 合并
+    pub(crate) fn load_body_file(&mut self, base_dir: &Path) -> Result<()> {
+        let Some(file) = &self.body_file else {
+            return Ok(());
+        };
+        let path = base_dir.join(file);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read body_file`读取失败: {}", path.display()))?;
+        let body: serde_json::Value = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yml" | "yaml") => serde_yaml::from_str(&content)?,
+            _ => serde_json::from_str(&content)?,
+        };
+        self.body = merge_json(Some(body), self.body.take());
+        Ok(())
+    }
+
+    /// 应用默认请求头、认证、模板函数和命令行参数之后的请求配置，和实际发送的内容一致
+    pub fn resolve_args(&self, args: &ExtraArgs) -> Result<Self> {
+        let (headers, params, body) = self.generate(args)?;
+        // protobuf、表单等编码后不是 JSON 的请求体保留配置中的值
+        let body = serde_json::from_slice(&body)
+            .ok()
+            .or_else(|| self.body.clone());
+        Ok(Self {
+            headers,
+            params: Some(params),
+            body,
+            user_agent: None,
+            auth: None,
+            body_file: None,
+            idempotency_key: false,
+            ..self.clone()
+        })
     }
 
     // 从ExtraArgs提取数据生成url
@@ -133,22 +566,48 @@ impl RequestProfile {
     }
 
     // 生成请求的HeaderMap、请求参数、请求体
-    fn generate(&self, args: &ExtraArgs) -> Result<(HeaderMap, serde_json::Value, String)> {
-        let mut headers = HeaderMap::new();
+    fn generate(&self, args: &ExtraArgs) -> Result<(HeaderMap, serde_json::Value, Vec<u8>)> {
+        let mut headers = self.headers.clone();
         let mut query = self.params.clone().unwrap_or_else(|| json!({}));
         let mut body = self.body.clone().unwrap_or_else(|| json!({}));
 
         // 将ExtraArgs中的headers合并到headers中
         for (k, v) in &args.headers {
-            headers.insert(HeaderName::from_str(k)?, HeaderName::from_str(v)?.into());
+            headers.insert(HeaderName::from_str(k)?, HeaderValue::from_str(v)?);
         }
 
-        // 如果headers中没有设置Content-Type，则设置为application/json
+        // 没有显式设置 User-Agent 时，使用配置中的或者默认的 User-Agent
+        if !headers.contains_key(header::USER_AGENT) {
+            let user_agent = self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+            headers.insert(header::USER_AGENT, HeaderValue::from_str(user_agent)?);
+        }
+
+        // 如果headers中没有设置Content-Type，则设置为application/json，使用 protobuf 时为 application/x-protobuf
         if !headers.contains_key(header::CONTENT_TYPE) {
-            headers.insert(
-                header::CONTENT_TYPE,
-                HeaderValue::from_static("application/json"),
-            );
+            let content_type = match &self.protobuf {
+                Some(ProtobufProfile {
+                    request: Some(_), ..
+                }) => PROTOBUF_CONTENT_TYPE,
+                _ => "application/json",
+            };
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+
+        if self.idempotency_key && !headers.contains_key("idempotency-key") {
+            let key = match &args.idempotency_key {
+                Some(key) => key.clone(),
+                None => uuid::Uuid::new_v4().to_string(),
+            };
+            headers.insert("idempotency-key", HeaderValue::from_str(&key)?);
+        }
+
+        // 渲染 params 和 body 中的模板函数
+        let mut template = Template::new(args.seed);
+        template.render_value(&mut query)?;
+        template.render_value(&mut body)?;
+
+        if let Some(auth) = &self.auth {
+            auth.apply(&mut headers, &mut query)?;
         }
 
         for (k, v) in &args.query {
@@ -156,7 +615,22 @@ impl RequestProfile {
         }
 
         for (k, v) in &args.body {
-            body[k] = v.parse()?;
+            let v: serde_json::Value = v.parse()?;
+            match body.as_array_mut() {
+                // NDJSON 的 body 是数组，覆盖每一条记录
+                Some(records) => records
+                    .iter_mut()
+                    .filter(|record| record.is_object())
+                    .for_each(|record| record[k] = v.clone()),
+                None => body[k] = v,
+            }
+        }
+
+        // 配置了 protobuf 请求消息时，按消息定义编码 body
+        if let Some(protobuf) = &self.protobuf {
+            if let Some(body) = protobuf.encode_request(&body)? {
+                return Ok((headers, query, body));
+            }
         }
 
         // 根据不同的 content type，将body序列化(serialize)为不同的格式
@@ -164,13 +638,30 @@ impl RequestProfile {
         let content_type = get_content_type(&headers);
         match content_type.as_deref() {
             Some("application/json") => {
-                let body = serde_json::to_string(&body)?;
+                let body = serde_json::to_vec(&body)?;
                 Ok((headers, query, body))
             }
             Some("application/x-www-form-urlencoded" | "multipart/form-data") => {
                 let body = serde_urlencoded::to_string(&body)?;
+                Ok((headers, query, body.into_bytes()))
+            }
+            Some(ct) if is_msgpack(ct) => {
+                let body = rmp_serde::to_vec_named(&body)?;
                 Ok((headers, query, body))
             }
+            Some(NDJSON_CONTENT_TYPE) => {
+                // 数组中的每个元素序列化为一行 JSON，每行以换行结尾
+                let records = match body {
+                    serde_json::Value::Array(records) => records,
+                    serde_json::Value::Object(map) if map.is_empty() => vec![],
+                    record => vec![record],
+                };
+                let mut body = String::new();
+                for record in records {
+                    writeln!(&mut body, "{}", serde_json::to_string(&record)?)?;
+                }
+                Ok((headers, query, body.into_bytes()))
+            }
             _ => Err(anyhow::anyhow!(
                 "Unsupported content type`不支持的内容类型 {:?}`",
                 content_type
@@ -222,6 +713,13 @@ impl FromStr for RequestProfile {
 
 impl ConfigValidate for RequestProfile {
     fn validate(&self) -> Result<()> {
+        self.validate_params()?;
+        self.validate_body()
+    }
+}
+
+impl RequestProfile {
+    pub(crate) fn validate_params(&self) -> Result<()> {
         if let Some(param) = self.params.as_ref() {
             if !param.is_object() {
                 // params 必须是 Object 对象,得到错误 yml 配置选项
@@ -231,9 +729,14 @@ impl ConfigValidate for RequestProfile {
                 ));
             }
         }
+        Ok(())
+    }
+
+    pub(crate) fn validate_body(&self) -> Result<()> {
         if let Some(body) = self.body.as_ref() {
-            // body 必须是 Object 对象
-            if !body.is_object() {
+            // body 必须是 Object 对象，NDJSON 的 body 可以是数组
+            let ndjson = get_content_type(&self.headers).as_deref() == Some(NDJSON_CONTENT_TYPE);
+            if !(body.is_object() || ndjson && body.is_array()) {
                 return Err(anyhow::anyhow!(
                     "Body must be an object but got: \n{}\n",
                     serde_yaml::to_string(body).unwrap()
@@ -247,43 +750,266 @@ impl ConfigValidate for RequestProfile {
 
 impl ResponseExt {
     pub fn into_inner(self) -> Response {
-        self.0
+        self.res
+    }
+
+    /// 从发送请求到收到响应头的时间
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
     }
 
     // 为 Response 对象添加一个获取文本的方法，该方法接受一个 ResponseProfile 对象并返回一个字符串
     pub async fn get_text(self, profile: &ResponseProfile) -> Result<String> {
-        // 获取 Response 对象
-        let res = self.0;
-        // 获取响应字符串
+        self.get_parts(profile).await?.to_text(profile)
+    }
 
-        let mut output = String::new();
-        let status = get_status_text(&res);
-        let header = get_heardes_text(&res, &profile.skip_headers)?;
-        let body = get_body_text(res, &profile.skip_body).await?;
-        writeln!(&mut output, "{}\n{}\n{}", status, header, body)?;
+    /// 获取过滤后的状态行、响应头和响应体
+    pub async fn get_parts(self, profile: &ResponseProfile) -> Result<ResponseParts> {
+        let status = get_status_text(&self.res);
+        let status_code = self.res.status().as_u16();
+        let header_map = self.res.headers().clone();
+        let request = profile
+            .include_request
+            .as_ref()
+            .map(|include| include.render(&self.request));
+        let headers = get_heardes_text(&self.res, profile)?;
+        let compressed = compression::content_encoding(self.res.headers()).is_some();
+        let warnings = charset_warning(self.res.headers()).into_iter().collect();
+        let start = Instant::now();
+        let elapsed = self.elapsed;
+        let body = self.get_body(profile).await?;
+        Ok(ResponseParts {
+            elapsed: elapsed + start.elapsed(),
+            request,
+            status,
+            status_code,
+            header_map,
+            headers,
+            body,
+            compressed,
+            warnings,
+        })
+    }
+
+    /// 获取过滤后的响应体，配置了 protobuf 响应消息时先解码为 JSON
+    pub async fn get_body(self, profile: &ResponseProfile) -> Result<ResponseBody> {
+        match self.protobuf {
+            Some(descriptor) => {
+                let body = read_bytes(self.res, profile, self.body_size).await?;
+                if body.truncated {
+                    return Ok(ResponseBody::Text(body.marker()));
+                }
+                let text = protobuf::decode_message(descriptor, &body.data.into_bytes()?)?;
+                format_body(Some("application/json"), &text, profile)
+            }
+            None => read_body(self.res, profile, self.body_size).await,
+        }
+    }
 
-        Ok(output)
+    pub async fn get_body_text(self, profile: &ResponseProfile) -> Result<String> {
+        Ok(self.get_body(profile).await?.to_string())
     }
 
     pub fn get_headers_keys(&self) -> Vec<String> {
-        let res = &self.0;
+        let res = &self.res;
         let headers = res.headers();
         headers.iter().map(|(k, _)| k.to_string()).collect()
     }
 }
 
-// 过滤 JSON 字符串，返回过滤后的字符串
-fn filter_json(text: &str, skip: &[String]) -> Result<String> {
+/// 过滤后的响应，比较前两个响应可以相互参照，例如数值误差
+#[derive(Debug, Clone)]
+pub struct ResponseParts {
+    // 配置了 include_request 时输出的请求
+    pub request: Option<String>,
+    pub status: String,
+    pub status_code: u16,
+    // 未经过滤的响应头
+    pub header_map: HeaderMap,
+    pub headers: String,
+    pub body: ResponseBody,
+    // 响应体是否经过压缩
+    pub compressed: bool,
+    // 包括读取响应体在内的响应时间
+    pub elapsed: Duration,
+    // 不影响比较但需要提示的问题，例如无法识别的 charset
+    pub warnings: Vec<String>,
+}
+
+impl ResponseParts {
+    /// 任意一个响应经过压缩时，两边都去掉 content-encoding 和 content-length，
+    /// 避免一边使用 gzip 另一边使用 brotli 时产生差异
+    pub fn strip_compression_headers(parts1: &mut Self, parts2: &mut Self) {
+        if parts1.compressed || parts2.compressed {
+            for parts in [parts1, parts2] {
+                parts.headers = compression::strip_compression_headers(&parts.headers);
+            }
+        }
+    }
+
+    /// 和另一个响应相比值不同、新增或缺少的响应头名称，按名称排序
+    pub fn changed_headers(&self, other: &Self) -> Vec<String> {
+        let (headers1, headers2) = (header_lines(&self.headers), header_lines(&other.headers));
+        let names: BTreeSet<&String> = headers1.keys().chain(headers2.keys()).collect();
+        names
+            .into_iter()
+            .filter(|name| headers1.get(*name) != headers2.get(*name))
+            .cloned()
+            .collect()
+    }
+
+    /// 按名称比较两个响应的响应头，只输出新增、删除和修改的响应头
+    pub fn diff_headers(&self, other: &Self) -> Result<String> {
+        let joined = |text| -> BTreeMap<String, String> {
+            header_lines(text)
+                .into_iter()
+                .map(|(name, values)| (name, values.join(", ")))
+                .collect()
+        };
+        diff_header_map(&joined(&self.headers), &joined(&other.headers))
+    }
+
+    /// 只保留响应的一部分，其他部分不参与比较
+    pub fn retain(&mut self, part: ResponsePart) {
+        if part != ResponsePart::Status {
+            self.status.clear();
+        }
+        if part != ResponsePart::Headers {
+            self.headers.clear();
+        }
+        if part != ResponsePart::Body {
+            self.body = ResponseBody::Text(String::new());
+        }
+        self.request = None;
+    }
+
+    /// 生成用于比较的文本
+    pub fn to_text(&self, profile: &ResponseProfile) -> Result<String> {
+        let mut output = String::new();
+        self.write_head(&mut output, profile)?;
+        let body = normalize::mask_text(&self.body.to_string(), &profile.mask)?;
+        writeln!(&mut output, "{}", body)?;
+        Ok(normalize::normalize_whitespace(
+            &output,
+            profile.trim_whitespace,
+            profile.collapse_whitespace,
+        ))
+    }
+
+    /// 不含响应体的文本：请求、状态行和响应头
+    pub fn head_text(&self, profile: &ResponseProfile) -> Result<String> {
+        let mut output = String::new();
+        self.write_head(&mut output, profile)?;
+        Ok(normalize::normalize_whitespace(
+            &output,
+            profile.trim_whitespace,
+            profile.collapse_whitespace,
+        ))
+    }
+
+    /// 经过 mask 处理的 JSON 响应体，不是 JSON 或 mask 后不再是合法 JSON 时返回 None
+    pub fn json_body(&self, profile: &ResponseProfile) -> Result<Option<serde_json::Value>> {
+        let ResponseBody::Json(json) = &self.body else {
+            return Ok(None);
+        };
+        if profile.mask.is_empty() {
+            return Ok(Some(json.clone()));
+        }
+        let text = normalize::mask_text(&self.body.to_string(), &profile.mask)?;
+        Ok(serde_json::from_str(&text).ok())
+    }
+
+    fn write_head(&self, output: &mut String, profile: &ResponseProfile) -> Result<()> {
+        let headers = match profile.lowercase_header_values {
+            true => self.headers.to_lowercase(),
+            false => self.headers.clone(),
+        };
+        if let Some(request) = &self.request {
+            writeln!(output, "{}", request)?;
+        }
+        // retain 之后状态行和响应头可能为空
+        if profile.status != StatusPolicy::Ignore && !self.status.is_empty() {
+            writeln!(output, "{}", self.status)?;
+        }
+        if !headers.is_empty() {
+            writeln!(output, "{}", headers)?;
+        }
+        Ok(())
+    }
+}
+
+/// 响应体，JSON 会被解析以便按字段处理
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseBody {
+    Json(serde_json::Value),
+    Text(String),
+    // 需要和另一个响应一起比较的图片
+    Image(Vec<u8>),
+}
+
+impl std::fmt::Display for ResponseBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ResponseBody::Json(json) => {
+                let text = serde_json::to_string_pretty(json).map_err(|_| std::fmt::Error)?;
+                writeln!(f, "{}", text)
+            }
+            // 304 等响应没有 body
+            ResponseBody::Text(text) if text.is_empty() => std::fmt::Result::Ok(()),
+            ResponseBody::Text(text) => writeln!(f, "{}", text),
+            ResponseBody::Image(bytes) => {
+                write!(f, "{}", binary::format_binary(bytes, BinaryMode::Summary))
+            }
+        }
+    }
+}
+
+// 过滤 JSON 字符串，返回过滤后的 JSON
+fn filter_json(text: &str, profile: &ResponseProfile) -> Result<serde_json::Value> {
     // 将 JSON 字符串解析为 serde_json::Value 对象
-    let mut json: serde_json::Value = serde_json::from_str(text)?;
+    let json: serde_json::Value = serde_json::from_str(text)?;
+    filter_value(json, profile)
+}
 
-    if let serde_json::Value::Object(ref mut map) = json {
-        // 对 JSON 对象进行过滤，去除指定的键值对
-        for k in skip {
-            map.remove(k);
+// 按 profile 过滤已经解析的 JSON
+fn filter_value(
+    mut json: serde_json::Value,
+    profile: &ResponseProfile,
+) -> Result<serde_json::Value> {
+    // 只比较选中的部分，之后的规则中的路径都相对于选中的部分
+    if let Some(select) = &profile.select {
+        let mut values = JsonPath::parse(select)?.select(&json);
+        json = match values.len() {
+            1 => values.remove(0).clone(),
+            _ => serde_json::Value::Array(values.into_iter().cloned().collect()),
+        };
+    }
+
+    for k in &profile.skip_body {
+        // `$.a[*].b` 或 `/a/b` 形式的路径可以删除嵌套的字段，字段名删除任意层级的同名字段
+        // redact_body 时保留字段，只替换值
+        match (JsonPath::is_path(k), profile.redact_body) {
+            (true, false) => JsonPath::parse(k)?.remove(&mut json),
+            (true, true) => JsonPath::parse(k)?.for_each_mut(&mut json, |v| {
+                *v = serde_json::Value::String(normalize::REDACTED.to_string())
+            }),
+            (false, false) => normalize::remove_key(&mut json, k),
+            (false, true) => normalize::redact_key(&mut json, k),
         }
     }
-    Ok(serde_json::to_string_pretty(&json)?)
+    for rule in &profile.timestamps {
+        rule.apply(&mut json)?;
+    }
+    // 默认按字段名排序，字段顺序不同但内容相同的响应不会产生差异
+    if !profile.preserve_key_order {
+        normalize::sort_keys(&mut json);
+    }
+    profile.sort_arrays.apply(&mut json)?;
+    normalize::apply_presets(&mut json, &profile.normalize);
+    if let Some(match_by) = &profile.match_by {
+        match_by.apply(&mut json)?;
+    }
+    Ok(json)
 }
 
 /// 获取响应的 content type
@@ -305,32 +1031,261 @@ pub fn get_status_text(res: &Response) -> String {
 }
 
 // 获取响应头的文本表示
-pub fn get_heardes_text(res: &Response, skip_headers: &[String]) -> Result<String> {
+// only_headers 不为空时只输出匹配的响应头
+pub fn get_heardes_text(res: &Response, profile: &ResponseProfile) -> Result<String> {
     let mut output = String::new();
 
     let headers = res.headers();
+    let skip_headers = normalize::header_patterns(&profile.skip_headers)?;
+    // only_headers 不为空时只输出匹配的响应头
+    let only_headers = normalize::header_patterns(&profile.only_headers)?;
+    let mut lines: Vec<(String, HeaderValue)> = vec![];
+    let mut cookies = vec![];
     // 输出所有非过滤的响应头
     for (h_name, h_value) in headers {
-        if !skip_headers.contains(&h_name.to_string()) {
-            writeln!(&mut output, "{}: {:?}", h_name, h_value)?;
+        let name = h_name.as_str();
+        let included = only_headers.is_empty() || only_headers.iter().any(|re| re.is_match(name));
+        if !included || skip_headers.iter().any(|re| re.is_match(name)) {
+            continue;
+        }
+        match (&profile.set_cookie, h_value.to_str()) {
+            // set-cookie 规范化后按 cookie 名称排序输出
+            (Some(cookie), std::result::Result::Ok(value)) if h_name == header::SET_COOKIE => {
+                cookies.push(cookie.canonicalize(value));
+            }
+            _ => lines.push((name.to_string(), h_value.clone())),
         }
     }
+    if !profile.preserve_header_order {
+        lines = merge_headers(lines);
+    }
+    for (name, value) in lines {
+        writeln!(&mut output, "{}: {:?}", name, value)?;
+    }
+    cookies.sort();
+    for (_, cookie) in cookies {
+        writeln!(&mut output, "{}: {:?}", header::SET_COOKIE, cookie)?;
+    }
     writeln!(&mut output)?;
     Ok(output)
 }
 
-pub async fn get_body_text(res: Response, skip_headers: &[String]) -> Result<String> {
-    let mut output = String::new();
+/// 把 `name: value` 格式的响应头文本解析回名称和值，名称转为小写，同名的值按出现顺序保留
+fn header_lines(text: &str) -> BTreeMap<String, Vec<&str>> {
+    let mut headers: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (name, value) in text.lines().filter_map(|line| line.split_once(": ")) {
+        headers.entry(name.to_lowercase()).or_default().push(value);
+    }
+    headers
+}
+
+/// 按名称排序响应头，同名的多个值合并为一行，用 `, ` 分隔；set-cookie 不能合并，每个值各占一行
+fn merge_headers(lines: Vec<(String, HeaderValue)>) -> Vec<(String, HeaderValue)> {
+    let mut merged: BTreeMap<String, Vec<HeaderValue>> = BTreeMap::new();
+    for (name, value) in lines {
+        merged.entry(name).or_default().push(value);
+    }
+    let mut lines = vec![];
+    for (name, mut values) in merged {
+        if name == header::SET_COOKIE.as_str() {
+            values.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+            lines.extend(values.into_iter().map(|v| (name.clone(), v)));
+            continue;
+        }
+        let joined = values
+            .iter()
+            .map(HeaderValue::as_bytes)
+            .collect::<Vec<_>>()
+            .join(&b", "[..]);
+        // 合并后的值一定是合法的响应头，失败时保留第一个值
+        let value = HeaderValue::from_bytes(&joined).unwrap_or_else(|_| values.remove(0));
+        lines.push((name, value));
+    }
+    lines
+}
+
+pub async fn get_body_text(res: Response, profile: &ResponseProfile) -> Result<String> {
+    Ok(get_body(res, profile).await?.to_string())
+}
+
+/// 获取过滤后的响应体
+pub async fn get_body(res: Response, profile: &ResponseProfile) -> Result<ResponseBody> {
+    read_body(res, profile, None).await
+}
+
+// size 是录制时被截断的响应体的实际大小，读取完整的响应时为 None
+async fn read_body(
+    res: Response,
+    profile: &ResponseProfile,
+    size: Option<usize>,
+) -> Result<ResponseBody> {
     let content_type = get_content_type(res.headers());
-    let text = res.text().await?;
-    match content_type.as_deref() {
-        Some("application/json") => {
-            let text = filter_json(&text, skip_headers)?;
-            writeln!(&mut output, "{}", text)?;
+    let charset = get_charset(res.headers()).or_else(|| profile.charset.clone());
+    // 事件流按数量或者时间读取，事件组成数组后按 JSON 的规则过滤
+    if content_type.as_deref() == Some(SSE_CONTENT_TYPE) {
+        let events = profile.sse.clone().unwrap_or_default().capture(res).await?;
+        let json = filter_value(serde_json::Value::Array(events), profile)?;
+        return Ok(ResponseBody::Json(json));
+    }
+    let mut body = read_bytes(res, profile, size).await?;
+    let is_binary = content_type.as_deref().is_some_and(binary::is_binary);
+    // 被截断的响应体无法解析，按原样输出并标明实际大小
+    if body.truncated {
+        let marker = body.marker();
+        let bytes = body.data.into_bytes()?;
+        let mut text = match is_binary {
+            true => binary::format_binary(&bytes, profile.binary),
+            false => decode_text(&bytes, charset.as_deref()),
+        };
+        if !text.ends_with('\n') {
+            text.push('\n');
         }
-        _ => {
-            writeln!(&mut output, "{}", text)?;
+        text.push_str(&marker);
+        return Ok(ResponseBody::Text(text));
+    }
+    // MessagePack 解码为 JSON 后再过滤
+    if content_type.as_deref().is_some_and(is_msgpack) {
+        if body.data.len() == 0 {
+            return Ok(ResponseBody::Text(String::new()));
         }
+        let json: serde_json::Value = rmp_serde::from_read(body.data.reader()?)?;
+        return Ok(ResponseBody::Json(filter_value(json, profile)?));
+    }
+    // 图片在两个响应都返回后再比较像素
+    if profile.image.is_some()
+        && content_type
+            .as_deref()
+            .is_some_and(|ct| ct.starts_with("image/"))
+    {
+        return Ok(ResponseBody::Image(body.data.into_bytes()?));
+    }
+    // 二进制内容只比较摘要，不通过 text() 输出乱码
+    if is_binary {
+        return Ok(ResponseBody::Text(binary::format_binary(
+            &body.data.into_bytes()?,
+            profile.binary,
+        )));
+    }
+    let text = decode_text(&body.data.into_bytes()?, charset.as_deref());
+    format_body(content_type.as_deref(), &text, profile)
+}
+
+/// 获取 content type 中的 charset 参数
+fn get_charset(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// 响应头中的 charset 无法识别时的警告，例如 `charset=binary`
+fn charset_warning(headers: &HeaderMap) -> Option<String> {
+    let charset = get_charset(headers)?;
+    encoding_rs::Encoding::for_label(charset.as_bytes())
+        .is_none()
+        .then(|| {
+            format!(
+                "unsupported charset, decoded as UTF-8`不支持的字符集，按 UTF-8 解码: {}",
+                charset
+            )
+        })
+}
+
+/// 按 charset 把响应体解码为文本，没有 charset 或无法识别时使用 UTF-8，无效的字节被替换；BOM 优先
+fn decode_text(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// 读取的响应体
+struct BodyBytes {
+    // 解压后的内容，超过 max_body_bytes 的部分已被丢弃
+    data: spool::Spool,
+    // 实际传输的字节数
+    size: usize,
+    compressed: bool,
+    truncated: bool,
+}
+
+impl BodyBytes {
+    /// 截断标记，显示保留的大小和实际大小
+    fn marker(&self) -> String {
+        format!(
+            "... truncated`已截断: showing {} of {}{}\n",
+            binary::human_size(self.data.len()),
+            binary::human_size(self.size),
+            if self.compressed { " (compressed)" } else { "" }
+        )
+    }
+}
+
+/// 读取响应体，按 Content-Encoding 解压 \
+/// 设置了 max_body_bytes 时最多保留 max_body_bytes 字节，剩余的部分只统计大小，不会全部读入内存 \
+/// size 是录制时被截断的响应体的实际大小，此时读到的内容不完整
+async fn read_bytes(
+    mut res: Response,
+    profile: &ResponseProfile,
+    size: Option<usize>,
+) -> Result<BodyBytes> {
+    let encoding = compression::content_encoding(res.headers());
+    let limit = profile.max_body_bytes;
+    let mut raw = spool::Spool::new(limit);
+    while let Some(chunk) = res.chunk().await? {
+        raw.write(&chunk)?;
+    }
+    let size = size.unwrap_or(raw.size()).max(raw.size());
+    let truncated = raw.truncated() || size > raw.len();
+    let Some(encoding) = encoding else {
+        return Ok(BodyBytes {
+            truncated,
+            data: raw,
+            size,
+            compressed: false,
+        });
+    };
+    // 截断的压缩数据无法完整解压，保留已经解压出的部分
+    let partial = truncated;
+    let mut data = spool::Spool::new(limit);
+    data.copy_from(compression::decoder(&encoding, raw.reader()?)?, partial)?;
+    Ok(BodyBytes {
+        truncated: partial || data.truncated(),
+        data,
+        size,
+        compressed: true,
+    })
+}
+
+/// 是否为 XML 的 content type
+fn is_xml(content_type: &str) -> bool {
+    matches!(content_type, "application/xml" | "text/xml") || content_type.ends_with("+xml")
+}
+
+/// 是否为 MessagePack 的 content type
+fn is_msgpack(content_type: &str) -> bool {
+    matches!(
+        content_type,
+        "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack"
+    )
+}
+
+// 根据 content type 选择格式化器，过滤并格式化响应体
+fn format_body(
+    content_type: Option<&str>,
+    text: &str,
+    profile: &ResponseProfile,
+) -> Result<ResponseBody> {
+    // 304 等响应没有 body，不需要过滤
+    if text.is_empty() {
+        return Ok(ResponseBody::Text(String::new()));
+    }
+    match content_type.and_then(formatter::find_formatter) {
+        Some(formatter) => formatter.format(text, profile),
+        None => Ok(ResponseBody::Text(text.to_string())),
     }
-    Ok(output)
 }
@@ -0,0 +1,620 @@
+use crate::JsonPath;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, SecondsFormat, TimeZone};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+};
+
+/// redact_body 时替换被跳过字段的值
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// 用正则表达式替换响应体中的内容，例如请求 ID、trace ID \
+/// `replacement` 中可以使用 `$1` 引用分组
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MaskRule {
+    pub pattern: String,
+    #[serde(default = "default_replacement")]
+    pub replacement: String,
+}
+
+fn default_replacement() -> String {
+    "<masked>".to_string()
+}
+
+impl MaskRule {
+    pub fn new(pattern: impl Into<String>, replacement: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+        }
+    }
+
+    fn regex(&self) -> Result<Regex> {
+        Regex::new(&self.pattern).map_err(|e| {
+            anyhow!(
+                "Invalid mask pattern`无效的正则表达式 {}: {}",
+                self.pattern,
+                e
+            )
+        })
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        self.regex().map(|_| ())
+    }
+}
+
+/// 依次应用所有的替换规则
+pub(crate) fn mask_text(text: &str, rules: &[MaskRule]) -> Result<String> {
+    let mut text = text.to_string();
+    for rule in rules {
+        text = rule
+            .regex()?
+            .replace_all(&text, rule.replacement.as_str())
+            .into_owned();
+    }
+    Ok(text)
+}
+
+/// 时间戳规范化规则，`path` 指向的 ISO-8601 字符串或者 epoch 数字 \
+/// 设置了 `granularity` 时按粒度向下取整，否则替换为 `placeholder`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TimestampRule {
+    pub path: String,
+    // 取整粒度，例如 `500ms`、`1s`、`5m`、`1h`、`1d`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub granularity: Option<String>,
+    #[serde(default = "default_timestamp_placeholder")]
+    pub placeholder: String,
+}
+
+fn default_timestamp_placeholder() -> String {
+    "<timestamp>".to_string()
+}
+
+// 小于这个值的 epoch 认为是秒，否则是毫秒
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e11;
+
+impl TimestampRule {
+    pub fn new(path: impl Into<String>, granularity: Option<String>) -> Self {
+        Self {
+            path: path.into(),
+            granularity,
+            placeholder: default_timestamp_placeholder(),
+        }
+    }
+
+    // 取整粒度(毫秒)
+    fn granularity_ms(&self) -> Result<Option<i64>> {
+        let Some(granularity) = &self.granularity else {
+            return Ok(None);
+        };
+        let invalid = || anyhow!("Invalid granularity`无效的时间粒度: {}", granularity);
+        let granularity = granularity.trim();
+        let split = granularity
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(granularity.len());
+        let (num, unit) = granularity.split_at(split);
+        let num: i64 = num.parse().map_err(|_| invalid())?;
+        let unit = match unit.trim() {
+            "ms" => 1,
+            "" | "s" => 1000,
+            "m" => 60 * 1000,
+            "h" => 60 * 60 * 1000,
+            "d" => 24 * 60 * 60 * 1000,
+            _ => return Err(invalid()),
+        };
+        if num <= 0 {
+            return Err(invalid());
+        }
+        Ok(Some(num * unit))
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        JsonPath::parse(&self.path)?;
+        self.granularity_ms()?;
+        Ok(())
+    }
+
+    /// 规范化所有匹配的时间戳，无法识别为时间戳的值保持不变
+    pub(crate) fn apply(&self, json: &mut Value) -> Result<()> {
+        let granularity = self.granularity_ms()?;
+        JsonPath::parse(&self.path)?.for_each_mut(json, |value| {
+            if let Some(normalized) = self.normalize(value, granularity) {
+                *value = normalized;
+            }
+        });
+        Ok(())
+    }
+
+    fn normalize(&self, value: &Value, granularity: Option<i64>) -> Option<Value> {
+        let floor = |ms: i64, g: i64| ms - ms.rem_euclid(g);
+        match value {
+            Value::String(s) => {
+                let dt = parse_datetime(s)?;
+                let Some(g) = granularity else {
+                    return Some(self.placeholder.clone().into());
+                };
+                let ms = floor(dt.timestamp_millis(), g);
+                let rounded = DateTime::from_timestamp_millis(ms)?.with_timezone(dt.offset());
+                Some(rounded.to_rfc3339_opts(SecondsFormat::AutoSi, true).into())
+            }
+            Value::Number(n) => {
+                let epoch = n.as_f64()?;
+                let Some(g) = granularity else {
+                    return Some(self.placeholder.clone().into());
+                };
+                // 秒级的 epoch 按秒取整，粒度小于 1 秒时保持不变
+                let (ms, scale) = if epoch.abs() < EPOCH_MILLIS_THRESHOLD {
+                    ((epoch * 1000.0) as i64, 1000)
+                } else {
+                    (epoch as i64, 1)
+                };
+                Some((floor(ms, g.max(scale)) / scale).into())
+            }
+            _ => None,
+        }
+    }
+}
+
+// 解析 RFC 3339 或者不带时区的 ISO-8601 时间，不带时区时按 UTC 处理
+fn parse_datetime(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(s).ok().or_else(|| {
+        ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+            .iter()
+            .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+            .map(|dt| FixedOffset::east_opt(0).unwrap().from_utc_datetime(&dt))
+    })
+}
+
+/// 内置的规范化预设，把 ID 类的值替换为按出现顺序编号的占位符 \
+/// 同一个值始终得到同一个占位符，所以值之间的引用关系仍然可以比较
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizePreset {
+    /// 字符串中 UUID 形式的内容，替换为 `<uuid:1>`
+    Uuids,
+    /// `id`、`xxx_id`、`xxxId` 字段中的整数，替换为 `<id:1>`
+    NumericIds,
+}
+
+/// 按顺序应用所有的预设
+pub(crate) fn apply_presets(json: &mut Value, presets: &[NormalizePreset]) {
+    let re = Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b")
+        .unwrap();
+    for preset in presets {
+        let mut placeholders = Placeholders::default();
+        match preset {
+            NormalizePreset::Uuids => {
+                walk_strings(json, &mut |s| {
+                    if re.is_match(s) {
+                        *s = re
+                            .replace_all(s, |caps: &regex::Captures| {
+                                placeholders.get("uuid", &caps[0].to_ascii_lowercase())
+                            })
+                            .into_owned();
+                    }
+                });
+            }
+            NormalizePreset::NumericIds => mask_numeric_ids(json, None, &mut placeholders),
+        }
+    }
+}
+
+// 每个不同的值按出现顺序分配一个编号
+#[derive(Default)]
+struct Placeholders {
+    seen: HashMap<String, usize>,
+}
+
+impl Placeholders {
+    fn get(&mut self, kind: &str, value: &str) -> String {
+        let next = self.seen.len() + 1;
+        let n = *self.seen.entry(value.to_string()).or_insert(next);
+        format!("<{}:{}>", kind, n)
+    }
+}
+
+fn walk_strings(json: &mut Value, f: &mut impl FnMut(&mut String)) {
+    match json {
+        Value::String(s) => f(s),
+        Value::Array(items) => items.iter_mut().for_each(|v| walk_strings(v, f)),
+        Value::Object(map) => map.values_mut().for_each(|v| walk_strings(v, f)),
+        _ => {}
+    }
+}
+
+// `id`、`user_id`、`userId` 以及复数形式 `ids`、`user_ids`
+fn is_id_key(key: &str) -> bool {
+    let key = key.strip_suffix('s').unwrap_or(key);
+    key.eq_ignore_ascii_case("id")
+        || key.ends_with("_id")
+        || key.ends_with("Id")
+        || key.ends_with("ID")
+}
+
+fn mask_numeric_ids(json: &mut Value, key: Option<&str>, placeholders: &mut Placeholders) {
+    let is_id = key.is_some_and(is_id_key);
+    match json {
+        Value::Number(n) if is_id && (n.is_u64() || n.is_i64()) => {
+            *json = placeholders.get("id", &n.to_string()).into();
+        }
+        Value::String(s) if is_id && !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => {
+            *json = placeholders.get("id", s).into();
+        }
+        // `ids: [1, 2, 3]` 这样的数组同样按 ID 处理
+        Value::Array(items) => {
+            for item in items {
+                mask_numeric_ids(item, key, placeholders);
+            }
+        }
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                mask_numeric_ids(v, Some(k), placeholders);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 数值误差：`path` 匹配到的数值在两个响应中相差不超过 `epsilon` 时视为相等 \
+/// 相等时把第二个响应中的值替换为第一个响应中的值
+pub(crate) fn apply_tolerance(json1: &Value, json2: &mut Value, path: &JsonPath, epsilon: f64) {
+    for pointer in path.find_pointers(json1) {
+        let Some(expected) = json1.pointer(&pointer).filter(|v| v.is_number()) else {
+            continue;
+        };
+        let Some(value) = json2.pointer_mut(&pointer) else {
+            continue;
+        };
+        let close = match (expected.as_f64(), value.as_f64()) {
+            (Some(a), Some(b)) => (a - b).abs() <= epsilon,
+            _ => false,
+        };
+        if close {
+            *value = expected.clone();
+        }
+    }
+}
+
+/// 比较前对数组排序：`sort_arrays: true` 排序所有数组，
+/// 或者指定路径 `sort_arrays: ["$.tags", { path: "$.items", by: id }]`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum SortArrays {
+    All(bool),
+    Paths(Vec<SortRule>),
+}
+
+impl Default for SortArrays {
+    fn default() -> Self {
+        SortArrays::All(false)
+    }
+}
+
+/// 需要排序的数组，`by` 为元素中用于排序的字段名或 JSON pointer
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum SortRule {
+    Path(String),
+    Keyed {
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        by: Option<String>,
+    },
+}
+
+impl SortRule {
+    fn parts(&self) -> (&str, Option<&str>) {
+        match self {
+            SortRule::Path(path) => (path, None),
+            SortRule::Keyed { path, by } => (path, by.as_deref()),
+        }
+    }
+}
+
+impl SortArrays {
+    pub(crate) fn validate(&self) -> Result<()> {
+        if let SortArrays::Paths(rules) = self {
+            for rule in rules {
+                JsonPath::parse(rule.parts().0)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply(&self, json: &mut Value) -> Result<()> {
+        match self {
+            SortArrays::All(false) => {}
+            SortArrays::All(true) => sort_all(json),
+            SortArrays::Paths(rules) => {
+                for rule in rules {
+                    let (path, by) = rule.parts();
+                    JsonPath::parse(path)?.for_each_mut(json, |value| {
+                        if let Value::Array(items) = value {
+                            sort_items(items, by);
+                        }
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// 先排序内层的数组，这样外层按整个元素比较时结果是稳定的
+fn sort_all(json: &mut Value) {
+    match json {
+        Value::Array(items) => {
+            items.iter_mut().for_each(sort_all);
+            sort_items(items, None);
+        }
+        Value::Object(map) => map.values_mut().for_each(sort_all),
+        _ => {}
+    }
+}
+
+fn sort_items(items: &mut [Value], by: Option<&str>) {
+    let key = |v: &Value| -> Value {
+        match by {
+            Some(by) if by.starts_with('/') => v.pointer(by).cloned().unwrap_or(Value::Null),
+            Some(by) => v.get(by).cloned().unwrap_or(Value::Null),
+            None => v.clone(),
+        }
+    };
+    items.sort_by(|a, b| compare_values(&key(a), &key(b)));
+}
+
+/// JSON 值的全序：null < bool < number < string < array < object
+pub(crate) fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| compare_values(a, b))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(_), Value::Object(_)) => a.to_string().cmp(&b.to_string()),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// 递归地删除任意层级的对象中名为 key 的字段，包括数组中的对象
+pub(crate) fn remove_key(json: &mut Value, key: &str) {
+    match json {
+        Value::Array(items) => items.iter_mut().for_each(|v| remove_key(v, key)),
+        Value::Object(map) => {
+            map.shift_remove(key);
+            map.values_mut().for_each(|v| remove_key(v, key));
+        }
+        _ => {}
+    }
+}
+
+/// 递归地把任意层级的对象中名为 key 的字段替换为 `<redacted>`
+pub(crate) fn redact_key(json: &mut Value, key: &str) {
+    match json {
+        Value::Array(items) => items.iter_mut().for_each(|v| redact_key(v, key)),
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                match k == key {
+                    true => *v = Value::String(REDACTED.to_string()),
+                    false => redact_key(v, key),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 递归地按字段名排序 JSON 对象
+pub(crate) fn sort_keys(json: &mut Value) {
+    match json {
+        Value::Array(items) => items.iter_mut().for_each(sort_keys),
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (k, mut v) in entries {
+                sort_keys(&mut v);
+                map.insert(k, v);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 按标识字段配对数组中的对象：`match_by: id` 作用于所有数组，
+/// 或者指定路径 `match_by: { "$.items": sku }` \
+/// 数组会被转换为以 `id=42` 为键的对象，两个响应中相同标识的元素逐个比较，
+/// 一侧多出的元素不会导致后面的元素错位
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum MatchBy {
+    Key(String),
+    Paths(BTreeMap<String, String>),
+}
+
+impl MatchBy {
+    pub(crate) fn validate(&self) -> Result<()> {
+        if let MatchBy::Paths(paths) = self {
+            for path in paths.keys() {
+                JsonPath::parse(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply(&self, json: &mut Value) -> Result<()> {
+        match self {
+            MatchBy::Key(key) => key_all_arrays(json, key),
+            MatchBy::Paths(paths) => {
+                for (path, key) in paths {
+                    JsonPath::parse(path)?.for_each_mut(json, |value| key_array(value, key));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn key_all_arrays(json: &mut Value, key: &str) {
+    match json {
+        Value::Array(items) => items.iter_mut().for_each(|v| key_all_arrays(v, key)),
+        Value::Object(map) => map.values_mut().for_each(|v| key_all_arrays(v, key)),
+        _ => {}
+    }
+    key_array(json, key);
+}
+
+// 只有所有元素都是包含标识字段的对象时才转换
+fn key_array(value: &mut Value, key: &str) {
+    let Value::Array(items) = value else {
+        return;
+    };
+    if items.is_empty() || !items.iter().all(|item| item.get(key).is_some()) {
+        return;
+    }
+    let mut items = std::mem::take(items);
+    items.sort_by(|a, b| compare_values(&a[key], &b[key]));
+    let mut map = serde_json::Map::new();
+    for item in items {
+        let id = match &item[key] {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        };
+        let mut name = format!("{}={}", key, id);
+        // 标识重复时加上序号
+        let mut n = 1;
+        while map.contains_key(&name) {
+            n += 1;
+            name = format!("{}={}#{}", key, id, n);
+        }
+        map.insert(name, item);
+    }
+    *value = Value::Object(map);
+}
+
+/// `preset:volatile` 展开的响应头，每次请求都可能不同
+const VOLATILE_HEADERS: &[&str] = &[
+    "date",
+    "age",
+    "expires",
+    "last-modified",
+    "etag",
+    "set-cookie",
+    "server-timing",
+    "x-request-id",
+    "x-correlation-id",
+    "x-trace-id",
+    "traceparent",
+    "tracestate",
+    "x-amzn-requestid",
+    "x-amzn-trace-id",
+    "x-amz-cf-id",
+    "x-amz-request-id",
+    "cf-ray",
+    "x-served-by",
+    "x-cache",
+    "x-cache-hits",
+    "x-timer",
+    "x-runtime",
+    "x-response-time",
+    "via",
+];
+
+/// 解析响应头规则，`preset:volatile` 展开为内置的易变响应头列表
+pub(crate) fn header_patterns(patterns: &[String]) -> Result<Vec<Regex>> {
+    let mut regexes = vec![];
+    for pattern in patterns {
+        match pattern.strip_prefix("preset:") {
+            Some("volatile") => {
+                for header in VOLATILE_HEADERS {
+                    regexes.push(header_pattern(header)?);
+                }
+            }
+            Some(preset) => {
+                return Err(anyhow!(
+                    "Unknown header preset`未知的响应头预设: {}",
+                    preset
+                ))
+            }
+            None => regexes.push(header_pattern(pattern)?),
+        }
+    }
+    Ok(regexes)
+}
+
+/// 响应头名称的匹配规则，不区分大小写 \
+/// `x-request-*` 为通配符，`/^cf-/` 为正则表达式，其他为完整的名称
+fn header_pattern(pattern: &str) -> Result<Regex> {
+    let regex = match pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+        Some(re) => re.to_string(),
+        None => {
+            let glob = regex::escape(pattern)
+                .replace(r"\*", ".*")
+                .replace(r"\?", ".");
+            format!("^{}$", glob)
+        }
+    };
+    Regex::new(&format!("(?i){}", regex))
+        .map_err(|e| anyhow!("Invalid header pattern`无效的响应头规则 {}: {}", pattern, e))
+}
+
+/// 按行规范化空白字符：去掉行尾空白，把行内连续的空白合并为一个空格(保留行首缩进)
+pub(crate) fn normalize_whitespace(text: &str, trim_trailing: bool, collapse: bool) -> String {
+    if !trim_trailing && !collapse {
+        return text.to_string();
+    }
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (line, newline) = match line.strip_suffix('\n') {
+            Some(line) => (line, "\n"),
+            None => (line, ""),
+        };
+        let mut line = line.to_string();
+        if collapse {
+            let content = line.trim_start();
+            let indent = &line[..line.len() - content.len()];
+            let mut collapsed = indent.to_string();
+            let mut last_space = false;
+            for c in content.chars() {
+                if c.is_whitespace() {
+                    if !last_space {
+                        collapsed.push(' ');
+                    }
+                    last_space = true;
+                } else {
+                    collapsed.push(c);
+                    last_space = false;
+                }
+            }
+            line = collapsed;
+        }
+        if trim_trailing {
+            line.truncate(line.trim_end().len());
+        }
+        output.push_str(&line);
+        output.push_str(newline);
+    }
+    output
+}
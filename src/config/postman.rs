@@ -0,0 +1,313 @@
+use super::{get_content_type, ApiKeyLocation, Auth, DiffConfig, RequestProfile, Secret};
+use anyhow::Result;
+use reqwest::header;
+use serde_json::{json, Value};
+
+const POSTMAN_SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+impl DiffConfig {
+    /// 转换为 Postman collection(v2.1)，每个 profile 是一个目录，其中的请求按 req1、req2... 命名 \
+    /// 模板函数保持原样；环境变量中的密钥转换为同名的 Postman 变量，其他来源的密钥使用占位变量，不会写入文件
+    pub fn to_postman(&self, name: &str) -> Result<Value> {
+        let mut names: Vec<&String> = self.profiles.keys().collect();
+        names.sort();
+        let mut items = vec![];
+        for profile_name in names {
+            let profile = &self.profiles[profile_name];
+            let mut requests = vec![];
+            for (i, req) in profile.requests().into_iter().enumerate() {
+                // 快照代替了 req2，req2 不会发送
+                if i == 1 && profile.snapshot.is_some() {
+                    continue;
+                }
+                requests.push(json!({
+                    "name": format!("req{}", i + 1),
+                    "request": postman_request(req)?,
+                }));
+            }
+            let mut item = json!({ "name": profile_name, "item": requests });
+            if !profile.tags.is_empty() {
+                item["description"] = json!(format!("tags: {}", profile.tags.join(", ")));
+            }
+            items.push(item);
+        }
+        Ok(json!({
+            "info": { "name": name, "schema": POSTMAN_SCHEMA },
+            "item": items,
+        }))
+    }
+}
+
+fn postman_request(req: &RequestProfile) -> Result<Value> {
+    let mut headers = vec![];
+    for (k, v) in &req.headers {
+        headers.push(json!({ "key": k.as_str(), "value": v.to_str()? }));
+    }
+    if let Some(user_agent) = req
+        .user_agent
+        .as_ref()
+        .filter(|_| !req.headers.contains_key(header::USER_AGENT))
+    {
+        headers.push(json!({ "key": "user-agent", "value": user_agent }));
+    }
+    if req.idempotency_key && !req.headers.contains_key("idempotency-key") {
+        headers.push(json!({ "key": "idempotency-key", "value": "{{$guid}}" }));
+    }
+
+    let mut request = json!({
+        "method": req.method.as_str(),
+        "header": headers,
+        "url": postman_url(req),
+    });
+    if let Some(body) = postman_body(req)? {
+        request["body"] = body;
+    }
+    if let Some(auth) = &req.auth {
+        request["auth"] = postman_auth(auth);
+    }
+    Ok(request)
+}
+
+fn postman_url(req: &RequestProfile) -> Value {
+    // URL 中的参数在前，数组参数展开为多个同名参数
+    let mut pairs: Vec<(String, String)> = req.url.query_pairs().into_owned().collect();
+    if let Some(params) = req.params.as_ref().and_then(|p| p.as_object()) {
+        for (k, v) in params {
+            let values = match v {
+                Value::Array(values) => values.iter().collect(),
+                v => vec![v],
+            };
+            pairs.extend(values.into_iter().map(|v| (k.clone(), json_text(v))));
+        }
+    }
+    let mut raw = req.url.clone();
+    raw.set_query(None);
+    if !pairs.is_empty() {
+        raw.query_pairs_mut().extend_pairs(&pairs);
+    }
+    let query: Vec<Value> = pairs
+        .iter()
+        .map(|(k, v)| json!({ "key": k, "value": v }))
+        .collect();
+    let mut url = json!({
+        "raw": raw.as_str(),
+        "protocol": req.url.scheme(),
+        "host": req.url.host_str().unwrap_or_default().split('.').collect::<Vec<_>>(),
+        "path": req.url.path().trim_start_matches('/').split('/').collect::<Vec<_>>(),
+    });
+    if let Some(port) = req.url.port() {
+        url["port"] = json!(port.to_string());
+    }
+    if !query.is_empty() {
+        url["query"] = json!(query);
+    }
+    url
+}
+
+fn postman_body(req: &RequestProfile) -> Result<Option<Value>> {
+    let Some(body) = req
+        .body
+        .as_ref()
+        .filter(|b| !b.is_null() && !b.as_object().is_some_and(|b| b.is_empty()))
+    else {
+        return Ok(None);
+    };
+    let body = match get_content_type(&req.headers).as_deref() {
+        Some("application/x-www-form-urlencoded") => {
+            let fields: Vec<Value> = body
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(k, v)| json!({ "key": k, "value": json_text(v) }))
+                .collect();
+            json!({ "mode": "urlencoded", "urlencoded": fields })
+        }
+        Some(super::NDJSON_CONTENT_TYPE) => {
+            let records = match body {
+                Value::Array(records) => records.clone(),
+                record => vec![record.clone()],
+            };
+            let mut raw = String::new();
+            for record in records {
+                raw.push_str(&serde_json::to_string(&record)?);
+                raw.push('\n');
+            }
+            json!({ "mode": "raw", "raw": raw })
+        }
+        // 其他类型(包括 msgpack 和 protobuf)在 xdiff 中也是以 JSON 编写的
+        _ => json!({
+            "mode": "raw",
+            "raw": serde_json::to_string_pretty(body)?,
+            "options": { "raw": { "language": "json" } },
+        }),
+    };
+    Ok(Some(body))
+}
+
+fn postman_auth(auth: &Auth) -> Value {
+    let pair = |key: &str, value: String| json!({ "key": key, "value": value, "type": "string" });
+    match auth {
+        Auth::Digest { username, password } => json!({
+            "type": "digest",
+            "digest": [
+                pair("username", username.clone()),
+                pair("password", secret_value(password, "password")),
+            ],
+        }),
+        Auth::ApiKey {
+            location,
+            name,
+            value,
+        } => json!({
+            "type": "apikey",
+            "apikey": [
+                pair("key", name.clone()),
+                pair("value", secret_value(value, "api_key")),
+                pair("in", match location {
+                    ApiKeyLocation::Header => "header".to_string(),
+                    ApiKeyLocation::Query => "query".to_string(),
+                }),
+            ],
+        }),
+        // Postman 无法按配置签发 JWT，使用变量中的 token
+        Auth::Jwt { .. } => json!({
+            "type": "bearer",
+            "bearer": [pair("token", "{{jwt_token}}".to_string())],
+        }),
+    }
+}
+
+// 只导出直接写在配置中的密钥
+fn secret_value(secret: &Secret, placeholder: &str) -> String {
+    match secret {
+        Secret::Plain(value) => value.clone(),
+        Secret::Env { env } => format!("{{{{{}}}}}", env),
+        _ => format!("{{{{{}}}}}", placeholder),
+    }
+}
+
+fn json_text(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LoadConfig;
+
+    fn collection(yaml: &str) -> Value {
+        DiffConfig::from_yaml(yaml)
+            .unwrap()
+            .to_postman("test")
+            .unwrap()
+    }
+
+    #[test]
+    fn to_postman_should_group_requests_by_profile() {
+        let value = collection(
+            r#"
+b:
+  req1: { url: "https://example.com/b" }
+  req2: { url: "https://example.org/b" }
+  tags: [smoke, api]
+a:
+  req1: { url: "https://example.com/a" }
+  req2: { url: "https://example.org/a" }
+"#,
+        );
+        assert_eq!(value["info"]["name"], "test");
+        assert_eq!(value["info"]["schema"], POSTMAN_SCHEMA);
+        let items = value["item"].as_array().unwrap();
+        assert_eq!(items[0]["name"], "a");
+        assert_eq!(items[1]["name"], "b");
+        assert_eq!(items[1]["description"], "tags: smoke, api");
+        assert_eq!(items[1]["item"][0]["name"], "req1");
+        assert_eq!(items[1]["item"][1]["name"], "req2");
+        assert_eq!(
+            items[1]["item"][1]["request"]["url"]["raw"],
+            "https://example.org/b"
+        );
+    }
+
+    #[test]
+    fn url_should_append_params_after_the_query() {
+        let value = collection(
+            r#"
+p:
+  req1:
+    url: "https://api.example.com:8443/v1/items?a=1"
+    params: { b: [2, 3], c: x }
+  req2: { url: "https://example.org/" }
+"#,
+        );
+        let url = &value["item"][0]["item"][0]["request"]["url"];
+        assert_eq!(
+            url["raw"],
+            "https://api.example.com:8443/v1/items?a=1&b=2&b=3&c=x"
+        );
+        assert_eq!(url["host"], json!(["api", "example", "com"]));
+        assert_eq!(url["port"], "8443");
+        assert_eq!(url["path"], json!(["v1", "items"]));
+        assert_eq!(url["query"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn body_should_follow_the_content_type() {
+        let value = collection(
+            r#"
+p:
+  req1:
+    method: POST
+    url: "https://example.com/"
+    headers: { content-type: application/x-www-form-urlencoded }
+    body: { a: 1, b: x }
+  req2:
+    method: POST
+    url: "https://example.org/"
+    body: { a: 1 }
+"#,
+        );
+        let requests = &value["item"][0]["item"];
+        assert_eq!(
+            requests[0]["request"]["body"],
+            json!({
+                "mode": "urlencoded",
+                "urlencoded": [{ "key": "a", "value": "1" }, { "key": "b", "value": "x" }],
+            })
+        );
+        let body = &requests[1]["request"]["body"];
+        assert_eq!(body["mode"], "raw");
+        assert_eq!(body["options"]["raw"]["language"], "json");
+        assert_eq!(
+            serde_json::from_str::<Value>(body["raw"].as_str().unwrap()).unwrap(),
+            json!({"a": 1})
+        );
+    }
+
+    #[test]
+    fn secrets_should_not_be_exported() {
+        let value = collection(
+            r#"
+p:
+  req1:
+    url: "https://example.com/"
+    auth: { type: api_key, in: header, name: x-api-key, value: { env: API_KEY } }
+  req2:
+    url: "https://example.org/"
+    auth: { type: digest, username: alice, password: { file: secret.txt } }
+"#,
+        );
+        let requests = &value["item"][0]["item"];
+        let apikey = &requests[0]["request"]["auth"];
+        assert_eq!(apikey["type"], "apikey");
+        assert_eq!(apikey["apikey"][1]["value"], "{{API_KEY}}");
+        assert_eq!(apikey["apikey"][2]["value"], "header");
+        let digest = &requests[1]["request"]["auth"];
+        assert_eq!(digest["type"], "digest");
+        assert_eq!(digest["digest"][0]["value"], "alice");
+        assert_eq!(digest["digest"][1]["value"], "{{password}}");
+    }
+}
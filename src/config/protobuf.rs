@@ -0,0 +1,72 @@
+use anyhow::{anyhow, Context, Result};
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// protobuf 的 content type，设置了请求消息且没有指定 content type 时使用
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// protobuf 编解码配置，descriptor 为 `protoc --descriptor_set_out` 生成的文件
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ProtobufProfile {
+    // FileDescriptorSet 文件路径，相对于配置文件
+    pub descriptor: String,
+    // 请求 body 的消息名称，例如 `user.v1.CreateUserRequest`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub request: Option<String>,
+    // 响应 body 的消息名称，设置后响应会解码为 JSON 再比较
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub response: Option<String>,
+    // load_files 时解码的 descriptor，之后编解码不再读取文件
+    #[serde(skip)]
+    pool: Option<DescriptorPool>,
+}
+
+impl ProtobufProfile {
+    // 把 descriptor 的路径转换为相对于 base_dir 的路径，并只解码一次
+    pub(crate) fn load(&mut self, base_dir: &Path) -> Result<()> {
+        self.descriptor = base_dir.join(&self.descriptor).display().to_string();
+        self.pool = Some(self.decode()?);
+        Ok(())
+    }
+
+    fn decode(&self) -> Result<DescriptorPool> {
+        let bytes = fs::read(&self.descriptor)
+            .with_context(|| format!("failed to read descriptor`读取失败: {}", self.descriptor))?;
+        Ok(DescriptorPool::decode(bytes.as_slice())?)
+    }
+
+    fn message(&self, name: &str) -> Result<MessageDescriptor> {
+        // 没有经过 load_files 的 profile 仍然按需读取
+        let pool = match &self.pool {
+            Some(pool) => pool.clone(),
+            None => self.decode()?,
+        };
+        pool.get_message_by_name(name)
+            .ok_or_else(|| anyhow!("Message {} not found in descriptor`未找到消息", name))
+    }
+
+    /// 按请求消息把 JSON body 编码为 protobuf
+    pub(crate) fn encode_request(&self, body: &serde_json::Value) -> Result<Option<Vec<u8>>> {
+        let Some(name) = &self.request else {
+            return Ok(None);
+        };
+        let message = DynamicMessage::deserialize(self.message(name)?, body)?;
+        Ok(Some(message.encode_to_vec()))
+    }
+
+    /// 响应消息的描述，没有设置时返回 None
+    pub(crate) fn response_message(&self) -> Result<Option<MessageDescriptor>> {
+        self.response
+            .as_deref()
+            .map(|name| self.message(name))
+            .transpose()
+    }
+}
+
+/// 把 protobuf 编码的响应解码为 JSON 字符串
+pub(crate) fn decode_message(descriptor: MessageDescriptor, bytes: &[u8]) -> Result<String> {
+    let message = DynamicMessage::decode(descriptor, bytes)?;
+    Ok(serde_json::to_string(&message)?)
+}
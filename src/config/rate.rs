@@ -0,0 +1,127 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use reqwest::{header, Response, StatusCode};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// 收到 429 后最多重试的次数
+const MAX_RETRIES: u32 = 3;
+/// 单次退避等待的最长时间
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 全局的请求速率限制，所有请求共享同一个限制
+static RATE_LIMITER: Mutex<Option<RateLimiter>> = Mutex::new(None);
+
+#[derive(Debug)]
+struct RateLimiter {
+    // 两次请求之间的最小间隔
+    interval: Duration,
+    // 下一个请求最早可以发送的时间
+    next: Instant,
+}
+
+/// 设置全局速率限制，`rate` 为每秒最多发送的请求数，必须是正数并且间隔不能超出 Duration 的范围
+/// set the global rate limit in requests per second
+pub fn set_rate_limit(rate: f64) -> Result<()> {
+    let interval = rate_interval(rate)?;
+    *RATE_LIMITER.lock().unwrap() = Some(RateLimiter {
+        interval,
+        next: Instant::now(),
+    });
+    Ok(())
+}
+
+/// 每秒 `rate` 个请求对应的请求间隔，NaN、无穷大、非正数以及过小的值都会返回错误
+pub(crate) fn rate_interval(rate: f64) -> Result<Duration> {
+    if !rate.is_finite() || rate <= 0.0 {
+        return Err(anyhow!(
+            "Rate must be a positive number`速率必须是正数: {}",
+            rate
+        ));
+    }
+    Duration::try_from_secs_f64(1.0 / rate)
+        .map_err(|_| anyhow!("Rate is too small`速率太小: {:e} requests per second", rate))
+}
+
+/// 按照全局速率限制等待，直到可以发送下一个请求
+pub(crate) async fn wait_rate_limit() {
+    let wait = {
+        let mut limiter = RATE_LIMITER.lock().unwrap();
+        match limiter.as_mut() {
+            Some(limiter) => {
+                let now = Instant::now();
+                let wait = limiter.next.saturating_duration_since(now);
+                limiter.next = limiter.next.max(now) + limiter.interval;
+                wait
+            }
+            None => Duration::ZERO,
+        }
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// 如果响应是 429，返回重试前需要等待的时间；优先使用 Retry-After，否则指数退避
+pub(crate) fn retry_after(res: &Response, attempt: u32) -> Option<Duration> {
+    if res.status() != StatusCode::TOO_MANY_REQUESTS || attempt >= MAX_RETRIES {
+        return None;
+    }
+    let wait = res
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_retry_after(v, Utc::now()))
+        .unwrap_or_else(|| Duration::from_secs(1 << attempt));
+    Some(wait.min(MAX_BACKOFF))
+}
+
+// Retry-After 可以是秒数，也可以是 HTTP-date，例如 `Wed, 21 Oct 2015 07:28:00 GMT`；已经过去的时间不需要等待
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_after_should_accept_seconds_and_http_dates() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn rate_interval_should_reject_invalid_rates() {
+        assert_eq!(rate_interval(4.0).unwrap(), Duration::from_millis(250));
+        assert_eq!(rate_interval(0.5).unwrap(), Duration::from_secs(2));
+        for rate in [0.0, -1.0, 1e-30, f64::NAN, f64::INFINITY] {
+            assert!(rate_interval(rate).is_err(), "{}", rate);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::{
+    get_content_type, spool::Spool, RequestProfile, ResponseExt, ResponseProfile, SentRequest,
+    SSE_CONTENT_TYPE,
+};
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{header::HeaderMap, Body, Method, Response, StatusCode, Url, Version};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path, time::Duration};
+
+/// 录制的原始响应，响应体单独保存在 `<name>.body` 中
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    status: u16,
+    #[serde(with = "http_serde::version")]
+    version: Version,
+    #[serde(with = "http_serde::header_map")]
+    headers: HeaderMap,
+    // 从发送请求到收到响应头的时间
+    elapsed_ms: u64,
+    // 响应体超过 max_body_bytes 时只保存前面的部分，这里是实际的大小
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    body_size: Option<usize>,
+    request: RecordedRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedRequest {
+    #[serde(with = "http_serde::method")]
+    method: Method,
+    url: Url,
+    #[serde(with = "http_serde::header_map")]
+    headers: HeaderMap,
+    body: String,
+    // 和 HAR 相同，不是 UTF-8 的请求体(例如 protobuf、msgpack)为 base64
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    encoding: Option<String>,
+}
+
+impl RecordedRequest {
+    fn new(req: &SentRequest) -> Self {
+        let (body, encoding) = match std::str::from_utf8(&req.body) {
+            Ok(text) => (text.to_string(), None),
+            Err(_) => (STANDARD.encode(&req.body), Some("base64".to_string())),
+        };
+        Self {
+            method: req.method.clone(),
+            url: req.url.clone(),
+            headers: req.headers.clone(),
+            body,
+            encoding,
+        }
+    }
+
+    fn to_request(&self) -> Result<SentRequest> {
+        let body = match self.encoding.as_deref() {
+            Some("base64") => STANDARD
+                .decode(&self.body)
+                .map_err(|e| anyhow!("invalid base64 request body`请求体的 base64 无效: {}", e))?,
+            Some(encoding) => {
+                return Err(anyhow!(
+                    "Unsupported request body encoding`不支持的请求体编码: {}",
+                    encoding
+                ))
+            }
+            None => self.body.clone().into_bytes(),
+        };
+        Ok(SentRequest {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            body,
+        })
+    }
+}
+
+impl ResponseExt {
+    /// 读取响应，把状态、响应头和原始的响应体保存到 dir 中的 `<name>.json` 和 `<name>.body`，
+    /// 返回的响应从 `<name>.body` 读取，可以继续过滤和比较 \
+    /// 和比较时一样，超过 max_body_bytes 的部分不保存
+    pub(crate) async fn record(
+        mut self,
+        dir: &Path,
+        name: &str,
+        profile: &ResponseProfile,
+    ) -> Result<Self> {
+        // 事件流没有结尾，无法完整读取
+        if get_content_type(self.res.headers()).as_deref() == Some(SSE_CONTENT_TYPE) {
+            return Err(anyhow!(
+                "Event streams can not be recorded`事件流无法录制: {}",
+                name
+            ));
+        }
+        let mut body = Spool::new(profile.max_body_bytes);
+        while let Some(chunk) = self.res.chunk().await? {
+            body.write(&chunk)?;
+        }
+        let recording = Recording {
+            status: self.res.status().as_u16(),
+            version: self.res.version(),
+            headers: self.res.headers().clone(),
+            elapsed_ms: self.elapsed.as_millis() as u64,
+            body_size: body.truncated().then(|| body.size()),
+            request: RecordedRequest::new(&self.request),
+        };
+        fs::create_dir_all(dir).with_context(|| {
+            format!("failed to create directory`创建目录失败: {}", dir.display())
+        })?;
+        let path = dir.join(format!("{}.json", name));
+        fs::write(&path, serde_json::to_string_pretty(&recording)?)
+            .with_context(|| format!("failed to write`写入失败: {}", path.display()))?;
+        let path = dir.join(format!("{}.body", name));
+        fs::File::create(&path)
+            .and_then(|mut file| io::copy(&mut body.reader().map_err(io::Error::other)?, &mut file))
+            .with_context(|| format!("failed to write`写入失败: {}", path.display()))?;
+        Ok(Self {
+            res: build_response(&recording, &path)?,
+            body_size: recording.body_size,
+            ..self
+        })
+    }
+
+    /// 从 `record` 保存的文件中恢复响应，protobuf 等解码配置来自当前的请求配置
+    pub(crate) fn replay(dir: &Path, name: &str, req: &RequestProfile) -> Result<Self> {
+        let path = dir.join(format!("{}.json", name));
+        let content = fs::read_to_string(&path).with_context(|| {
+            format!("failed to read recording`读取录制失败: {}", path.display())
+        })?;
+        let recording: Recording = serde_json::from_str(&content)
+            .with_context(|| format!("invalid recording`无效的录制: {}", path.display()))?;
+        let path = dir.join(format!("{}.body", name));
+        Ok(Self {
+            res: build_response(&recording, &path)?,
+            protobuf: req.response_message()?,
+            request: recording.request.to_request()?,
+            elapsed: Duration::from_millis(recording.elapsed_ms),
+            body_size: recording.body_size,
+        })
+    }
+}
+
+// 响应体从文件中按块读取，不需要全部读入内存
+fn build_response(recording: &Recording, body: &Path) -> Result<Response> {
+    let file = fs::File::open(body)
+        .with_context(|| format!("failed to read recording`读取录制失败: {}", body.display()))?;
+    let mut res = http::Response::new(Body::from(tokio::fs::File::from_std(file)));
+    *res.status_mut() = StatusCode::from_u16(recording.status)?;
+    *res.version_mut() = recording.version;
+    *res.headers_mut() = recording.headers.clone();
+    Ok(Response::from(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sent(body: &[u8]) -> SentRequest {
+        SentRequest {
+            method: Method::POST,
+            url: Url::parse("https://example.com/a").unwrap(),
+            headers: HeaderMap::new(),
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn recorded_request_should_keep_text_body_readable() {
+        let recorded = RecordedRequest::new(&sent(br#"{"id":1}"#));
+        assert_eq!(recorded.body, r#"{"id":1}"#);
+        assert_eq!(recorded.encoding, None);
+        assert_eq!(recorded.to_request().unwrap().body, br#"{"id":1}"#);
+    }
+
+    #[test]
+    fn recorded_request_should_round_trip_binary_body() {
+        let body = [0x08, 0x96, 0x01, 0xff, 0x00];
+        let recorded = RecordedRequest::new(&sent(&body));
+        assert_eq!(recorded.encoding.as_deref(), Some("base64"));
+        let json = serde_json::to_string(&recorded).unwrap();
+        let recorded: RecordedRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(recorded.to_request().unwrap().body, body);
+    }
+}
@@ -0,0 +1,90 @@
+use reqwest::{header::HeaderMap, Method, Request};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use url::Url;
+
+/// 比较时在响应前面加上实际发送的请求，用于发现由请求构造不同导致的差异
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IncludeRequest {
+    // 需要输出的请求头，默认不输出请求头
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub headers: Vec<String>,
+}
+
+/// 实际发送的请求
+#[derive(Debug, Clone)]
+pub struct SentRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl From<&Request> for SentRequest {
+    fn from(req: &Request) -> Self {
+        Self {
+            method: req.method().clone(),
+            url: req.url().clone(),
+            headers: req.headers().clone(),
+            body: req
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(<[u8]>::to_vec)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl IncludeRequest {
+    /// 按 `> ` 开头的行输出请求行、选中的请求头和请求体
+    pub(crate) fn render(&self, req: &SentRequest) -> String {
+        let mut output = String::new();
+        let _ = writeln!(output, "> {} {}", req.method, req.url);
+        for name in &self.headers {
+            for value in req.headers.get_all(name.as_str()) {
+                let value = String::from_utf8_lossy(value.as_bytes());
+                let _ = writeln!(output, "> {}: {}", name.to_lowercase(), value);
+            }
+        }
+        // JSON 请求体格式化后输出，便于逐字段比较
+        let body = match serde_json::from_slice::<serde_json::Value>(&req.body) {
+            Ok(json) => serde_json::to_string_pretty(&json).unwrap_or_default(),
+            Err(_) => String::from_utf8_lossy(&req.body).into_owned(),
+        };
+        if !body.is_empty() {
+            let _ = writeln!(output, ">");
+            for line in body.lines() {
+                let _ = writeln!(output, "> {}", line);
+            }
+        }
+        output
+    }
+}
+
+impl SentRequest {
+    /// 完整的请求：请求行、所有请求头和请求体，无法按文本显示的请求体只输出大小
+    pub fn to_text(&self) -> String {
+        let mut output = String::new();
+        let _ = writeln!(output, "{} {}", self.method, self.url);
+        for (name, value) in &self.headers {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            let _ = writeln!(output, "{}: {}", name, value);
+        }
+        let body = self.body_text();
+        if !body.is_empty() {
+            let _ = writeln!(output, "\n{}", body.trim_end());
+        }
+        output
+    }
+
+    /// 请求体的文本：JSON 格式化输出，无法按文本显示的只输出大小
+    pub(crate) fn body_text(&self) -> String {
+        match serde_json::from_slice::<serde_json::Value>(&self.body) {
+            Ok(json) => serde_json::to_string_pretty(&json).unwrap_or_default(),
+            Err(_) => match std::str::from_utf8(&self.body) {
+                Ok(text) => text.to_string(),
+                Err(_) => format!("<{} bytes of binary data>", self.body.len()),
+            },
+        }
+    }
+}
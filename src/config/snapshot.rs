@@ -0,0 +1,114 @@
+use super::{ResponseBody, ResponseParts};
+use anyhow::{Context, Result};
+use std::{fs, path::Path, time::Duration};
+
+/// 用快照文件中的响应体代替第二个响应 \
+/// 快照只保存过滤后的响应体，状态行和响应头沿用实时的响应 \
+/// 快照按写入时的形式读取：实时响应体是 JSON 时按 JSON 解析，图片按原始字节，其他按文本，
+/// 不再按实时响应的 content-type 解码，否则 NDJSON、msgpack、protobuf 和二进制的快照无法还原
+pub(crate) fn snapshot_parts(file: &str, live: &ResponseParts) -> Result<ResponseParts> {
+    let bytes = fs::read(file).with_context(|| {
+        format!(
+            "failed to read snapshot, create it with xdiff snapshot --update`读取快照失败: {}",
+            file
+        )
+    })?;
+    let text = || String::from_utf8_lossy(&bytes).into_owned();
+    let body = match &live.body {
+        // 快照不是 JSON 时按文本比较，差异中可以看到快照的内容
+        ResponseBody::Json(_) => serde_json::from_slice(&bytes)
+            .map(ResponseBody::Json)
+            .unwrap_or_else(|_| ResponseBody::Text(text())),
+        ResponseBody::Text(_) => ResponseBody::Text(text()),
+        ResponseBody::Image(_) => ResponseBody::Image(bytes.clone()),
+    };
+    Ok(ResponseParts {
+        body,
+        compressed: false,
+        elapsed: Duration::ZERO,
+        warnings: vec![],
+        ..live.clone()
+    })
+}
+
+/// 把过滤后的响应体写入快照文件，目录不存在时自动创建
+pub(crate) fn write_snapshot(file: &str, body: &ResponseBody) -> Result<()> {
+    let bytes = match body {
+        ResponseBody::Json(json) => {
+            let mut text = serde_json::to_string_pretty(json)?;
+            text.push('\n');
+            text.into_bytes()
+        }
+        ResponseBody::Text(text) => text.clone().into_bytes(),
+        ResponseBody::Image(bytes) => bytes.clone(),
+    };
+    if let Some(dir) = Path::new(file).parent() {
+        fs::create_dir_all(dir).with_context(|| {
+            format!("failed to create directory`创建目录失败: {}", dir.display())
+        })?;
+    }
+    fs::write(file, bytes)
+        .with_context(|| format!("failed to write snapshot`写入快照失败: {}", file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_body, BinaryMode, ImageProfile, ResponseProfile};
+    use reqwest::{header, Response};
+    use serde_json::json;
+
+    // 用实时响应生成快照，再读取快照，两边的响应体应该相同
+    async fn round_trip(content_type: &str, body: Vec<u8>, profile: &ResponseProfile) {
+        let mut res = http::Response::new(body);
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+        let live = ResponseParts {
+            request: None,
+            status: "HTTP/1.1 200 OK".to_string(),
+            status_code: 200,
+            header_map: res.headers().clone(),
+            headers: String::new(),
+            body: get_body(Response::from(res), profile).await.unwrap(),
+            compressed: false,
+            elapsed: Duration::ZERO,
+            warnings: vec![],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("snapshot").display().to_string();
+        write_snapshot(&file, &live.body).unwrap();
+        let snapshot = snapshot_parts(&file, &live).unwrap();
+        assert_eq!(snapshot.body, live.body, "{}", content_type);
+    }
+
+    #[tokio::test]
+    async fn snapshot_should_round_trip_every_body_kind() {
+        let profile = ResponseProfile::default();
+        let json = json!({"id": 1, "tags": ["a", "b"]});
+        round_trip("application/json", json.to_string().into_bytes(), &profile).await;
+        round_trip(
+            "application/x-ndjson",
+            b"{\"id\": 1}\n{\"id\": 2}\n".to_vec(),
+            &profile,
+        )
+        .await;
+        round_trip(
+            "application/msgpack",
+            rmp_serde::to_vec_named(&json).unwrap(),
+            &profile,
+        )
+        .await;
+        round_trip("text/plain", b"hello\n".to_vec(), &profile).await;
+        round_trip("application/octet-stream", vec![0, 159, 146, 150], &profile).await;
+        let hexdump = ResponseProfile {
+            binary: BinaryMode::Hexdump,
+            ..Default::default()
+        };
+        round_trip("application/octet-stream", vec![0, 159, 146, 150], &hexdump).await;
+        let image = ResponseProfile {
+            image: Some(ImageProfile::default()),
+            ..Default::default()
+        };
+        round_trip("image/png", b"\x89PNG\r\n\x1a\n".to_vec(), &image).await;
+    }
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use std::io::Read;
+
+/// 响应体的存储，超过 limit 的部分被丢弃，只统计大小
+pub(crate) struct Spool {
+    bytes: Vec<u8>,
+    limit: Option<usize>,
+    // 写入的总字节数，包括被丢弃的部分
+    size: usize,
+}
+
+impl Spool {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            bytes: vec![],
+            limit,
+            size: 0,
+        }
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.size += data.len();
+        let n = match self.limit {
+            Some(limit) => data.len().min(limit.saturating_sub(self.bytes.len())),
+            None => data.len(),
+        };
+        self.bytes.extend_from_slice(&data[..n]);
+        Ok(())
+    }
+
+    /// 从 reader 读取全部内容，超过 limit 后停止读取 \
+    /// partial 表示输入不完整(例如被截断的压缩数据)，此时忽略读取错误并保留已经读到的部分
+    pub fn copy_from(&mut self, mut reader: impl Read, partial: bool) -> Result<()> {
+        let mut buf = vec![0; 64 * 1024];
+        while !self.truncated() {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(_) if partial => break,
+                Err(e) => return Err(e.into()),
+            };
+            self.write(&buf[..n])?;
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// 是否有内容因为超过 limit 被丢弃
+    pub fn truncated(&self) -> bool {
+        self.size > self.bytes.len()
+    }
+
+    /// 从头读取保留的内容
+    pub fn reader(&mut self) -> Result<Box<dyn Read + '_>> {
+        Ok(Box::new(self.bytes.as_slice()))
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        Ok(self.bytes)
+    }
+}
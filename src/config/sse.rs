@@ -0,0 +1,139 @@
+use anyhow::Result;
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+
+/// text/event-stream 的 content type
+pub const SSE_CONTENT_TYPE: &str = "text/event-stream";
+
+/// Server-Sent Events 的读取配置，事件流不会结束，按数量或者时间停止读取
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SseProfile {
+    // 最多读取的事件数
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_events: Option<usize>,
+    // 最多读取的时间
+    #[serde(default = "default_duration_ms")]
+    pub duration_ms: u64,
+    // 不比较事件的 id
+    #[serde(skip_serializing_if = "crate::is_default", default)]
+    pub skip_ids: bool,
+}
+
+fn default_duration_ms() -> u64 {
+    5000
+}
+
+impl Default for SseProfile {
+    fn default() -> Self {
+        Self {
+            max_events: None,
+            duration_ms: default_duration_ms(),
+            skip_ids: false,
+        }
+    }
+}
+
+impl SseProfile {
+    /// 读取事件，每个事件转换为 `{event, id, data}`，data 是 JSON 时解析为 JSON
+    pub(crate) async fn capture(&self, mut res: Response) -> Result<Vec<Value>> {
+        let deadline = Instant::now() + Duration::from_millis(self.duration_ms);
+        let mut buffer = EventBuffer::default();
+        let mut events = vec![];
+        while self.max_events.is_none_or(|max| events.len() < max) {
+            let chunk = match timeout_at(deadline, res.chunk()).await {
+                Ok(chunk) => chunk?,
+                Err(_) => break,
+            };
+            let Some(chunk) = chunk else {
+                break;
+            };
+            for block in buffer.push(&chunk) {
+                if let Some(event) = self.parse_event(&block) {
+                    events.push(event);
+                }
+            }
+        }
+        if let Some(max) = self.max_events {
+            events.truncate(max);
+        }
+        Ok(events)
+    }
+
+    fn parse_event(&self, block: &str) -> Option<Value> {
+        let (mut name, mut id, mut data) = (None, None, vec![]);
+        for line in block.lines() {
+            // `:` 开头的是注释，常用于保持连接
+            if line.is_empty() || line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => name = Some(value.to_string()),
+                "id" => id = Some(value.to_string()),
+                "data" => data.push(value),
+                _ => {}
+            }
+        }
+        if data.is_empty() && name.is_none() {
+            return None;
+        }
+        let data = data.join("\n");
+        let data = serde_json::from_str(&data).unwrap_or(Value::String(data));
+        let mut event = json!({
+            "event": name.unwrap_or_else(|| "message".to_string()),
+            "data": data,
+        });
+        if let (Some(id), false) = (id, self.skip_ids) {
+            event["id"] = Value::String(id);
+        }
+        Some(event)
+    }
+}
+
+/// 按字节缓存事件流，只解码完整的行，多字节字符和 `\r\n` 可能被拆在两个 chunk 中
+#[derive(Debug, Default)]
+struct EventBuffer {
+    bytes: Vec<u8>,
+    text: String,
+}
+
+impl EventBuffer {
+    /// 加入一个 chunk，返回已经完整的事件
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.bytes.extend_from_slice(chunk);
+        while let Some(pos) = self.bytes.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.bytes.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.text.push_str(line.trim_end_matches(['\r', '\n']));
+            self.text.push('\n');
+        }
+        // 空行表示一个事件结束
+        let mut blocks = vec![];
+        while let Some(end) = self.text.find("\n\n") {
+            blocks.push(self.text.drain(..end + 2).collect());
+        }
+        blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_buffer_should_decode_characters_split_across_chunks() {
+        let bytes = "data: 你好\r\n\r\n".as_bytes();
+        let mut buffer = EventBuffer::default();
+        // 在“你”的中间和 `\r\n` 之间拆分
+        assert!(buffer.push(&bytes[..7]).is_empty());
+        assert!(buffer.push(&bytes[7..13]).is_empty());
+        let blocks = buffer.push(&bytes[13..]);
+        assert_eq!(blocks, vec!["data: 你好\n\n"]);
+        let event = SseProfile::default().parse_event(&blocks[0]).unwrap();
+        assert_eq!(event, json!({"event": "message", "data": "你好"}));
+    }
+}
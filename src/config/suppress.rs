@@ -0,0 +1,379 @@
+use super::{check::sections, normalize, ResponseBody, ResponseParts};
+use crate::JsonPath;
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::{DiffTag, TextDiff};
+use std::{fs, ops::Range};
+
+/// 预期中的差异，符合规则的差异不会出现在结果中，而是单独列为 suppressed \
+/// `path` 为 JSONPath，`header` 的写法和 skip_headers 相同，`hunk` 为匹配一段差异的正则表达式
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Suppression {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub header: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub hunk: Option<String>,
+    // 为什么这个差异是预期的，和规则一起输出
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reason: Option<String>,
+}
+
+impl Suppression {
+    pub(crate) fn validate(&self) -> Result<()> {
+        match (&self.path, &self.header, &self.hunk) {
+            (Some(path), None, None) => {
+                JsonPath::parse(path)?;
+            }
+            (None, Some(header), None) => {
+                normalize::header_patterns(std::slice::from_ref(header))?;
+            }
+            (None, None, Some(hunk)) => {
+                Regex::new(hunk)?;
+            }
+            _ => {
+                return Err(anyhow!(
+                    "suppress rule needs exactly one of path, header or hunk`suppress 规则必须且只能设置 path、header、hunk 中的一个"
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// 规则的描述，例如 `path $.version (v2 migration)`
+    pub fn describe(&self) -> String {
+        let rule = match (&self.path, &self.header, &self.hunk) {
+            (Some(path), _, _) => format!("path {}", path),
+            (_, Some(header), _) => format!("header {}", header),
+            (_, _, Some(hunk)) => format!("hunk /{}/", hunk),
+            _ => String::new(),
+        };
+        match &self.reason {
+            Some(reason) => format!("{} ({})", rule, reason),
+            None => rule,
+        }
+    }
+}
+
+/// 把规则追加到配置文件中 profile 的 suppress 列表 \
+/// 只在 profile 的 suppress 下插入新的行，注释、锚点和其他 profile 保持原样
+pub fn save_suppressions(path: &str, profile: &str, rules: &[Suppression]) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read config`读取配置失败: {}", path))?;
+    let content = insert_suppressions(&content, profile, rules)
+        .with_context(|| format!("failed to update config`更新配置失败: {}", path))?;
+    fs::write(path, content)
+        .with_context(|| format!("failed to write config`写入配置失败: {}", path))?;
+    Ok(())
+}
+
+// 在配置文本中 profile 的 suppress 列表末尾插入规则，没有 suppress 时在 profile 末尾新增
+fn insert_suppressions(content: &str, profile: &str, rules: &[Suppression]) -> Result<String> {
+    let not_found = || anyhow!("Profile {} not found`配置文件中未找到", profile);
+    let (start, end) = sections(content)
+        .into_iter()
+        .find(|(key, _, _)| key == profile)
+        .map(|(_, start, text)| (start, start + text.lines().count()))
+        .ok_or_else(not_found)?;
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+    let is_content = |line: &str| !line.trim().is_empty() && !line.trim().starts_with('#');
+    // profile 下一层的缩进和最后一个有内容的行，profile 之后的空行和注释保持在原来的位置
+    let children: Vec<usize> = (start + 1..end)
+        .filter(|i| is_content(&lines[*i]))
+        .collect();
+    let (Some(first), Some(&last)) = (children.first(), children.last()) else {
+        return Err(anyhow!(
+            "profile {} is not a block mapping, add the rules by hand`profile 不是块格式的映射，请手动添加",
+            profile
+        ));
+    };
+    let indent = indentation(&lines[*first]).to_string();
+    // 文件的最后一行可能没有换行
+    if !lines[last].ends_with('\n') {
+        lines[last].push('\n');
+    }
+    let key = children.iter().copied().find(|i| {
+        lines[*i]
+            .strip_prefix(&indent)
+            .is_some_and(|line| line.starts_with("suppress:"))
+    });
+    let (at, item_indent) = match key {
+        Some(i) => {
+            let value = lines[i][indent.len() + "suppress:".len()..].trim();
+            match value {
+                "[]" => lines[i] = format!("{}suppress:\n", indent),
+                _ if value.is_empty() || value.starts_with('#') => {}
+                _ => {
+                    return Err(anyhow!(
+                        "suppress of profile {} is not a block list, add the rules by hand`suppress 不是块格式的列表，请手动添加",
+                        profile
+                    ))
+                }
+            }
+            // 列表中的行比 suppress 缩进更多，或者与 suppress 对齐并以 `-` 开头
+            let items: Vec<usize> = children
+                .iter()
+                .copied()
+                .skip_while(|j| *j <= i)
+                .take_while(|j| {
+                    let line = &lines[*j];
+                    indentation(line).len() > indent.len()
+                        || (indentation(line) == indent && line.trim_start().starts_with('-'))
+                })
+                .collect();
+            let item_indent = match items.first() {
+                Some(j) => indentation(&lines[*j]).to_string(),
+                None => format!("{}  ", indent),
+            };
+            (items.last().copied().unwrap_or(i) + 1, item_indent)
+        }
+        None => {
+            lines.insert(last + 1, format!("{}suppress:\n", indent));
+            (last + 2, format!("{}  ", indent))
+        }
+    };
+    let items = serde_yaml::to_string(rules)?;
+    let items = items
+        .lines()
+        .map(|line| format!("{}{}\n", item_indent, line));
+    lines.splice(at..at, items);
+    let updated = lines.concat();
+
+    // 写入之前确认 suppress 列表正好多了这些规则
+    let suppress = |content: &str| -> Result<Vec<serde_yaml::Value>> {
+        let config: serde_yaml::Value = serde_yaml::from_str(content)?;
+        let profile = config.get(profile).ok_or_else(not_found)?;
+        Ok(profile
+            .get("suppress")
+            .and_then(|list| list.as_sequence())
+            .cloned()
+            .unwrap_or_default())
+    };
+    let mut expected = suppress(content)?;
+    for rule in rules {
+        expected.push(serde_yaml::to_value(rule)?);
+    }
+    if suppress(&updated).ok() != Some(expected) {
+        return Err(anyhow!(
+            "failed to insert suppress rules into profile {}, add them by hand`无法自动插入 suppress 规则，请手动添加",
+            profile
+        ));
+    }
+    Ok(updated)
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// 比较之前按 path 和 header 规则消除两边的差异，返回生效的规则 \
+/// 不同的字段在 req2 中还原为 req1 的值，不同的响应头从两边去掉
+pub(crate) fn suppress_parts(
+    rules: &[Suppression],
+    parts1: &mut ResponseParts,
+    parts2: &mut ResponseParts,
+) -> Result<Vec<String>> {
+    let mut suppressed = vec![];
+    for rule in rules {
+        let matched = match (&rule.path, &rule.header) {
+            (Some(path), _) => match (&parts1.body, &mut parts2.body) {
+                (ResponseBody::Json(json1), ResponseBody::Json(json2)) => {
+                    suppress_path(&JsonPath::parse(path)?, json1, json2)
+                }
+                _ => false,
+            },
+            (_, Some(header)) => {
+                let patterns = normalize::header_patterns(std::slice::from_ref(header))?;
+                let names: Vec<String> = parts1
+                    .changed_headers(parts2)
+                    .into_iter()
+                    .filter(|name| patterns.iter().any(|p| p.is_match(name)))
+                    .collect();
+                for parts in [&mut *parts1, &mut *parts2] {
+                    parts.headers = remove_header_lines(&parts.headers, &names);
+                }
+                !names.is_empty()
+            }
+            _ => false,
+        };
+        if matched {
+            suppressed.push(rule.describe());
+        }
+    }
+    Ok(suppressed)
+}
+
+// 把 json2 中与 json1 不同的匹配值还原为 json1 的值
+fn suppress_path(path: &JsonPath, json1: &Value, json2: &mut Value) -> bool {
+    let mut matched = false;
+    for pointer in path.find_pointers(json1) {
+        let value = json1.pointer(&pointer);
+        if value != json2.pointer(&pointer) {
+            matched |= value.is_some_and(|v| set_pointer(json2, &pointer, v.clone()));
+        }
+    }
+    // 只在 req2 中存在的值倒序删除，避免数组下标发生变化
+    for pointer in path.find_pointers(json2).iter().rev() {
+        if json1.pointer(pointer).is_none() {
+            matched |= crate::jsonpath::remove_pointer(json2, pointer).is_some();
+        }
+    }
+    matched
+}
+
+fn set_pointer(root: &mut Value, pointer: &str, value: Value) -> bool {
+    if let Some(target) = root.pointer_mut(pointer) {
+        *target = value;
+        return true;
+    }
+    let Some((parent, token)) = pointer.rsplit_once('/') else {
+        return false;
+    };
+    let token = token.replace("~1", "/").replace("~0", "~");
+    match root.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(token, value);
+            true
+        }
+        Some(Value::Array(items)) if token.parse() == Ok(items.len()) => {
+            items.push(value);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn remove_header_lines(headers: &str, names: &[String]) -> String {
+    headers
+        .lines()
+        .filter(|line| {
+            let name = line.split_once(": ").map(|(name, _)| name.to_lowercase());
+            !name.is_some_and(|name| names.contains(&name))
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// 把 text2 中符合 hunk 规则的差异还原为 text1 的内容，生效的规则加入 suppressed
+pub(crate) fn suppress_hunks(
+    rules: &[Suppression],
+    text1: &str,
+    text2: &str,
+    suppressed: &mut Vec<String>,
+) -> Result<String> {
+    let rules = rules
+        .iter()
+        .filter_map(|rule| rule.hunk.as_ref().map(|hunk| Ok((rule, Regex::new(hunk)?))))
+        .collect::<Result<Vec<_>>>()?;
+    if rules.is_empty() {
+        return Ok(text2.to_string());
+    }
+
+    let diff = TextDiff::from_lines(text1, text2);
+    let (old, new) = (diff.old_slices(), diff.new_slices());
+    let mut output = String::new();
+    // 相邻的修改组成一段差异，整段还原或者保留
+    let mut pending = vec![];
+    let mut flush = |pending: &mut Vec<(Range<usize>, Range<usize>)>, output: &mut String| {
+        if pending.is_empty() {
+            return;
+        }
+        let mut hunk = String::new();
+        for (old_range, new_range) in pending.iter().cloned() {
+            for i in old_range {
+                hunk.push('-');
+                hunk.push_str(old[i]);
+            }
+            for i in new_range {
+                hunk.push('+');
+                hunk.push_str(new[i]);
+            }
+        }
+        let rule = rules.iter().find(|(_, regex)| regex.is_match(&hunk));
+        for (old_range, new_range) in pending.drain(..) {
+            let lines = match rule {
+                Some(_) => old_range.map(|i| old[i]).collect::<Vec<_>>(),
+                None => new_range.map(|i| new[i]).collect(),
+            };
+            output.extend(lines);
+        }
+        if let Some((rule, _)) = rule {
+            let description = rule.describe();
+            if !suppressed.contains(&description) {
+                suppressed.push(description);
+            }
+        }
+    };
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            flush(&mut pending, &mut output);
+            output.extend(new_range.map(|i| new[i]));
+        } else {
+            pending.push((old_range, new_range));
+        }
+    }
+    flush(&mut pending, &mut output);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn suppress_path_should_remove_each_extra_item_once() {
+        // `$..a..[0]` 从 /a 和 /a/a 两条路线都会匹配到 /a/a/0
+        let path = JsonPath::parse("$..a..[0]").unwrap();
+        let json1 = json!({"a": {"a": [], "c": []}});
+        let mut json2 = json!({"a": {"a": [10, 20], "c": [30]}});
+        assert!(suppress_path(&path, &json1, &mut json2));
+        assert_eq!(json2, json!({"a": {"a": [20], "c": []}}));
+    }
+
+    #[test]
+    fn suppress_path_should_restore_changed_values() {
+        let path = JsonPath::parse("$.items[*].ts").unwrap();
+        let json1 = json!({"items": [{"ts": 1}, {"ts": 2}]});
+        let mut json2 = json!({"items": [{"ts": 3}, {"ts": 2}]});
+        assert!(suppress_path(&path, &json1, &mut json2));
+        assert_eq!(json2, json1);
+    }
+
+    fn rule(path: &str) -> Suppression {
+        Suppression {
+            path: Some(path.to_string()),
+            reason: Some("expected".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn insert_suppressions_should_append_to_the_existing_list() {
+        let content = "# comment\nbase: &base\n  req1:\n    url: http://a\ntodo:\n  <<: *base\n  suppress:\n  - path: $.a # keep\n  res:\n    skip_body: [id]\n\n# next\nother: {}\n";
+        let updated = insert_suppressions(content, "todo", &[rule("$.b")]).unwrap();
+        assert_eq!(
+            updated,
+            "# comment\nbase: &base\n  req1:\n    url: http://a\ntodo:\n  <<: *base\n  suppress:\n  - path: $.a # keep\n  - path: $.b\n    reason: expected\n  res:\n    skip_body: [id]\n\n# next\nother: {}\n"
+        );
+    }
+
+    #[test]
+    fn insert_suppressions_should_add_the_list_at_the_end_of_the_profile() {
+        let content = "todo:\n    req1:\n        url: http://a\n\nother:\n    req1: {}";
+        let updated = insert_suppressions(content, "todo", &[rule("$.b")]).unwrap();
+        assert_eq!(
+            updated,
+            "todo:\n    req1:\n        url: http://a\n    suppress:\n      - path: $.b\n        reason: expected\n\nother:\n    req1: {}"
+        );
+        let updated = insert_suppressions(content, "other", &[rule("$.b")]).unwrap();
+        assert!(updated.ends_with(
+            "other:\n    req1: {}\n    suppress:\n      - path: $.b\n        reason: expected\n"
+        ));
+        assert!(insert_suppressions(content, "missing", &[rule("$.b")]).is_err());
+    }
+}
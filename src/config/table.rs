@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// text/csv 响应的比较配置 \
+/// CSV 会被解析为行，每个单元格输出为 `列名: 值` 的一行，这样差异可以精确到单元格
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct CsvProfile {
+    // 忽略的列
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub skip_columns: Vec<String>,
+    // 标识一行的列，用于输出行标题和排序
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub key: Option<String>,
+    // 比较前按 key 列(没有 key 时按整行)排序
+    #[serde(skip_serializing_if = "crate::is_default", default)]
+    pub sort: bool,
+}
+
+impl CsvProfile {
+    /// 解析 CSV 并按单元格输出
+    pub(crate) fn format(&self, text: &str) -> Result<String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(text.as_bytes());
+        let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+        let key = match &self.key {
+            Some(key) => Some(
+                headers
+                    .iter()
+                    .position(|h| h == key)
+                    .ok_or_else(|| anyhow!("CSV column {} not found`CSV 中没有这一列", key))?,
+            ),
+            None => None,
+        };
+        let mut rows = reader
+            .records()
+            .map(|record| Ok(record?.iter().map(str::to_string).collect()))
+            .collect::<Result<Vec<Vec<String>>>>()?;
+        if self.sort {
+            match key {
+                Some(key) => rows.sort_by(|a, b| compare_cells(cell(a, key), cell(b, key))),
+                None => rows.sort(),
+            }
+        }
+
+        let mut output = String::new();
+        for (i, row) in rows.iter().enumerate() {
+            match key {
+                Some(key) => writeln!(output, "[{}={}]", headers[key], cell(row, key))?,
+                None => writeln!(output, "[row {}]", i + 1)?,
+            }
+            for (j, value) in row.iter().enumerate() {
+                let name = headers.get(j).map(String::as_str).unwrap_or_default();
+                if Some(j) == key || self.skip_columns.iter().any(|c| c == name) {
+                    continue;
+                }
+                writeln!(output, "  {}: {}", name, value)?;
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn cell(row: &[String], i: usize) -> &str {
+    row.get(i).map(String::as_str).unwrap_or_default()
+}
+
+// 数字按数值比较，其他按字符串比较
+fn compare_cells(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::{anyhow, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::Value;
+
+const FIRST_NAMES: &[&str] = &[
+    "James",
+    "Mary",
+    "John",
+    "Patricia",
+    "Robert",
+    "Jennifer",
+    "Michael",
+    "Linda",
+    "William",
+    "Elizabeth",
+    "David",
+    "Barbara",
+    "Richard",
+    "Susan",
+    "Joseph",
+    "Jessica",
+    "Thomas",
+    "Sarah",
+    "Charles",
+    "Karen",
+    "Wei",
+    "Fang",
+    "Min",
+    "Jing",
+    "Lei",
+    "Yan",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Smith",
+    "Johnson",
+    "Williams",
+    "Brown",
+    "Jones",
+    "Garcia",
+    "Miller",
+    "Davis",
+    "Rodriguez",
+    "Martinez",
+    "Wilson",
+    "Anderson",
+    "Taylor",
+    "Thomas",
+    "Moore",
+    "Jackson",
+    "Wang",
+    "Li",
+    "Zhang",
+    "Liu",
+    "Chen",
+    "Yang",
+];
+
+const DOMAINS: &[&str] = &["example.com", "example.org", "example.net", "test.dev"];
+
+/// 渲染请求中的 `{{ fake_name() }}` 等模板函数 \
+/// 相同的 seed 会按相同的顺序生成相同的值，所以两个请求会得到一样的数据
+pub struct Template {
+    rng: StdRng,
+}
+
+impl Template {
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self { rng }
+    }
+
+    /// 递归渲染 JSON 中所有字符串里的模板
+    /// render templates in every string of a JSON value
+    pub fn render_value(&mut self, value: &mut Value) -> Result<()> {
+        match value {
+            Value::String(s) if s.contains("{{") => *value = self.render_str(s)?,
+            Value::Array(items) => {
+                for item in items {
+                    self.render_value(item)?;
+                }
+            }
+            Value::Object(map) => {
+                for (_, item) in map.iter_mut() {
+                    self.render_value(item)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // 整个字符串只有一个模板时保留函数结果的类型，例如 random_int 得到数字
+    fn render_str(&mut self, s: &str) -> Result<Value> {
+        let mut output = String::new();
+        let mut rest = s;
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| anyhow!("Unclosed template`模板未闭合: {}", s))?
+                + start;
+            let value = self.call(rest[start + 2..end].trim())?;
+            if start == 0 && end + 2 == rest.len() && output.is_empty() {
+                return Ok(value);
+            }
+            output.push_str(&rest[..start]);
+            match value {
+                Value::String(v) => output.push_str(&v),
+                v => output.push_str(&v.to_string()),
+            }
+            rest = &rest[end + 2..];
+        }
+        output.push_str(rest);
+        Ok(Value::String(output))
+    }
+
+    fn call(&mut self, expr: &str) -> Result<Value> {
+        let (name, args) = expr
+            .strip_suffix(')')
+            .and_then(|expr| expr.split_once('('))
+            .ok_or_else(|| anyhow!("Invalid template expression`无效的模板表达式: {}", expr))?;
+        let args: Vec<&str> = args
+            .split(',')
+            .map(|arg| arg.trim().trim_matches(|c| c == '"' || c == '\''))
+            .filter(|arg| !arg.is_empty())
+            .collect();
+        match (name.trim(), args.as_slice()) {
+            ("fake_first_name", []) => Ok(self.pick(FIRST_NAMES).into()),
+            ("fake_last_name", []) => Ok(self.pick(LAST_NAMES).into()),
+            ("fake_name", []) => {
+                Ok(format!("{} {}", self.pick(FIRST_NAMES), self.pick(LAST_NAMES)).into())
+            }
+            ("fake_email", []) => {
+                let user = format!(
+                    "{}.{}{}",
+                    self.pick(FIRST_NAMES),
+                    self.pick(LAST_NAMES),
+                    self.rng.gen_range(1..1000)
+                );
+                Ok(format!("{}@{}", user.to_lowercase(), self.pick(DOMAINS)).into())
+            }
+            ("random_int", [min, max]) => {
+                let (min, max): (i64, i64) = (min.parse()?, max.parse()?);
+                if min > max {
+                    return Err(anyhow!(
+                        "random_int: min must not exceed max`最小值大于最大值"
+                    ));
+                }
+                Ok(self.rng.gen_range(min..=max).into())
+            }
+            (name, _) => Err(anyhow!(
+                "Unknown template function or wrong arguments`未知的模板函数或参数错误: {}",
+                name
+            )),
+        }
+    }
+
+    fn pick(&mut self, items: &[&'static str]) -> &'static str {
+        items[self.rng.gen_range(0..items.len())]
+    }
+}
@@ -0,0 +1,109 @@
+use super::SentRequest;
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    Response,
+};
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// 全局的跟踪级别：1 输出请求行、请求头和响应的元数据，2 额外输出请求体和 DNS 耗时
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// 设置输出到 stderr 的请求跟踪级别，对应命令行 `-v` 的次数，0 为不输出
+/// set how much of each request and response is traced to stderr
+pub fn set_verbosity(level: u8) {
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
+
+pub(crate) fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+/// 记录 DNS 解析耗时的 resolver；URL 中是 IP 地址时不经过解析，没有耗时
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TimedResolver {
+    elapsed: Arc<Mutex<Option<Duration>>>,
+}
+
+impl TimedResolver {
+    /// 最近一次解析的耗时
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let elapsed = self.elapsed.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            // 端口由连接时替换，这里只需要地址
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            *elapsed.lock().unwrap() = Some(start.elapsed());
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// 按 `curl -v` 的格式把一次请求和响应输出到 stderr：`>` 为请求，`<` 为响应，`*` 为连接信息 \
+/// 整段一次输出，同时运行的多个请求不会交错
+pub(crate) fn trace(req: &SentRequest, res: &Response, elapsed: Duration, dns: Option<Duration>) {
+    let level = verbosity();
+    if level == 0 {
+        return;
+    }
+    let mut output = String::new();
+    if let Some(addr) = res.remote_addr() {
+        let _ = writeln!(output, "* Connected to {}", addr);
+    }
+    let _ = writeln!(output, "> {} {}", req.method, req.url);
+    for (name, value) in &req.headers {
+        let _ = writeln!(
+            output,
+            "> {}: {}",
+            name,
+            String::from_utf8_lossy(value.as_bytes())
+        );
+    }
+    if level > 1 {
+        let body = req.body_text();
+        if !body.is_empty() {
+            let _ = writeln!(output, ">");
+            for line in body.lines() {
+                let _ = writeln!(output, "> {}", line);
+            }
+        }
+    }
+    let _ = writeln!(output, "< {:?} {}", res.version(), res.status());
+    for (name, value) in res.headers() {
+        let _ = writeln!(
+            output,
+            "< {}: {}",
+            name,
+            String::from_utf8_lossy(value.as_bytes())
+        );
+    }
+    // reqwest 0.11 不能替换 connector，无法单独记录建立连接和 TLS 握手的耗时；
+    // 每次发送都使用新的 Client 和新的连接，所以响应头的耗时包含这两部分
+    let mut timings = vec![];
+    if let (true, Some(dns)) = (level > 1, dns) {
+        timings.push(format!("dns {}ms", dns.as_millis()));
+    }
+    let phases = match req.url.scheme() {
+        "https" => "connect, TLS and response headers",
+        _ => "connect and response headers",
+    };
+    timings.push(format!("{} {}ms", phases, elapsed.as_millis()));
+    let _ = writeln!(output, "* {}", timings.join(", "));
+    eprint!("{}", output);
+}
@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Result};
+use jaq_core::{
+    load::{Arena, File, Loader},
+    Compiler, Ctx, Native, RcIter,
+};
+use jaq_json::Val;
+use serde::{Deserialize, Serialize};
+
+/// jq 表达式，在比较前对解析后的 JSON 做任意变换 \
+/// 例如 `del(.debug) | .items |= sort_by(.id)`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Transform {
+    /// 两个响应使用同一个表达式
+    Both(String),
+    /// 分别设置两个响应的表达式
+    Sides {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        req1: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        req2: Option<String>,
+    },
+}
+
+impl Transform {
+    /// 两个响应各自的表达式
+    pub(crate) fn sides(&self) -> [Option<&str>; 2] {
+        match self {
+            Transform::Both(expr) => [Some(expr), Some(expr)],
+            Transform::Sides { req1, req2 } => [req1.as_deref(), req2.as_deref()],
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        for expr in self.sides().into_iter().flatten() {
+            compile(expr)?;
+        }
+        Ok(())
+    }
+}
+
+/// 执行 jq 表达式，多个结果合并为数组，没有结果时为 null
+pub(crate) fn apply(expr: &str, json: serde_json::Value) -> Result<serde_json::Value> {
+    let filter = compile(expr)?;
+    let inputs = RcIter::new(core::iter::empty());
+    let mut values = vec![];
+    for value in filter.run((Ctx::new([], &inputs), Val::from(json))) {
+        let value = value.map_err(|e| anyhow!("jq transform failed`jq 变换失败: {}", e))?;
+        values.push(serde_json::Value::from(value));
+    }
+    Ok(match values.len() {
+        0 => serde_json::Value::Null,
+        1 => values.remove(0),
+        _ => serde_json::Value::Array(values),
+    })
+}
+
+fn compile(expr: &str) -> Result<jaq_core::Filter<Native<Val>>> {
+    let invalid = || anyhow!("Invalid jq expression`无效的 jq 表达式: {}", expr);
+    let program = File {
+        code: expr,
+        path: (),
+    };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader.load(&arena, program).map_err(|_| invalid())?;
+    Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|_| invalid())
+}
@@ -1,20 +1,55 @@
-use super::RequestProfile;
-use crate::{is_default, utils::diff_text, ConfigValidate, ExtraArgs, LoadConfig};
-use anyhow::{Context, Result};
+use super::{
+    normalize, snapshot, suppress, transform, Assertions, BinaryMode, ConditionalOutcome,
+    CookieProfile, CsvProfile, HtmlProfile, ImageProfile, IncludeRequest, MaskRule, MatchBy,
+    NormalizePreset, RequestDelta, RequestProfile, ResponseBody, ResponseExt, ResponseParts,
+    SentRequest, SortArrays, SseProfile, Suppression, TimestampRule, Transform, Validators,
+};
+use crate::{
+    diff_hunks, is_default, json_changes, json_patch,
+    utils::{diff_json, diff_text_with, truncate_diff},
+    ConfigValidate, DiffColors, DiffContext, DiffMode, DiffOptions, DiffReport, DiffResult,
+    ExtraArgs, JsonPath, LoadConfig, OutputFormat,
+};
+use anyhow::{anyhow, Context, Result};
+use reqwest::{header::HeaderMap, Method};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    path::Path,
+    time::Duration,
+};
+use url::Url;
 
 /// 配置文件结构体, 用于保存多个 DiffProfile
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DiffConfig {
+    // 全局设置，放在单独的 settings 节点中，不会和 profile 的名称冲突
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub settings: ConfigSettings,
     // 不定项字段，包含多个 DiffProfile
     #[serde(flatten)]
     pub profiles: HashMap<String, DiffProfile>,
 }
 
+/// 配置文件中 `settings` 节点的全局设置，未知的字段会报错 \
+/// xreq 只使用其中的 theme
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigSettings {
+    // 语法高亮主题的名称或者 .tmTheme 文件，命令行的 --theme 优先
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub theme: Option<String>,
+    // diff 的颜色和样式，包含红绿色盲友好的 colorblind 预设
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub colors: Option<DiffColors>,
+}
+
 /// 保存需要进行差异比较的请求配置；\
 /// 包含比较 `req1:req2` 两个请求的配置和一个响应`res`配置
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(try_from = "DiffProfileSpec")]
 pub struct DiffProfile {
     // 请求1配置
     pub req1: RequestProfile,
@@ -23,33 +58,475 @@ pub struct DiffProfile {
     // 响应配置
     #[serde(skip_serializing_if = "is_default", default)]
     pub res: ResponseProfile,
+    // 发送 req1 之后等待多少毫秒再发送 req2
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub delay_ms: u64,
+    // 比较之前对两个响应分别检查的断言
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub assert: Option<Assertions>,
+    // 预期中的差异，不出现在结果中而是单独列出
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub suppress: Vec<Suppression>,
+    // req2 之后的请求(req3、req4...)，用于同时比较多个部署
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub reqs: Vec<RequestProfile>,
+    // 多于两个请求时的比较方式
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub compare: CompareMode,
+    // 设置后不发送 req2，而是和这个文件中保存的响应体(golden 快照)比较
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub snapshot: Option<String>,
+    // 标签，例如 smoke、slow，命令行可以用 --tag 和 --skip-tag 选择要运行的 profile
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+    // 用每个响应中的 ETag/Last-Modified 再发送一次条件请求，两边的处理不同(例如一边返回 304)时给出警告
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub conditional: bool,
+}
+
+/// 多于两个请求时的比较方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompareMode {
+    /// 其他请求都和第一个请求比较，例如以 baseline 为准比较 canary 和 shadow
+    #[default]
+    AgainstFirst,
+    /// 所有请求两两比较
+    Pairwise,
+}
+
+/// 配置文件中 DiffProfile 的原始形式，支持四种写法：\
+/// 1. `req1` + `req2`，`req2` 可以只写出与 `req1` 不同的部分 \
+/// 2. `request` + `hosts`，同一个请求分别发送到两个或更多的 host \
+/// 3. `reqs: [a, b, c]`，第一个请求必须完整，之后的可以只写出与第一个不同的部分；
+///    和 `req1` + `req2` 一起使用时为 req2 之后的请求 \
+/// 4. `req1` + `snapshot`，实时的响应和保存的快照比较
+#[derive(Debug, Deserialize)]
+struct DiffProfileSpec {
+    req1: Option<RequestProfile>,
+    req2: Option<RequestDelta>,
+    request: Option<RequestDelta>,
+    #[serde(default)]
+    hosts: Vec<Url>,
+    #[serde(default)]
+    reqs: Vec<RequestDelta>,
+    #[serde(default)]
+    compare: CompareMode,
+    snapshot: Option<String>,
+    #[serde(default)]
+    res: ResponseProfile,
+    #[serde(default)]
+    delay_ms: u64,
+    assert: Option<Assertions>,
+    #[serde(default)]
+    suppress: Vec<Suppression>,
+    // 同时作用于两个请求的 User-Agent，请求中单独设置的优先
+    user_agent: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    conditional: bool,
+}
+
+impl TryFrom<DiffProfileSpec> for DiffProfile {
+    type Error = anyhow::Error;
+
+    // serde 只会输出错误的 Display，所以把原因拼接到同一条信息中
+    fn try_from(spec: DiffProfileSpec) -> Result<Self> {
+        // 只有 reqs 时前两个请求作为 req1 和 req2，错误信息中的下标需要加上偏移
+        let offset = match spec.req1.is_none() && spec.request.is_none() {
+            true => 2,
+            false => 0,
+        };
+        let mut reqs = spec.reqs;
+        if spec.snapshot.is_some() && !reqs.is_empty() {
+            return Err(anyhow!(
+                "`snapshot` can not be used together with `reqs``snapshot 不能和 reqs 一起使用"
+            ));
+        }
+        // 快照代替 req2 的响应，同时写出 req2 时 req2 不会被比较
+        if spec.snapshot.is_some() && spec.req2.is_some() {
+            return Err(anyhow!(
+                "`snapshot` can not be used together with `req2``snapshot 不能和 req2 一起使用"
+            ));
+        }
+        if spec.snapshot.is_some() && spec.request.is_some() {
+            return Err(anyhow!(
+                "`snapshot` can not be used together with `request``snapshot 不能和 request 一起使用"
+            ));
+        }
+        // request 的请求由 hosts 展开，不能再和其他写法混用
+        let with_req = spec.req1.is_some() || spec.req2.is_some();
+        match (spec.request.is_some(), spec.hosts.is_empty()) {
+            (true, _) if with_req => {
+                return Err(anyhow!(
+                    "`request` and `req1`/`req2` are mutually exclusive`request 和 req1/req2 不能同时使用"
+                ))
+            }
+            (true, _) if !reqs.is_empty() => {
+                return Err(anyhow!(
+                    "`request` and `reqs` are mutually exclusive, list the targets in `hosts` instead`request 和 reqs 不能同时使用，请把目标写在 hosts 中"
+                ))
+            }
+            (false, false) => {
+                return Err(anyhow!(
+                    "`hosts` can only be used together with `request``hosts 只能和 request 一起使用"
+                ))
+            }
+            _ => {}
+        }
+        let (profile, base) = match (spec.req1, spec.req2, spec.request) {
+            // 快照代替 req2，req2 只是占位，不会发送
+            (Some(req1), None, None) if spec.snapshot.is_some() => {
+                (Self::new(req1.clone(), req1.clone(), spec.res), Some(req1))
+            }
+            (Some(req1), Some(req2), None) => {
+                let req2 = req2
+                    .resolve(&req1)
+                    .map_err(|e| anyhow!("req2 failed to resolve: {}", e))?;
+                (Self::new(req1.clone(), req2, spec.res), Some(req1))
+            }
+            (None, None, Some(request)) => {
+                if spec.hosts.len() < 2 {
+                    return Err(anyhow!(
+                        "`request` requires at least two `hosts` but got`hosts 至少需要两个: {}",
+                        spec.hosts.len()
+                    ));
+                }
+                let resolve = |host: &Url| {
+                    request
+                        .clone()
+                        .resolve_on_host(host)
+                        .map_err(|e| anyhow!("request failed to resolve on {}: {}", host, e))
+                };
+                let mut requests = spec.hosts.iter().map(resolve).collect::<Result<Vec<_>>>()?;
+                let others = requests.split_off(2);
+                let req2 = requests.pop().unwrap();
+                let req1 = requests.pop().unwrap();
+                let profile = Self {
+                    reqs: others,
+                    ..Self::new(req1, req2, spec.res)
+                };
+                (profile, None)
+            }
+            (None, None, None) if reqs.len() >= 2 => {
+                let first = reqs.remove(0);
+                let Some(url) = first.url.clone() else {
+                    return Err(anyhow!(
+                        "the first of `reqs` must have a `url``reqs 中的第一个请求必须有 url"
+                    ));
+                };
+                let base = RequestProfile::new(Method::default(), url, None, HeaderMap::new(), None);
+                let req1 = first
+                    .resolve(&base)
+                    .map_err(|e| anyhow!("reqs[0] failed to resolve: {}", e))?;
+                let req2 = reqs
+                    .remove(0)
+                    .resolve(&req1)
+                    .map_err(|e| anyhow!("reqs[1] failed to resolve: {}", e))?;
+                (Self::new(req1.clone(), req2, spec.res), Some(req1))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "profile needs either `req1` and `req2`, `request` and `hosts`, or at least two `reqs``需要 req1 和 req2，request 和 hosts，或者至少两个 reqs"
+                ))
+            }
+        };
+        let mut others = profile.reqs.clone();
+        if let Some(base) = base {
+            for (i, req) in reqs.into_iter().enumerate() {
+                let req = req
+                    .resolve(&base)
+                    .map_err(|e| anyhow!("reqs[{}] failed to resolve: {}", i + offset, e))?;
+                others.push(req);
+            }
+        }
+        let mut profile = Self {
+            delay_ms: spec.delay_ms,
+            assert: spec.assert,
+            suppress: spec.suppress,
+            reqs: others,
+            compare: spec.compare,
+            snapshot: spec.snapshot,
+            tags: spec.tags,
+            conditional: spec.conditional,
+            ..profile
+        };
+        if let Some(user_agent) = spec.user_agent {
+            for req in profile.requests_mut() {
+                req.user_agent.get_or_insert_with(|| user_agent.clone());
+            }
+        }
+        Ok(profile)
+    }
 }
 
 /// 用于保存需要跳过的响应头和响应体字段
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct ResponseProfile {
-    // 跳过的响应头字段
+    // 跳过的响应头字段，不区分大小写，支持 `x-request-*` 通配符、`/^cf-/` 正则表达式和 `preset:volatile` 预设
     #[serde(skip_serializing_if = "Vec::is_empty ", default)]
     pub skip_headers: Vec<String>,
-    // 跳过的响应体字段
+    // 只比较这些响应头，规则和 skip_headers 相同；为空时比较所有未跳过的响应头
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub only_headers: Vec<String>,
+    // 解析 set-cookie 后比较，忽略 expires、max-age 等易变的属性
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub set_cookie: Option<CookieProfile>,
+    // 默认按名称排序响应头并合并同名的响应头，设置后保持服务器返回的顺序
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub preserve_header_order: bool,
+    // 按名称比较响应头，输出新增、删除和修改的响应头，不受顺序影响
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub semantic_headers: bool,
+    // 跳过的响应体字段，字段名会删除任意层级的同名字段；也支持 `$.data[*].updated_at` 或 `/meta/ts` 形式的路径；XML 响应只使用 `/` 开头的 XPath，字段名不会作用于 XML
     #[serde(skip_serializing_if = "Vec::is_empty ", default)]
     pub skip_body: Vec<String>,
+    // 把 skip_body 中字段的值替换为 `<redacted>` 而不是删除，一侧缺少字段时仍然可以看到差异
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub redact_body: bool,
+    // 只比较响应体中选中的部分，例如 `$.data`，其他规则中的路径相对于选中的部分
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub select: Option<String>,
+    // 对响应体做正则替换，用于屏蔽字符串中的请求 ID 等
+    #[serde(skip_serializing_if = "Vec::is_empty ", default)]
+    pub mask: Vec<MaskRule>,
+    // 时间戳规范化规则，避免两个服务的时钟偏差造成差异
+    #[serde(skip_serializing_if = "Vec::is_empty ", default)]
+    pub timestamps: Vec<TimestampRule>,
+    // 内置的规范化预设：uuids, numeric_ids
+    #[serde(skip_serializing_if = "Vec::is_empty ", default)]
+    pub normalize: Vec<NormalizePreset>,
+    // 数值误差，`$.metrics.*: 0.001` 表示匹配到的数值相差不超过 0.001 时视为相等
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub tolerance: BTreeMap<String, f64>,
+    // 比较前对数组排序，忽略元素的顺序
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub sort_arrays: SortArrays,
+    // 保留响应中字段的原始顺序，默认按字段名排序后再比较
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub preserve_key_order: bool,
+    // 按标识字段配对数组中的对象，例如 `match_by: id`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub match_by: Option<MatchBy>,
+    // 去掉每行末尾的空白字符
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub trim_whitespace: bool,
+    // 把行内连续的空白字符合并为一个空格
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub collapse_whitespace: bool,
+    // 比较响应头的值时不区分大小写
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub lowercase_header_values: bool,
+    // 解析 text/html 响应并规范化输出
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub html: Option<HtmlProfile>,
+    // text/csv 响应按单元格比较的配置，没有设置时按原文比较
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub csv: Option<CsvProfile>,
+    // 二进制响应的比较方式：summary(大小和 sha256) 或 hexdump
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub binary: BinaryMode,
+    // 图片响应比较格式、尺寸和像素
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub image: Option<ImageProfile>,
+    // 响应头中没有声明 charset 时使用的字符集，例如 gbk
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub charset: Option<String>,
+    // 响应体最多读取的字节数，超过的部分被丢弃，只统计实际大小
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_body_bytes: Option<usize>,
+    // 状态码的比较方式：作为文本比较(默认)、ignore、must_match 或 expect: [200, 404]
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub status: StatusPolicy,
+    // 任意一边返回非 2xx 响应时直接失败，不比较错误页面
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub fail_on_error: bool,
+    // 在响应前面输出实际发送的请求：请求行、选中的请求头和请求体
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub include_request: Option<IncludeRequest>,
+    // 比较前对 JSON 执行的 jq 表达式，可以两边共用或者分别设置 req1、req2
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub transform: Option<Transform>,
+    // 比较两边的响应时间并在差异之后输出，慢的一边超过 warn_ratio 倍时给出警告
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub latency: Option<LatencyProfile>,
+    // text/event-stream 响应读取的事件数和时间，默认读取 5 秒
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sse: Option<SseProfile>,
+    // 差异前后显示的上下文行数，`full` 显示完整的响应，命令行的 --context 优先
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub context: Option<DiffContext>,
+}
+
+/// 状态码的比较方式
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusPolicy {
+    /// 状态行和响应头、响应体一起作为文本比较
+    #[default]
+    Diff,
+    /// 不比较状态行
+    Ignore,
+    /// 状态码不同时直接失败
+    MustMatch,
+    /// 两边的状态码都必须在列表中，否则失败
+    Expect(Vec<u16>),
+}
+
+/// 响应时间的比较配置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LatencyProfile {
+    // 慢的一边是快的一边的多少倍时给出警告
+    pub warn_ratio: f64,
+}
+
+impl LatencyProfile {
+    /// 响应时间相差超过 warn_ratio 倍时返回警告
+    pub fn check(&self, elapsed1: Duration, elapsed2: Duration) -> Option<String> {
+        let (fast, slow) = match elapsed1 <= elapsed2 {
+            true => (("req1", elapsed1), ("req2", elapsed2)),
+            false => (("req2", elapsed2), ("req1", elapsed1)),
+        };
+        let ratio = slow.1.as_secs_f64() / fast.1.as_secs_f64().max(1e-6);
+        (ratio > self.warn_ratio).then(|| {
+            format!(
+                "warning: {} is {:.1}x slower than {} (warn_ratio {})",
+                slow.0, ratio, fast.0, self.warn_ratio
+            )
+        })
+    }
 }
 
 impl ResponseProfile {
     pub fn new(skip_headers: Vec<String>, skip_body: Vec<String>) -> Self {
         Self {
             skip_headers,
+            only_headers: vec![],
+            set_cookie: None,
+            preserve_header_order: false,
+            semantic_headers: false,
             skip_body,
+            redact_body: false,
+            select: None,
+            mask: vec![],
+            timestamps: vec![],
+            normalize: vec![],
+            tolerance: BTreeMap::new(),
+            sort_arrays: SortArrays::default(),
+            preserve_key_order: false,
+            match_by: None,
+            trim_whitespace: false,
+            collapse_whitespace: false,
+            lowercase_header_values: false,
+            html: None,
+            csv: None,
+            binary: BinaryMode::default(),
+            image: None,
+            charset: None,
+            max_body_bytes: None,
+            status: StatusPolicy::default(),
+            fail_on_error: false,
+            include_request: None,
+            transform: None,
+            latency: None,
+            sse: None,
+            context: None,
+        }
+    }
+
+    /// 按 fail_on_error 和 status 配置检查两个响应的状态码 \
+    /// fail_on_error 直接返回错误；status 不符合时返回失败信息，和断言失败一起输出
+    pub fn check_status(
+        &self,
+        (name1, parts1): (&str, &ResponseParts),
+        (name2, parts2): (&str, &ResponseParts),
+    ) -> Result<Vec<String>> {
+        if self.fail_on_error {
+            for (name, parts) in [(name1, parts1), (name2, parts2)] {
+                if !(200..300).contains(&parts.status_code) {
+                    return Err(ResponseStatusError {
+                        name: name.to_string(),
+                        status: parts.status.clone(),
+                    }
+                    .into());
+                }
+            }
+        }
+        let (status1, status2) = (parts1.status_code, parts2.status_code);
+        let failures = match &self.status {
+            StatusPolicy::MustMatch if status1 != status2 => vec![format!(
+                "Status code mismatch`状态码不一致: {} != {}",
+                status1, status2
+            )],
+            StatusPolicy::Expect(codes) => [(name1, status1), (name2, status2)]
+                .into_iter()
+                .filter(|(_, status)| !codes.contains(status))
+                .map(|(name, status)| {
+                    format!(
+                        "Unexpected status code`状态码不符合预期: {} returned {}, expected {:?}",
+                        name, status, codes
+                    )
+                })
+                .collect(),
+            _ => vec![],
+        };
+        Ok(failures)
+    }
+
+    /// 需要两个响应一起处理的比较：各自的 jq 变换、JSON 数值误差和图片像素
+    pub fn compare_bodies(&self, body1: &mut ResponseBody, body2: &mut ResponseBody) -> Result<()> {
+        if let Some(transform) = &self.transform {
+            for (body, expr) in [&mut *body1, &mut *body2]
+                .into_iter()
+                .zip(transform.sides())
+            {
+                if let (ResponseBody::Json(json), Some(expr)) = (body, expr) {
+                    *json = transform::apply(expr, json.take())?;
+                    if !self.preserve_key_order {
+                        normalize::sort_keys(json);
+                    }
+                }
+            }
+        }
+        if let (ResponseBody::Json(json1), ResponseBody::Json(json2)) = (&*body1, &mut *body2) {
+            for (path, epsilon) in &self.tolerance {
+                normalize::apply_tolerance(json1, json2, &JsonPath::parse(path)?, *epsilon);
+            }
+        }
+        if let (Some(image), ResponseBody::Image(bytes1), ResponseBody::Image(bytes2)) =
+            (&self.image, &*body1, &*body2)
+        {
+            let (text1, text2) = image.compare(bytes1, bytes2)?;
+            *body1 = ResponseBody::Text(text1);
+            *body2 = ResponseBody::Text(text2);
+        }
+        Ok(())
+    }
+}
+
+impl LoadConfig for DiffConfig {
+    fn load_files(&mut self, base_dir: &Path) -> Result<()> {
+        for (name, profile) in self.profiles.iter_mut() {
+            for req in profile.requests_mut() {
+                req.load_files(base_dir)
+                    .with_context(|| format!("failed to load profile`加载失败: `{}`", name))?;
+            }
+            if let Some(snapshot) = profile.snapshot.as_mut() {
+                *snapshot = base_dir.join(&*snapshot).display().to_string();
+            }
         }
+        Ok(())
     }
 }
-impl LoadConfig for DiffConfig {}
 
 impl DiffConfig {
     // 接受一个DiffProfile集合，构建DiffConfig
     pub fn new(profiles: HashMap<String, DiffProfile>) -> Self {
-        Self { profiles }
+        Self {
+            settings: ConfigSettings::default(),
+            profiles,
+        }
     }
 
     // 获取指定名称的 DiffProfile
@@ -62,35 +539,539 @@ impl DiffConfig {
 impl DiffProfile {
     // 创建new函数，传入请求配置[1,2]，和响应：req1,req2,res
     pub fn new(req1: RequestProfile, req2: RequestProfile, res: ResponseProfile) -> Self {
-        Self { req1, req2, res }
+        Self {
+            req1,
+            req2,
+            res,
+            delay_ms: 0,
+            assert: None,
+            suppress: vec![],
+            reqs: vec![],
+            compare: CompareMode::default(),
+            snapshot: None,
+            tags: vec![],
+            conditional: false,
+        }
     }
 
-    // 差异比较，返回结果
-    pub async fn diff(&self, args: &ExtraArgs) -> Result<String> {
-        // 用 args 覆盖请求中的参数：headers，query，body
-        // use args to override the parameters in the request
-        let res1 = self.req1.send(&args).await?;
-        let res2 = self.req2.send(&args).await?;
+    /// 应用命令行参数之后实际会发送的 profile，req1 使用 args1，其他请求使用 args2
+    pub fn resolve_args(&self, args1: &ExtraArgs, args2: &ExtraArgs) -> Result<Self> {
+        let mut profile = self.clone();
+        for (i, req) in profile.requests_mut().into_iter().enumerate() {
+            let args = match i {
+                0 => args1,
+                _ => args2,
+            };
+            *req = req.resolve_args(args)?;
+        }
+        Ok(profile)
+    }
 
-        // 过滤响应内容字段
-        // filter response content fields
-        let text1 = res1.get_text(&self.res).await?;
-        let text2 = res2.get_text(&self.res).await?;
+    /// 构造所有会发送的请求但不发送，返回 `req1` 这样的名称和请求；配置了快照时不包含 req2
+    pub fn dry_run(
+        &self,
+        args1: &ExtraArgs,
+        args2: &ExtraArgs,
+    ) -> Result<Vec<(String, SentRequest)>> {
+        let mut requests = vec![];
+        for (i, req) in self.requests().into_iter().enumerate() {
+            let args = match (i, &self.snapshot) {
+                (0, _) => args1,
+                (1, Some(_)) => continue,
+                _ => args2,
+            };
+            requests.push((format!("req{}", i + 1), req.dry_run(args)?));
+        }
+        Ok(requests)
+    }
 
-        diff_text(&text1, &text2)
+    /// 是否有 tags 中的任意一个标签
+    pub fn has_any_tag(&self, tags: &[String]) -> bool {
+        self.tags.iter().any(|tag| tags.contains(tag))
+    }
+
+    /// 所有请求：req1、req2 以及 reqs 中的请求
+    pub fn requests(&self) -> Vec<&RequestProfile> {
+        [&self.req1, &self.req2]
+            .into_iter()
+            .chain(&self.reqs)
+            .collect()
+    }
+
+    fn requests_mut(&mut self) -> Vec<&mut RequestProfile> {
+        [&mut self.req1, &mut self.req2]
+            .into_iter()
+            .chain(&mut self.reqs)
+            .collect()
+    }
+
+    /// 需要比较的请求下标，按 compare 配置生成
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let n = self.reqs.len() + 2;
+        match self.compare {
+            CompareMode::AgainstFirst => (1..n).map(|j| (0, j)).collect(),
+            CompareMode::Pairwise => (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .collect(),
+        }
+    }
+
+    // 差异比较，返回结构化的结果，可以交给任意 DiffRenderer 渲染
+    pub async fn diff(&self, args: &ExtraArgs) -> Result<DiffResult> {
+        self.diff_with(args, args, &DiffOptions::default()).await
+    }
+
+    // 差异比较，两个请求分别使用各自的参数 \
+    // 多于两个请求时只比较 req1 和 req2，其他的请使用 compare_all
+    pub async fn diff_with(
+        &self,
+        args1: &ExtraArgs,
+        args2: &ExtraArgs,
+        options: &DiffOptions,
+    ) -> Result<DiffResult> {
+        let mut parts = self
+            .fetch(2, args1, args2, options.record.as_deref())
+            .await?;
+        let parts2 = parts.pop().unwrap();
+        let parts1 = parts.pop().unwrap();
+        self.compare_parts(("req1", parts1), ("req2", parts2), options)
+    }
+
+    // 差异比较，返回包含状态码、响应时间和断言结果的报告
+    pub async fn compare(
+        &self,
+        args1: &ExtraArgs,
+        args2: &ExtraArgs,
+        options: &DiffOptions,
+    ) -> Result<DiffReport> {
+        Ok(self.diff_with(args1, args2, options).await?.report)
+    }
+
+    // 发送所有请求后按 compare 配置逐对比较，返回 `req1 vs req3` 这样的名称和报告 \
+    // req1 使用 args1，其他请求使用 args2
+    pub async fn compare_all(
+        &self,
+        args1: &ExtraArgs,
+        args2: &ExtraArgs,
+        options: &DiffOptions,
+    ) -> Result<Vec<(String, DiffReport)>> {
+        let parts = self
+            .fetch(self.reqs.len() + 2, args1, args2, options.record.as_deref())
+            .await?;
+        let conditional = match self.conditional {
+            true => self.send_conditional(&parts, args1, args2).await?,
+            false => vec![],
+        };
+        self.compare_pairs(parts, &conditional, options)
+    }
+
+    // 用 record 保存的响应重新过滤和比较，不发送请求
+    pub async fn replay(
+        &self,
+        dir: &Path,
+        options: &DiffOptions,
+    ) -> Result<Vec<(String, DiffReport)>> {
+        let mut parts = vec![];
+        for (i, req) in self.requests().into_iter().enumerate() {
+            if let (1, Some(snapshot)) = (i, &self.snapshot) {
+                parts.push(snapshot::snapshot_parts(snapshot, &parts[0])?);
+                continue;
+            }
+            let res = ResponseExt::replay(dir, &format!("req{}", i + 1), req)?;
+            parts.push(res.get_parts(&self.res).await?);
+        }
+        self.compare_pairs(parts, &[], options)
+    }
+
+    // conditional 是每个请求对条件请求的处理结果，为空时不比较
+    fn compare_pairs(
+        &self,
+        parts: Vec<ResponseParts>,
+        conditional: &[Option<ConditionalOutcome>],
+        options: &DiffOptions,
+    ) -> Result<Vec<(String, DiffReport)>> {
+        let mut reports = vec![];
+        for (i, j) in self.pairs() {
+            let (name1, name2) = (format!("req{}", i + 1), format!("req{}", j + 1));
+            let mut report = self
+                .compare_parts(
+                    (&name1, parts[i].clone()),
+                    (&name2, parts[j].clone()),
+                    options,
+                )?
+                .report;
+            if let (Some(Some(outcome1)), Some(Some(outcome2))) =
+                (conditional.get(i), conditional.get(j))
+            {
+                if !outcome1.same_handling(outcome2) {
+                    report.warnings.push(format!(
+                        "conditional requests handled differently`条件请求的处理不同: {}: {}; {}: {}",
+                        name1, outcome1, name2, outcome2
+                    ));
+                }
+            }
+            reports.push((format!("{} vs {}", name1, name2), report));
+        }
+        Ok(reports)
+    }
+
+    // 用每个响应中的校验器重新发送请求，返回每个请求对条件请求的处理结果；快照没有对应的请求，结果为 None
+    async fn send_conditional(
+        &self,
+        parts: &[ResponseParts],
+        args1: &ExtraArgs,
+        args2: &ExtraArgs,
+    ) -> Result<Vec<Option<ConditionalOutcome>>> {
+        let mut outcomes = vec![];
+        for (i, (req, parts)) in self.requests().into_iter().zip(parts).enumerate() {
+            if i == 1 && self.snapshot.is_some() {
+                outcomes.push(None);
+                continue;
+            }
+            let validators = Validators::from_headers(&parts.header_map);
+            if validators.is_empty() {
+                outcomes.push(Some(ConditionalOutcome::NoValidators));
+                continue;
+            }
+            if i > 0 && self.delay_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            }
+            let mut args = match i {
+                0 => args1,
+                _ => args2,
+            }
+            .clone();
+            args.headers.extend(validators.to_headers());
+            let res = req.send(&args).await?;
+            let current = Validators::from_headers(res.headers());
+            outcomes.push(Some(validators.outcome(res.status(), &current)));
+        }
+        Ok(outcomes)
+    }
+
+    // 重新发送 req1，把过滤后的响应体写入快照文件；没有配置快照时返回 false
+    pub async fn update_snapshot(&self, args: &ExtraArgs) -> Result<bool> {
+        let Some(snapshot) = &self.snapshot else {
+            return Ok(false);
+        };
+        let parts = self.fetch(1, args, args, None).await?;
+        snapshot::write_snapshot(snapshot, &parts[0].body)?;
+        Ok(true)
+    }
+
+    // 依次发送前 n 个请求，每两个请求之间等待 delay_ms；设置了 record 时保存原始响应
+    async fn fetch(
+        &self,
+        n: usize,
+        args1: &ExtraArgs,
+        args2: &ExtraArgs,
+        record: Option<&str>,
+    ) -> Result<Vec<ResponseParts>> {
+        let mut parts = vec![];
+        for (i, req) in self.requests().into_iter().take(n).enumerate() {
+            if i > 0 && self.delay_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            }
+            // 用 args 覆盖请求中的参数：headers，query，body
+            // use args to override the parameters in the request
+            if let (1, Some(snapshot)) = (i, &self.snapshot) {
+                parts.push(snapshot::snapshot_parts(snapshot, &parts[0])?);
+                continue;
+            }
+            let args = match i {
+                0 => args1,
+                _ => args2,
+            };
+            let mut res = req.send(args).await?;
+            if let Some(dir) = record {
+                res = res
+                    .record(Path::new(dir), &format!("req{}", i + 1), &self.res)
+                    .await?;
+            }
+            // 过滤响应内容字段
+            // filter response content fields
+            parts.push(res.get_parts(&self.res).await?);
+        }
+        Ok(parts)
+    }
+
+    fn compare_parts(
+        &self,
+        (name1, mut parts1): (&str, ResponseParts),
+        (name2, mut parts2): (&str, ResponseParts),
+        options: &DiffOptions,
+    ) -> Result<DiffResult> {
+        let mut failures = self.res.check_status((name1, &parts1), (name2, &parts2))?;
+        if let Some(assert) = &self.assert {
+            failures.extend(assert.check(name1, &parts1)?);
+            failures.extend(assert.check(name2, &parts2)?);
+        }
+        self.res
+            .compare_bodies(&mut parts1.body, &mut parts2.body)?;
+        ResponseParts::strip_compression_headers(&mut parts1, &mut parts2);
+        if let Some(part) = options.only {
+            parts1.retain(part);
+            parts2.retain(part);
+        }
+        let mut suppressed = suppress::suppress_parts(&self.suppress, &mut parts1, &mut parts2)?;
+        let changed_headers = parts1.changed_headers(&parts2);
+        // 按名称比较时响应头单独输出，不再参与文本比较
+        let semantic_headers = options.semantic_headers || self.res.semantic_headers;
+        let header_diff = match semantic_headers && options.format == OutputFormat::Text {
+            true => {
+                let diff = parts1.diff_headers(&parts2)?;
+                parts1.headers.clear();
+                parts2.headers.clear();
+                diff
+            }
+            false => String::new(),
+        };
+        let text1 = parts1.to_text(&self.res)?;
+        let text2 = parts2.to_text(&self.res)?;
+        let text2 = suppress::suppress_hunks(&self.suppress, &text1, &text2, &mut suppressed)?;
+        // 响应时间不参与文本比较，输出在差异之后
+        let options = &DiffOptions {
+            context: options.context.or(self.res.context),
+            labels: Some([name1.to_string(), name2.to_string()]),
+            ..options.clone()
+        };
+        let diff = header_diff + &self.diff_parts(&parts1, &parts2, (&text1, &text2), options)?;
+        // patch 和 JSON Patch 需要完整才能使用，只截断给人看的差异
+        let diff = match options.max_lines {
+            Some(max)
+                if options.mode != DiffMode::JsonPatch && options.format != OutputFormat::Patch =>
+            {
+                truncate_diff(diff, max)
+            }
+            _ => diff,
+        };
+        let mut warnings: Vec<String> = self
+            .res
+            .latency
+            .as_ref()
+            .and_then(|latency| latency.check(parts1.elapsed, parts2.elapsed))
+            .into_iter()
+            .collect();
+        for (name, parts) in [(name1, &parts1), (name2, &parts2)] {
+            let prefixed = parts.warnings.iter().map(|w| format!("{}: {}", name, w));
+            warnings.extend(prefixed);
+        }
+        let changes = match (parts1.json_body(&self.res)?, parts2.json_body(&self.res)?) {
+            (Some(json1), Some(json2)) => json_changes(&json1, &json2),
+            _ => vec![],
+        };
+        let changed_paths = changes
+            .iter()
+            .map(|change| change.path().to_string())
+            .collect();
+        let hunks = diff_hunks(&text1, &text2, options);
+        let similarity = TextDiff::from_lines(&text1, &text2).ratio();
+        let report = DiffReport {
+            names: [name1.to_string(), name2.to_string()],
+            status: [parts1.status_code, parts2.status_code],
+            elapsed: [parts1.elapsed, parts2.elapsed],
+            show_elapsed: self.res.latency.is_some(),
+            diff,
+            warnings,
+            failures,
+            changed_headers,
+            changed_paths,
+            hunks,
+            similarity,
+            min_similarity: options.min_similarity,
+            suppressed,
+        };
+        Ok(DiffResult {
+            names: [name1.to_string(), name2.to_string()],
+            texts: [text1, text2],
+            changes,
+            report,
+        })
+    }
+}
+
+impl DiffProfile {
+    // json 和 json-patch 模式下两边都是 JSON 时，状态行和响应头按文本比较，响应体按字段比较 \
+    // 其他情况比较已经应用了 hunk 规则的完整文本
+    fn diff_parts(
+        &self,
+        parts1: &ResponseParts,
+        parts2: &ResponseParts,
+        (text1, text2): (&str, &str),
+        options: &DiffOptions,
+    ) -> Result<String> {
+        if options.mode != DiffMode::Text && options.format == OutputFormat::Text {
+            let json1 = parts1.json_body(&self.res)?;
+            let json2 = parts2.json_body(&self.res)?;
+            if let (Some(json1), Some(json2)) = (json1, json2) {
+                let head1 = parts1.head_text(&self.res)?;
+                let head2 = parts2.head_text(&self.res)?;
+                let head2 = suppress::suppress_hunks(&self.suppress, &head1, &head2, &mut vec![])?;
+                let mut output = diff_text_with(&head1, &head2, options)?;
+                let changes = json_changes(&json1, &json2);
+                match options.mode {
+                    DiffMode::JsonPatch if !changes.is_empty() => {
+                        let patch = serde_json::to_string_pretty(&json_patch(&changes))?;
+                        writeln!(output, "{}", patch)?;
+                    }
+                    _ => output.push_str(&diff_json(&changes)?),
+                }
+                return Ok(output);
+            }
+        }
+        diff_text_with(text1, text2, options)
     }
 }
 
+/// fail_on_error 时非 2xx 响应产生的错误，命令行使用单独的退出码
+#[derive(Debug)]
+pub struct ResponseStatusError {
+    pub name: String,
+    pub status: String,
+}
+
+impl ResponseStatusError {
+    pub const EXIT_CODE: i32 = 3;
+}
+
+impl std::fmt::Display for ResponseStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} returned a non-success response`响应不成功: {}",
+            self.name, self.status
+        )
+    }
+}
+
+impl std::error::Error for ResponseStatusError {}
+
 impl ConfigValidate for DiffProfile {
     // 校验请求配置[1,2]是否正确，使用 RequestProfile 的 validate 方法验证
     fn validate(&self) -> Result<()> {
         self.req1.validate().context("req1 failed to validate")?;
         self.req2.validate().context("req2 failed to validate")?;
+        for (i, req) in self.reqs.iter().enumerate() {
+            req.validate()
+                .with_context(|| format!("req{} failed to validate", i + 3))?;
+        }
+        self.res.validate().context("res failed to validate")?;
+        if let Some(assert) = &self.assert {
+            assert.validate().context("assert failed to validate")?;
+        }
+        for rule in &self.suppress {
+            rule.validate().context("suppress failed to validate")?;
+        }
 
         Ok(())
     }
 }
 
+impl ConfigValidate for ResponseProfile {
+    // 校验规则中的路径表达式和正则表达式，返回第一个错误
+    fn validate(&self) -> Result<()> {
+        match self.field_errors().into_iter().next() {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ResponseProfile {
+    /// 逐个字段校验，返回所有出错的字段名称和错误，一个字段出错不影响检查其他字段
+    pub(crate) fn field_errors(&self) -> Vec<(&'static str, anyhow::Error)> {
+        let mut errors = vec![];
+        let mut check = |field: &'static str, result: Result<()>| {
+            if let Err(e) = result {
+                errors.push((field, e));
+            }
+        };
+        if let Some(charset) = &self.charset {
+            if encoding_rs::Encoding::for_label(charset.as_bytes()).is_none() {
+                check(
+                    "charset",
+                    Err(anyhow!("Unsupported charset`不支持的字符集: {}", charset)),
+                );
+            }
+        }
+        if let StatusPolicy::Expect(codes) = &self.status {
+            if codes.is_empty() {
+                check(
+                    "status",
+                    Err(anyhow!(
+                        "status expect list must not be empty`status expect 不能为空"
+                    )),
+                );
+            }
+        }
+        if self.max_body_bytes == Some(0) {
+            check(
+                "max_body_bytes",
+                Err(anyhow!(
+                    "max_body_bytes must be greater than 0`max_body_bytes 必须大于 0"
+                )),
+            );
+        }
+        check(
+            "skip_headers",
+            normalize::header_patterns(&self.skip_headers).map(|_| ()),
+        );
+        check(
+            "only_headers",
+            normalize::header_patterns(&self.only_headers).map(|_| ()),
+        );
+        if let Some(latency) = &self.latency {
+            if latency.warn_ratio.is_nan() || latency.warn_ratio < 1.0 {
+                check(
+                    "latency",
+                    Err(anyhow!(
+                        "latency warn_ratio must be at least 1`warn_ratio 不能小于 1"
+                    )),
+                );
+            }
+        }
+        if let Some(transform) = &self.transform {
+            check("transform", transform.validate());
+        }
+        if let Some(select) = &self.select {
+            check("select", JsonPath::parse(select).map(|_| ()));
+        }
+        check(
+            "skip_body",
+            self.skip_body
+                .iter()
+                .filter(|k| JsonPath::is_path(k))
+                .try_for_each(|path| JsonPath::parse(path).map(|_| ())),
+        );
+        check(
+            "mask",
+            self.mask.iter().try_for_each(|rule| rule.validate()),
+        );
+        check(
+            "timestamps",
+            self.timestamps.iter().try_for_each(|rule| rule.validate()),
+        );
+        check("sort_arrays", self.sort_arrays.validate());
+        if let Some(match_by) = &self.match_by {
+            check("match_by", match_by.validate());
+        }
+        check(
+            "tolerance",
+            self.tolerance.iter().try_for_each(|(path, epsilon)| {
+                JsonPath::parse(path)?;
+                if epsilon.is_nan() || *epsilon < 0.0 {
+                    return Err(anyhow!(
+                        "tolerance must be a non-negative number`误差必须为非负数: {}",
+                        path
+                    ));
+                }
+                Ok(())
+            }),
+        );
+        errors
+    }
+}
+
 impl ConfigValidate for DiffConfig {
     // 校验请求配置是否正确，使用 RequestProfile 的 validate 方法验证
     fn validate(&self) -> Result<()> {
@@ -99,6 +1080,148 @@ impl ConfigValidate for DiffConfig {
                 .validate()
                 .context(format!("failed to validate profile`验证失败: `{}`", name))?;
         }
+        if let Some(colors) = &self.settings.colors {
+            colors
+                .validate()
+                .context("settings.colors failed to validate")?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::{header, Response};
+
+    fn parts(status_code: u16) -> ResponseParts {
+        ResponseParts {
+            request: None,
+            status: format!("HTTP/1.1 {}", status_code),
+            status_code,
+            header_map: HeaderMap::new(),
+            headers: String::new(),
+            body: ResponseBody::Text(String::new()),
+            compressed: false,
+            elapsed: Duration::ZERO,
+            warnings: vec![],
+        }
+    }
+
+    #[test]
+    fn check_status_should_collect_policy_failures() {
+        let mut res = ResponseProfile {
+            status: StatusPolicy::Expect(vec![200]),
+            ..Default::default()
+        };
+        let failures = res
+            .check_status(("req1", &parts(200)), ("req2", &parts(404)))
+            .unwrap();
+        assert_eq!(
+            failures,
+            vec!["Unexpected status code`状态码不符合预期: req2 returned 404, expected [200]"]
+        );
+
+        res.fail_on_error = true;
+        assert!(res
+            .check_status(("req1", &parts(200)), ("req2", &parts(404)))
+            .is_err());
+    }
+
+    #[test]
+    fn compare_pairs_should_warn_when_conditional_handling_differs() {
+        let req: RequestProfile = "https://example.com/todos/1".parse().unwrap();
+        let profile = DiffProfile::new(req.clone(), req, ResponseProfile::default());
+        let compare = |outcome2| {
+            let conditional = [Some(ConditionalOutcome::NotModified), Some(outcome2)];
+            let reports = profile
+                .compare_pairs(
+                    vec![parts(200), parts(200)],
+                    &conditional,
+                    &DiffOptions::default(),
+                )
+                .unwrap();
+            reports[0].1.warnings.clone()
+        };
+        assert_eq!(
+            compare(ConditionalOutcome::Ignored(reqwest::StatusCode::OK)),
+            ["conditional requests handled differently`条件请求的处理不同: \
+              req1: 304 Not Modified, validators honored`校验器生效; \
+              req2: 200 OK with unchanged validators, conditional headers ignored`服务端忽略了条件请求头"]
+        );
+        assert!(compare(ConditionalOutcome::NotModified).is_empty());
+    }
+
+    #[test]
+    fn snapshot_should_not_be_used_with_req2() {
+        let profile = serde_yaml::from_str::<DiffProfile>(
+            "req1: { url: https://example.com/a }\n\
+             req2: { url: https://example.com/b }\n\
+             snapshot: ./a.json\n",
+        );
+        assert!(profile
+            .unwrap_err()
+            .to_string()
+            .contains("`snapshot` can not be used together with `req2`"));
+        let profile = serde_yaml::from_str::<DiffProfile>(
+            "req1: { url: https://example.com/a }\nsnapshot: ./a.json\n",
+        );
+        assert!(profile.is_ok());
+    }
+
+    #[test]
+    fn req2_headers_should_replace_every_value_of_the_base() {
+        let profile: DiffProfile = serde_yaml::from_str(
+            "req1:\n\
+             \x20 url: https://example.com/a\n\
+             \x20 headers: { accept: [a, b], x-keep: k }\n\
+             req2:\n\
+             \x20 headers: { accept: [c, d] }\n",
+        )
+        .unwrap();
+        fn values(req: &RequestProfile, name: &str) -> Vec<String> {
+            let values = req.headers.get_all(name).iter();
+            values.map(|v| v.to_str().unwrap().to_string()).collect()
+        }
+        assert_eq!(values(&profile.req1, "accept"), ["a", "b"]);
+        assert_eq!(values(&profile.req2, "accept"), ["c", "d"]);
+        assert_eq!(values(&profile.req2, "x-keep"), ["k"]);
+    }
+
+    #[tokio::test]
+    async fn unknown_charset_should_fall_back_to_utf8_with_a_warning() {
+        let response = |content_type: &str| {
+            let mut res = http::Response::new("héllo".as_bytes().to_vec());
+            res.headers_mut()
+                .insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+            ResponseExt {
+                res: Response::from(res),
+                protobuf: None,
+                request: SentRequest {
+                    method: Method::GET,
+                    url: "https://example.com/".parse().unwrap(),
+                    headers: HeaderMap::new(),
+                    body: vec![],
+                },
+                elapsed: Duration::ZERO,
+                body_size: None,
+            }
+        };
+        let res = ResponseProfile::default();
+        let parts1 = response("text/plain; charset=binary")
+            .get_parts(&res)
+            .await
+            .unwrap();
+        let parts2 = response("text/plain").get_parts(&res).await.unwrap();
+        assert_eq!(parts1.body, ResponseBody::Text("héllo".to_string()));
+        let req: RequestProfile = "https://example.com/".parse().unwrap();
+        let profile = DiffProfile::new(req.clone(), req, res);
+        let reports = profile
+            .compare_pairs(vec![parts1, parts2], &[], &DiffOptions::default())
+            .unwrap();
+        assert_eq!(
+            reports[0].1.warnings,
+            ["req1: unsupported charset, decoded as UTF-8`不支持的字符集，按 UTF-8 解码: binary"]
+        );
+    }
+}
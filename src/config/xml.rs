@@ -0,0 +1,258 @@
+use anyhow::{anyhow, Result};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::fmt::Write;
+
+/// XML 元素，属性按名称排序
+#[derive(Debug, Clone, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+}
+
+#[derive(Debug, Clone)]
+enum XmlNode {
+    Element(Element),
+    Text(String),
+}
+
+/// XPath 的子集：`/a/b`、`//b`、`/a/*`、`/a/b[2]`、`/a/b/@id`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    // `//` 匹配任意层级
+    descendant: bool,
+    test: StepTest,
+    // 从 1 开始的位置
+    index: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StepTest {
+    Name(String),
+    Any,
+    Attr(String),
+}
+
+/// 解析 XML，去掉 skip_body 中的节点并规范化输出 \
+/// 只有 skip_body 中以 `/` 开头的 XPath 作用于 XML，普通字段名和 `$` 开头的 JSONPath 只作用于 JSON
+pub(crate) fn format_xml(text: &str, skip: &[String]) -> Result<String> {
+    let mut root = parse(text)?;
+    for path in skip.iter().filter(|k| k.starts_with('/')) {
+        remove(&mut root.children, &parse_xpath(path)?);
+    }
+    let mut output = String::new();
+    for node in &root.children {
+        render(node, 0, &mut output);
+    }
+    Ok(output)
+}
+
+fn parse(text: &str) -> Result<Element> {
+    let mut reader = Reader::from_str(text);
+    reader.trim_text(true);
+    // 栈底为文档本身
+    let mut stack = vec![Element::default()];
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => stack.push(element(&start)?),
+            Event::Empty(start) => {
+                let element = element(&start)?;
+                push(&mut stack, XmlNode::Element(element));
+            }
+            Event::End(_) => {
+                let element = stack
+                    .pop()
+                    .filter(|_| !stack.is_empty())
+                    .ok_or_else(|| anyhow!("Unbalanced XML`XML 标签不匹配"))?;
+                push(&mut stack, XmlNode::Element(element));
+            }
+            Event::Text(text) => push(&mut stack, XmlNode::Text(text.unescape()?.into_owned())),
+            Event::CData(data) => {
+                let text = String::from_utf8_lossy(&data.into_inner()).into_owned();
+                push(&mut stack, XmlNode::Text(text));
+            }
+            Event::Eof => break,
+            // 忽略声明、注释和处理指令
+            _ => {}
+        }
+    }
+    match stack.len() {
+        1 => Ok(stack.pop().unwrap()),
+        _ => Err(anyhow!("Unclosed XML element`XML 标签未闭合")),
+    }
+}
+
+fn element(start: &BytesStart) -> Result<Element> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+    let mut attrs = vec![];
+    for attr in start.attributes() {
+        let attr = attr?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+        attrs.push((key, attr.unescape_value()?.into_owned()));
+    }
+    attrs.sort();
+    Ok(Element {
+        name,
+        attrs,
+        children: vec![],
+    })
+}
+
+fn push(stack: &mut [Element], node: XmlNode) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+fn parse_xpath(path: &str) -> Result<Vec<Step>> {
+    let invalid = || anyhow!("Invalid XPath`无效的 XPath: {}", path);
+    let mut steps = vec![];
+    let mut rest = path.strip_prefix('/').ok_or_else(invalid)?;
+    while !rest.is_empty() {
+        let descendant = match rest.strip_prefix('/') {
+            Some(r) => {
+                rest = r;
+                true
+            }
+            None => false,
+        };
+        let (step, next) = rest.split_once('/').unwrap_or((rest, ""));
+        let (test, index) = match step.split_once('[') {
+            Some((test, index)) => {
+                let index = index.strip_suffix(']').ok_or_else(invalid)?;
+                (test, Some(index.parse::<usize>().map_err(|_| invalid())?))
+            }
+            None => (step, None),
+        };
+        let test = match test {
+            "" => return Err(invalid()),
+            "*" => StepTest::Any,
+            t => match t.strip_prefix('@') {
+                Some(attr) if next.is_empty() => StepTest::Attr(attr.to_string()),
+                Some(_) => return Err(invalid()),
+                None => StepTest::Name(t.to_string()),
+            },
+        };
+        steps.push(Step {
+            descendant,
+            test,
+            index,
+        });
+        rest = next;
+    }
+    Ok(steps)
+}
+
+fn remove(nodes: &mut Vec<XmlNode>, steps: &[Step]) {
+    let Some((step, rest)) = steps.split_first() else {
+        return;
+    };
+    let mut position = 0;
+    let mut matched = vec![];
+    for (i, node) in nodes.iter().enumerate() {
+        let XmlNode::Element(element) = node else {
+            continue;
+        };
+        let is_match = match &step.test {
+            StepTest::Name(name) => &element.name == name,
+            StepTest::Any => true,
+            StepTest::Attr(_) => false,
+        };
+        if is_match {
+            position += 1;
+            if step.index.is_none_or(|index| index == position) {
+                matched.push(i);
+            }
+        }
+    }
+
+    match rest.first().map(|s| &s.test) {
+        None => {
+            for i in matched.into_iter().rev() {
+                nodes.remove(i);
+            }
+        }
+        Some(StepTest::Attr(attr)) => {
+            for i in matched {
+                if let XmlNode::Element(element) = &mut nodes[i] {
+                    element.attrs.retain(|(k, _)| k != attr);
+                }
+            }
+        }
+        Some(_) => {
+            for i in matched {
+                if let XmlNode::Element(element) = &mut nodes[i] {
+                    remove(&mut element.children, rest);
+                }
+            }
+        }
+    }
+
+    // `//` 继续在所有子元素中查找
+    if step.descendant {
+        for node in nodes.iter_mut() {
+            if let XmlNode::Element(element) = node {
+                remove(&mut element.children, steps);
+            }
+        }
+    }
+}
+
+fn render(node: &XmlNode, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    let element = match node {
+        XmlNode::Text(text) => {
+            let _ = writeln!(output, "{}{}", indent, escape(text));
+            return;
+        }
+        XmlNode::Element(element) => element,
+    };
+    let _ = write!(output, "{}<{}", indent, element.name);
+    for (k, v) in &element.attrs {
+        let _ = write!(output, " {}=\"{}\"", k, escape(v).replace('"', "&quot;"));
+    }
+    match element.children.as_slice() {
+        [] => {
+            let _ = writeln!(output, "/>");
+        }
+        // 只有文本的元素输出在同一行
+        [XmlNode::Text(text)] => {
+            let _ = writeln!(output, ">{}</{}>", escape(text), element.name);
+        }
+        children => {
+            let _ = writeln!(output, ">");
+            for child in children {
+                render(child, depth + 1, output);
+            }
+            let _ = writeln!(output, "{}</{}>", indent, element.name);
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_xml_should_only_apply_xpath_keys() {
+        let text = r#"<user><id>1</id><name>a</name><token>t</token></user>"#;
+        let skip = vec![
+            "id".to_string(),
+            "$.name".to_string(),
+            "/user/token".to_string(),
+        ];
+        assert_eq!(
+            format_xml(text, &skip).unwrap(),
+            "<user>\n  <id>1</id>\n  <name>a</name>\n</user>\n"
+        );
+    }
+}
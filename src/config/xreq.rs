@@ -1,21 +1,40 @@
-use crate::{ConfigValidate, LoadConfig, RequestProfile};
+use crate::{is_default, ConfigSettings, ConfigValidate, LoadConfig, RequestProfile};
 use anyhow::{Context, Result};
+use reqwest::{
+    header::{self, HeaderMap},
+    StatusCode,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, fs, path::Path};
 
 /// 包含多个请求配置
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RequestConfig {
+    // 全局设置，和 xdiff 的配置相同，放在单独的 settings 节点中
+    #[serde(skip_serializing_if = "is_default", default)]
+    pub settings: ConfigSettings,
     #[serde(flatten)]
     pub profiles: HashMap<String, RequestProfile>,
 }
 
-impl LoadConfig for RequestConfig {}
+impl LoadConfig for RequestConfig {
+    fn load_files(&mut self, base_dir: &Path) -> Result<()> {
+        for (name, profile) in self.profiles.iter_mut() {
+            profile
+                .load_files(base_dir)
+                .with_context(|| format!("failed to load profile`加载失败: `{}`", name))?;
+        }
+        Ok(())
+    }
+}
 
 impl RequestConfig {
     // 接受一个RequestProfile集合，构建RequestConfig
     pub fn new(profiles: HashMap<String, RequestProfile>) -> Self {
-        Self { profiles }
+        Self {
+            settings: ConfigSettings::default(),
+            profiles,
+        }
     }
     // 获取指定名称的 RequestProfile
     pub fn get_profile(&self, name: &str) -> Option<&RequestProfile> {
@@ -34,3 +53,136 @@ impl ConfigValidate for RequestConfig {
         Ok(())
     }
 }
+
+/// 上一次响应中的缓存校验器，用于发送条件请求
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    // 从响应头中提取 ETag 和 Last-Modified
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let get = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        Self {
+            etag: get(header::ETAG),
+            last_modified: get(header::LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// 转换为条件请求头：If-None-Match 和 If-Modified-Since
+    pub fn to_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![];
+        if let Some(etag) = &self.etag {
+            headers.push((header::IF_NONE_MATCH.to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push((header::IF_MODIFIED_SINCE.to_string(), last_modified.clone()));
+        }
+        headers
+    }
+
+    /// 描述服务端对条件请求的处理结果
+    /// describe how the server handled the conditional request
+    pub fn report(&self, status: StatusCode, current: &Validators) -> String {
+        self.outcome(status, current).to_string()
+    }
+
+    /// 服务端对条件请求的处理结果，current 是条件请求的响应中的校验器
+    pub fn outcome(&self, status: StatusCode, current: &Validators) -> ConditionalOutcome {
+        if status == StatusCode::NOT_MODIFIED {
+            ConditionalOutcome::NotModified
+        } else if !status.is_success() {
+            ConditionalOutcome::Failed(status)
+        } else if current == self {
+            ConditionalOutcome::Ignored(status)
+        } else {
+            ConditionalOutcome::Changed(status)
+        }
+    }
+}
+
+/// 服务端对条件请求的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionalOutcome {
+    // 响应中没有 ETag 和 Last-Modified，无法发送条件请求
+    NoValidators,
+    NotModified,
+    Failed(StatusCode),
+    // 校验器没有变化但仍然返回了完整的响应
+    Ignored(StatusCode),
+    Changed(StatusCode),
+}
+
+impl ConditionalOutcome {
+    /// 两个结果是否是同一种处理方式，不比较状态码
+    pub fn same_handling(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl fmt::Display for ConditionalOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoValidators => write!(f, "no ETag or Last-Modified`响应中没有校验器"),
+            Self::NotModified => write!(f, "304 Not Modified, validators honored`校验器生效"),
+            Self::Failed(status) => {
+                write!(f, "{}, conditional request failed`条件请求失败", status)
+            }
+            Self::Ignored(status) => write!(
+                f,
+                "{} with unchanged validators, conditional headers ignored`服务端忽略了条件请求头",
+                status
+            ),
+            Self::Changed(status) => write!(f, "{}, resource changed`资源已变化", status),
+        }
+    }
+}
+
+/// 按 profile 名称保存的缓存校验器，存储为 JSON 文件
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ValidatorStore {
+    #[serde(flatten)]
+    pub profiles: HashMap<String, Validators>,
+}
+
+impl ValidatorStore {
+    // 从文件加载，文件不存在时返回空的存储
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse validators`无法解析: {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Validators> {
+        self.profiles.get(name).filter(|v| !v.is_empty())
+    }
+
+    // 更新 profile 的校验器，响应中没有新的校验器时(例如 304)保留原来的
+    pub fn update(&mut self, name: &str, validators: Validators) {
+        if !validators.is_empty() {
+            self.profiles.insert(name.to_string(), validators);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{JsonPath, Segment};
+use serde_json::{json, Value};
+
+/// 两个 JSON 之间的一处差异，路径为 JSONPath 形式
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonChange {
+    Added {
+        path: JsonPath,
+        value: Value,
+    },
+    Removed {
+        path: JsonPath,
+        value: Value,
+    },
+    Changed {
+        path: JsonPath,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl JsonChange {
+    pub fn path(&self) -> &JsonPath {
+        match self {
+            JsonChange::Added { path, .. }
+            | JsonChange::Removed { path, .. }
+            | JsonChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// 把差异转换为 JSON Patch (RFC 6902) 文档，按顺序应用到旧的 JSON 上可以得到新的 JSON
+/// convert the changes into a JSON Patch document
+pub fn json_patch(changes: &[JsonChange]) -> Value {
+    let operations = changes
+        .iter()
+        .map(|change| {
+            let path = change.path().to_pointer().unwrap_or_default();
+            match change {
+                JsonChange::Added { value, .. } => {
+                    json!({"op": "add", "path": path, "value": value})
+                }
+                JsonChange::Removed { .. } => json!({"op": "remove", "path": path}),
+                JsonChange::Changed { new, .. } => {
+                    json!({"op": "replace", "path": path, "value": new})
+                }
+            }
+        })
+        .collect();
+    Value::Array(operations)
+}
+
+/// 按结构比较两个 JSON，返回每个路径上的新增、删除和修改 \
+/// 对象按字段比较，数组按下标比较
+/// compare two JSON trees and report per-path changes
+pub fn json_changes(old: &Value, new: &Value) -> Vec<JsonChange> {
+    let mut changes = vec![];
+    walk(&mut vec![], old, new, &mut changes);
+    changes
+}
+
+fn walk(path: &mut Vec<Segment>, old: &Value, new: &Value, changes: &mut Vec<JsonChange>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                path.push(Segment::Child(key.clone()));
+                match new.get(key) {
+                    Some(new_value) => walk(path, old_value, new_value, changes),
+                    None => changes.push(JsonChange::Removed {
+                        path: JsonPath::Path(path.clone()),
+                        value: old_value.clone(),
+                    }),
+                }
+                path.pop();
+            }
+            for (key, new_value) in new.iter().filter(|(k, _)| !old.contains_key(*k)) {
+                path.push(Segment::Child(key.clone()));
+                changes.push(JsonChange::Added {
+                    path: JsonPath::Path(path.clone()),
+                    value: new_value.clone(),
+                });
+                path.pop();
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (i, (old_value, new_value)) in old.iter().zip(new).enumerate() {
+                path.push(Segment::Index(i as i64));
+                walk(path, old_value, new_value, changes);
+                path.pop();
+            }
+            for (i, value) in new.iter().enumerate().skip(old.len()) {
+                path.push(Segment::Index(i as i64));
+                changes.push(JsonChange::Added {
+                    path: JsonPath::Path(path.clone()),
+                    value: value.clone(),
+                });
+                path.pop();
+            }
+            // 多出的元素从后往前删除，按顺序应用时下标不会变化
+            for (i, value) in old.iter().enumerate().skip(new.len()).rev() {
+                path.push(Segment::Index(i as i64));
+                changes.push(JsonChange::Removed {
+                    path: JsonPath::Path(path.clone()),
+                    value: value.clone(),
+                });
+                path.pop();
+            }
+        }
+        (old, new) if old != new => changes.push(JsonChange::Changed {
+            path: JsonPath::Path(path.clone()),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(changes: &[JsonChange]) -> Vec<String> {
+        changes.iter().map(|c| c.path().to_string()).collect()
+    }
+
+    // 按 RFC 6902 依次应用 add、remove 和 replace
+    fn apply(mut value: Value, patch: &Value) -> Value {
+        for op in patch.as_array().unwrap() {
+            let path = op["path"].as_str().unwrap();
+            if path.is_empty() {
+                value = op["value"].clone();
+                continue;
+            }
+            let (parent, token) = path.rsplit_once('/').unwrap();
+            let token = token.replace("~1", "/").replace("~0", "~");
+            match (
+                op["op"].as_str().unwrap(),
+                value.pointer_mut(parent).unwrap(),
+            ) {
+                ("remove", _) => {
+                    crate::jsonpath::remove_pointer(&mut value, path).unwrap();
+                }
+                ("add", Value::Array(items)) => {
+                    items.insert(token.parse().unwrap(), op["value"].clone())
+                }
+                (_, Value::Array(items)) => {
+                    items[token.parse::<usize>().unwrap()] = op["value"].clone()
+                }
+                (_, Value::Object(map)) => {
+                    map.insert(token, op["value"].clone());
+                }
+                _ => panic!("invalid patch path: {}", path),
+            }
+        }
+        value
+    }
+
+    #[test]
+    fn equal_values_should_have_no_changes() {
+        let value = json!({"a": [1, {"b": null}], "c": "x"});
+        assert!(json_changes(&value, &value).is_empty());
+    }
+
+    #[test]
+    fn objects_should_be_compared_by_field() {
+        let old = json!({"a": 1, "b": {"c": true}, "d": "gone"});
+        let new = json!({"a": 2, "b": {"c": true}, "e": [1]});
+        assert_eq!(
+            json_changes(&old, &new),
+            vec![
+                JsonChange::Changed {
+                    path: JsonPath::parse("$.a").unwrap(),
+                    old: json!(1),
+                    new: json!(2),
+                },
+                JsonChange::Removed {
+                    path: JsonPath::parse("$.d").unwrap(),
+                    value: json!("gone"),
+                },
+                JsonChange::Added {
+                    path: JsonPath::parse("$.e").unwrap(),
+                    value: json!([1]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_should_be_compared_by_index() {
+        let changes = json_changes(&json!([1, 2, 3]), &json!([1, 5]));
+        assert_eq!(paths(&changes), vec!["$[1]", "$[2]"]);
+        assert!(matches!(changes[1], JsonChange::Removed { .. }));
+
+        let changes = json_changes(&json!([1, 2, 3, 4]), &json!([1]));
+        assert_eq!(paths(&changes), vec!["$[3]", "$[2]", "$[1]"]);
+
+        let changes = json_changes(&json!({"a": []}), &json!({"a": [1, 2]}));
+        assert_eq!(paths(&changes), vec!["$.a[0]", "$.a[1]"]);
+    }
+
+    #[test]
+    fn type_changes_should_replace_the_whole_value() {
+        let changes = json_changes(&json!({"a": {"b": 1}}), &json!({"a": [1]}));
+        assert_eq!(
+            changes,
+            vec![JsonChange::Changed {
+                path: JsonPath::parse("$.a").unwrap(),
+                old: json!({"b": 1}),
+                new: json!([1]),
+            }]
+        );
+    }
+
+    #[test]
+    fn paths_should_quote_unusual_names() {
+        let changes = json_changes(&json!({"a b": {"c/d": 1}}), &json!({"a b": {"c/d": 2}}));
+        assert_eq!(paths(&changes), vec!["$['a b']['c/d']"]);
+    }
+
+    #[test]
+    fn json_patch_should_turn_old_into_new() {
+        let cases = [
+            (json!({"a": 1, "b": 2}), json!({"a": 3, "c": {"d": [1]}})),
+            (json!([1, 2, 3, 4]), json!([0, 2])),
+            (json!({"list": [1]}), json!({"list": [1, 2, 3]})),
+            (json!({"a/b": {"c~d": 1}}), json!({"a/b": {"c~d": 2}})),
+            (json!({"a": 1}), json!([1])),
+        ];
+        for (old, new) in cases {
+            let patch = json_patch(&json_changes(&old, &new));
+            assert_eq!(apply(old.clone(), &patch), new, "{}", patch);
+        }
+    }
+
+    #[test]
+    fn json_patch_should_use_rfc6902_operations() {
+        let patch = json_patch(&json_changes(
+            &json!({"a": 1, "b": 2}),
+            &json!({"a": 3, "c": 4}),
+        ));
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "replace", "path": "/a", "value": 3},
+                {"op": "remove", "path": "/b"},
+                {"op": "add", "path": "/c", "value": 4},
+            ])
+        );
+    }
+}
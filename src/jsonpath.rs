@@ -0,0 +1,474 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt};
+
+/// JSONPath 的子集或者 JSON pointer，用于定位响应中的字段 \
+/// 支持 `$.a.b`、`$['a']`、`$.a[0]`、`$.a[*]`、`$.*`、`$..a`，以及 `/a/b/0` 形式的 JSON pointer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPath {
+    Path(Vec<Segment>),
+    Pointer(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Child(String),
+    Index(i64),
+    Wildcard,
+    // `..` 递归查找所有层级
+    Descendant(Box<Segment>),
+}
+
+impl JsonPath {
+    /// 是否为路径表达式，普通的字段名返回 false
+    pub fn is_path(s: &str) -> bool {
+        s.starts_with('$') || s.starts_with('/')
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        if s.starts_with('/') {
+            return Ok(JsonPath::Pointer(s.to_string()));
+        }
+        let rest = s.strip_prefix('$').ok_or_else(|| {
+            anyhow!(
+                "JSONPath must start with `$` or `/``路径必须以 $ 或 / 开头: {}",
+                s
+            )
+        })?;
+        let invalid = || anyhow!("Invalid JSONPath`无效的路径: {}", s);
+        let chars: Vec<char> = rest.chars().collect();
+        let mut segments = vec![];
+        let mut i = 0;
+        while i < chars.len() {
+            let descendant = chars[i..].starts_with(&['.', '.']);
+            let segment = match chars[i] {
+                '.' => {
+                    i += if descendant { 2 } else { 1 };
+                    if chars.get(i) == Some(&'[') {
+                        let (segment, next) = parse_bracket(&chars, i).ok_or_else(invalid)?;
+                        i = next;
+                        segment
+                    } else {
+                        let start = i;
+                        while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                            i += 1;
+                        }
+                        match chars[start..i].iter().collect::<String>() {
+                            name if name == "*" => Segment::Wildcard,
+                            name if name.is_empty() => return Err(invalid()),
+                            name => Segment::Child(name),
+                        }
+                    }
+                }
+                '[' => {
+                    let (segment, next) = parse_bracket(&chars, i).ok_or_else(invalid)?;
+                    i = next;
+                    segment
+                }
+                _ => return Err(invalid()),
+            };
+            segments.push(if descendant {
+                Segment::Descendant(Box::new(segment))
+            } else {
+                segment
+            });
+        }
+        Ok(JsonPath::Path(segments))
+    }
+
+    /// 找到所有匹配的节点，返回它们的 JSON pointer，按文档顺序排列
+    /// find every match and return their JSON pointers in document order
+    pub fn find_pointers(&self, root: &Value) -> Vec<String> {
+        match self {
+            JsonPath::Pointer(pointer) => root
+                .pointer(pointer)
+                .map(|_| vec![pointer.clone()])
+                .unwrap_or_default(),
+            JsonPath::Path(segments) => {
+                let mut current = vec![Node::root(root)];
+                for segment in segments {
+                    let mut next = vec![];
+                    for node in &current {
+                        select(segment, node, &mut next);
+                    }
+                    current = next;
+                }
+                // `$..a..b` 这样的路径会从不同的祖先重复匹配同一个节点，按节点在文档中的位置去重并排序
+                let pointers: BTreeMap<Vec<usize>, String> = current
+                    .into_iter()
+                    .map(|node| (node.position, node.pointer))
+                    .collect();
+                pointers.into_values().collect()
+            }
+        }
+    }
+
+    /// 返回所有匹配的值
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        self.find_pointers(root)
+            .iter()
+            .filter_map(|p| root.pointer(p))
+            .collect()
+    }
+
+    /// 对每个匹配的值执行 f
+    pub fn for_each_mut(&self, root: &mut Value, mut f: impl FnMut(&mut Value)) {
+        for pointer in self.find_pointers(root) {
+            if let Some(value) = root.pointer_mut(&pointer) {
+                f(value);
+            }
+        }
+    }
+
+    /// 只由字段名和非负下标组成的路径可以转换为唯一的 JSON pointer
+    pub fn to_pointer(&self) -> Option<String> {
+        match self {
+            JsonPath::Pointer(pointer) => Some(pointer.clone()),
+            JsonPath::Path(segments) => {
+                segments
+                    .iter()
+                    .try_fold(String::new(), |pointer, s| match s {
+                        Segment::Child(name) => Some(child_pointer(&pointer, name)),
+                        Segment::Index(idx) if *idx >= 0 => Some(format!("{}/{}", pointer, idx)),
+                        _ => None,
+                    })
+            }
+        }
+    }
+
+    /// 删除所有匹配的字段或数组元素
+    pub fn remove(&self, root: &mut Value) {
+        // 倒序删除，避免数组下标发生变化
+        for pointer in self.find_pointers(root).iter().rev() {
+            remove_pointer(root, pointer);
+        }
+    }
+
+    /// 列出 JSON 中所有字段的路径，外层字段在前；数组元素的字段合并为 `[*]`，同一个路径只出现一次
+    pub fn fields(root: &Value) -> Vec<JsonPath> {
+        let mut fields = vec![];
+        collect_fields(root, &mut vec![], &mut fields);
+        fields
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonPath::Pointer(pointer) => write!(f, "{}", pointer),
+            JsonPath::Path(segments) => {
+                write!(f, "$")?;
+                for segment in segments {
+                    write_segment(f, segment)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn collect_fields(value: &Value, prefix: &mut Vec<Segment>, fields: &mut Vec<JsonPath>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                prefix.push(Segment::Child(key.clone()));
+                let path = JsonPath::Path(prefix.clone());
+                if !fields.contains(&path) {
+                    fields.push(path);
+                }
+                collect_fields(value, prefix, fields);
+                prefix.pop();
+            }
+        }
+        Value::Array(items) => {
+            prefix.push(Segment::Wildcard);
+            for item in items {
+                collect_fields(item, prefix, fields);
+            }
+            prefix.pop();
+        }
+        _ => {}
+    }
+}
+
+fn write_segment(f: &mut fmt::Formatter, segment: &Segment) -> fmt::Result {
+    match segment {
+        // 不是普通标识符的字段名使用 `['a b']` 的形式
+        Segment::Child(name) if !is_identifier(name) => {
+            write!(f, "['{}']", name.replace('\\', "\\\\").replace('\'', "\\'"))
+        }
+        Segment::Child(name) => write!(f, ".{}", name),
+        Segment::Index(idx) => write!(f, "[{}]", idx),
+        Segment::Wildcard => write!(f, "[*]"),
+        // `..name` 或者 `..[...]`
+        Segment::Descendant(segment) => match segment.as_ref() {
+            Segment::Child(name) if is_identifier(name) => write!(f, "..{}", name),
+            segment => {
+                write!(f, "..")?;
+                write_segment(f, segment)
+            }
+        },
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+// 解析 `[0]`、`[*]`、`['name']`，返回解析结果和下一个字符的位置 \
+// 引号中的名称可以包含 `]`，`\'`、`\"` 和 `\\` 转义为对应的字符
+fn parse_bracket(chars: &[char], start: usize) -> Option<(Segment, usize)> {
+    let skip_spaces = |mut i: usize| {
+        while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+            i += 1;
+        }
+        i
+    };
+    let mut i = skip_spaces(start + 1);
+    if let Some(quote @ ('\'' | '"')) = chars.get(i).copied() {
+        let mut name = String::new();
+        i += 1;
+        loop {
+            match *chars.get(i)? {
+                '\\' => {
+                    name.push(*chars.get(i + 1)?);
+                    i += 2;
+                }
+                c if c == quote => break,
+                c => {
+                    name.push(c);
+                    i += 1;
+                }
+            }
+        }
+        let end = skip_spaces(i + 1);
+        return (chars.get(end) == Some(&']')).then(|| (Segment::Child(name), end + 1));
+    }
+    let end = start + chars[start..].iter().position(|c| *c == ']')?;
+    let inner: String = chars[start + 1..end].iter().collect();
+    let segment = match inner.trim() {
+        "*" => Segment::Wildcard,
+        inner => Segment::Index(inner.parse().ok()?),
+    };
+    Some((segment, end + 1))
+}
+
+// 匹配到的节点：position 为每一层在对象中的键序号或数组下标，用于按文档顺序排序
+struct Node<'a> {
+    position: Vec<usize>,
+    pointer: String,
+    value: &'a Value,
+}
+
+impl<'a> Node<'a> {
+    fn root(value: &'a Value) -> Self {
+        Self {
+            position: vec![],
+            pointer: String::new(),
+            value,
+        }
+    }
+
+    fn child(&self, idx: usize, token: &str, value: &'a Value) -> Self {
+        let mut position = self.position.clone();
+        position.push(idx);
+        Self {
+            position,
+            pointer: child_pointer(&self.pointer, token),
+            value,
+        }
+    }
+
+    fn children(&self) -> Vec<Node<'a>> {
+        match self.value {
+            Value::Object(map) => map
+                .iter()
+                .enumerate()
+                .map(|(i, (k, v))| self.child(i, k, v))
+                .collect(),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| self.child(i, &i.to_string(), v))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+fn select<'a>(segment: &Segment, node: &Node<'a>, out: &mut Vec<Node<'a>>) {
+    match (segment, node.value) {
+        (Segment::Child(name), Value::Object(map)) => {
+            if let Some((i, (k, v))) = map.iter().enumerate().find(|(_, (k, _))| *k == name) {
+                out.push(node.child(i, k, v));
+            }
+        }
+        (Segment::Index(idx), Value::Array(items)) => {
+            let idx = if *idx < 0 {
+                items.len() as i64 + idx
+            } else {
+                *idx
+            };
+            if let Some((i, v)) = usize::try_from(idx)
+                .ok()
+                .and_then(|i| items.get(i).map(|v| (i, v)))
+            {
+                out.push(node.child(i, &i.to_string(), v));
+            }
+        }
+        (Segment::Wildcard, _) => out.extend(node.children()),
+        (Segment::Descendant(inner), _) => {
+            select(inner, node, out);
+            for child in node.children() {
+                select(segment, &child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// 按照 RFC 6901 转义，拼接子节点的 JSON pointer
+fn child_pointer(parent: &str, token: &str) -> String {
+    format!("{}/{}", parent, token.replace('~', "~0").replace('/', "~1"))
+}
+
+/// 删除 JSON pointer 指向的字段或数组元素
+pub(crate) fn remove_pointer(root: &mut Value, pointer: &str) -> Option<Value> {
+    let (parent, token) = pointer.rsplit_once('/')?;
+    let token = token.replace("~1", "/").replace("~0", "~");
+    match root.pointer_mut(parent)? {
+        Value::Object(map) => map.shift_remove(&token),
+        Value::Array(items) => {
+            let idx: usize = token.parse().ok()?;
+            (idx < items.len()).then(|| items.remove(idx))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pointers(path: &str, value: &Value) -> Vec<String> {
+        JsonPath::parse(path).unwrap().find_pointers(value)
+    }
+
+    #[test]
+    fn parse_should_accept_dot_and_bracket_segments() {
+        let path = JsonPath::parse("$.a['b c'][0][*]..d").unwrap();
+        assert_eq!(
+            path,
+            JsonPath::Path(vec![
+                Segment::Child("a".into()),
+                Segment::Child("b c".into()),
+                Segment::Index(0),
+                Segment::Wildcard,
+                Segment::Descendant(Box::new(Segment::Child("d".into()))),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_should_unescape_quoted_names() {
+        let path = JsonPath::parse(r#"$['it\'s']["a]b"]['back\\slash']"#).unwrap();
+        assert_eq!(
+            path,
+            JsonPath::Path(vec![
+                Segment::Child("it's".into()),
+                Segment::Child("a]b".into()),
+                Segment::Child("back\\slash".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_should_reject_invalid_paths() {
+        for path in ["a.b", "$.", "$[", "$['a'", "$['a'x]", "$[abc]", "$a"] {
+            assert!(JsonPath::parse(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn display_should_round_trip() {
+        for path in [
+            "$.a.b",
+            "$['a b'][0]",
+            r"$['it\'s']",
+            r"$['back\\slash']",
+            "$..a",
+            "$..['a b']",
+            "$..[*]",
+            "$[-1]",
+        ] {
+            let parsed = JsonPath::parse(path).unwrap();
+            assert_eq!(parsed.to_string(), path);
+            assert_eq!(JsonPath::parse(&parsed.to_string()).unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn negative_index_should_count_from_the_end() {
+        let value = json!({"items": [1, 2, 3]});
+        assert_eq!(pointers("$.items[-1]", &value), vec!["/items/2"]);
+        assert_eq!(pointers("$.items[-3]", &value), vec!["/items/0"]);
+        assert!(pointers("$.items[-4]", &value).is_empty());
+        assert_eq!(JsonPath::parse("$.items[-1]").unwrap().to_pointer(), None);
+    }
+
+    #[test]
+    fn pointers_should_escape_slash_and_tilde() {
+        let value = json!({"a/b": {"c~d": 1}});
+        assert_eq!(pointers("$['a/b']['c~d']", &value), vec!["/a~1b/c~0d"]);
+        assert_eq!(
+            JsonPath::parse("$['a/b']").unwrap().to_pointer(),
+            Some("/a~1b".to_string())
+        );
+    }
+
+    #[test]
+    fn descendant_should_match_every_level() {
+        let value = json!({"id": 1, "items": [{"id": 2}, {"child": {"id": 3}}]});
+        assert_eq!(
+            pointers("$..id", &value),
+            vec!["/id", "/items/0/id", "/items/1/child/id"]
+        );
+    }
+
+    #[test]
+    fn nested_descendants_should_match_each_node_once_in_document_order() {
+        let mut items = vec![Value::Null; 11];
+        items[2] = json!({"a": {"b": 2}});
+        items[10] = json!({"b": 10});
+        let value = json!({ "a": items });
+        assert_eq!(pointers("$..a..b", &value), vec!["/a/2/a/b", "/a/10/b"]);
+    }
+
+    #[test]
+    fn remove_should_delete_array_items_from_the_back() {
+        let mut value = json!({"items": [{"id": 1}, {"id": 2}], "id": 0});
+        JsonPath::parse("$.items[*]").unwrap().remove(&mut value);
+        assert_eq!(value, json!({"items": [], "id": 0}));
+    }
+
+    #[test]
+    fn json_pointer_should_be_used_as_is() {
+        let value = json!({"a": [1, 2]});
+        assert_eq!(pointers("/a/1", &value), vec!["/a/1"]);
+        assert!(pointers("/a/5", &value).is_empty());
+    }
+
+    #[test]
+    fn fields_should_merge_array_items() {
+        let value = json!({"a": [{"b": 1}, {"b": 2, "c": 3}]});
+        let fields: Vec<String> = JsonPath::fields(&value)
+            .iter()
+            .map(JsonPath::to_string)
+            .collect();
+        assert_eq!(fields, vec!["$.a", "$.a[*].b", "$.a[*].c"]);
+    }
+}
@@ -1,8 +1,21 @@
 pub mod cli;
 mod config;
+mod jsondiff;
+mod jsonpath;
+mod render;
+mod report;
 mod utils;
 
 pub use config::*;
+pub use jsondiff::{json_changes, json_patch, JsonChange};
+pub use jsonpath::{JsonPath, Segment};
+pub use render::{
+    DiffRenderer, DiffResult, HtmlRenderer, JsonRenderer, SideBySideRenderer, TextRenderer,
+};
+pub use report::{
+    diff_hunks, render_json, render_junit, render_markdown, render_tap, DiffHunk, DiffReport,
+    ReportFormat,
+};
 pub use utils::*;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -10,10 +23,29 @@ pub struct ExtraArgs {
     pub headers: Vec<(String, String)>,
     pub query: Vec<(String, String)>,
     pub body: Vec<(String, String)>,
+    // 模板函数使用的随机数种子，相同的种子生成相同的数据
+    pub seed: Option<u64>,
+    // 本次运行的 Idempotency-Key，两个请求使用同一个值
+    pub idempotency_key: Option<String>,
 }
 
 impl ExtraArgs {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// 合并另一组参数，other 排在后面，同名参数会覆盖当前的值
+    pub fn merge(&self, other: &ExtraArgs) -> Self {
+        let join = |a: &[(String, String)], b: &[(String, String)]| [a, b].concat();
+        Self {
+            headers: join(&self.headers, &other.headers),
+            query: join(&self.query, &other.query),
+            body: join(&self.body, &other.body),
+            seed: other.seed.or(self.seed),
+            idempotency_key: other
+                .idempotency_key
+                .clone()
+                .or_else(|| self.idempotency_key.clone()),
+        }
+    }
 }
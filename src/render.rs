@@ -0,0 +1,129 @@
+use crate::{
+    diff_hunks, diff_side_by_side, diff_text_with, json_patch,
+    report::{escape_xml, report_json},
+    DiffOptions, DiffReport, DiffView, JsonChange, OutputFormat,
+};
+use anyhow::Result;
+use serde_json::json;
+use std::fmt::Write;
+
+/// 一次比较的结构化结果，可以交给任意 DiffRenderer 渲染
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffResult {
+    // 两边请求的名称，例如 `req1` 和 `req2`
+    pub names: [String; 2],
+    // 两边经过过滤和 suppress 规则之后参与比较的文本
+    pub texts: [String; 2],
+    // 两边的响应体都是 JSON 时按字段的差异
+    pub changes: Vec<JsonChange>,
+    // 状态码、响应时间、断言结果和按选项渲染好的差异
+    pub report: DiffReport,
+}
+
+impl DiffResult {
+    pub fn has_diff(&self) -> bool {
+        self.report.has_diff()
+    }
+
+    pub fn render(&self, renderer: &impl DiffRenderer) -> Result<String> {
+        renderer.render(self)
+    }
+}
+
+/// 把比较结果渲染为文本，嵌入 xdiff 的程序可以实现自己的渲染方式
+/// render a DiffResult, implement it to display diffs your own way
+pub trait DiffRenderer {
+    fn render(&self, result: &DiffResult) -> Result<String>;
+}
+
+/// 带颜色的 unified diff，之后是响应时间和相似度，和命令行的默认输出相同
+#[derive(Debug, Clone, Default)]
+pub struct TextRenderer {
+    pub options: DiffOptions,
+}
+
+impl DiffRenderer for TextRenderer {
+    fn render(&self, result: &DiffResult) -> Result<String> {
+        let options = DiffOptions {
+            format: OutputFormat::Text,
+            view: DiffView::Unified,
+            ..self.options.clone()
+        };
+        let [text1, text2] = &result.texts;
+        let diff = diff_text_with(text1, text2, &options)?;
+        Ok(diff + &result.report.notes(OutputFormat::Text)?)
+    }
+}
+
+/// 左右两栏的差异，width 为总宽度，小于 40 时按 40 列输出
+#[derive(Debug, Clone, Default)]
+pub struct SideBySideRenderer {
+    pub width: usize,
+    pub options: DiffOptions,
+}
+
+impl DiffRenderer for SideBySideRenderer {
+    fn render(&self, result: &DiffResult) -> Result<String> {
+        let [text1, text2] = &result.texts;
+        let diff = diff_side_by_side(text1, text2, self.width, &self.options)?;
+        Ok(diff + &result.report.notes(OutputFormat::Text)?)
+    }
+}
+
+/// 和 `--format json` 相同的字段，另外加上 JSON Patch 形式的字段差异
+#[derive(Debug, Clone, Default)]
+pub struct JsonRenderer;
+
+impl DiffRenderer for JsonRenderer {
+    fn render(&self, result: &DiffResult) -> Result<String> {
+        let mut value = report_json(("requests", json!(result.names)), &result.report);
+        value["changes"] = json_patch(&result.changes);
+        Ok(serde_json::to_string_pretty(&value)? + "\n")
+    }
+}
+
+/// HTML 表格，每行的 class 为 `hunk`、`equal`、`delete` 或 `insert`，不包含样式
+#[derive(Debug, Clone, Default)]
+pub struct HtmlRenderer {
+    pub options: DiffOptions,
+}
+
+impl DiffRenderer for HtmlRenderer {
+    fn render(&self, result: &DiffResult) -> Result<String> {
+        let [text1, text2] = &result.texts;
+        let [name1, name2] = &result.names;
+        let mut output = String::new();
+        writeln!(output, r#"<table class="xdiff">"#)?;
+        writeln!(
+            output,
+            r#"  <caption>{} vs {}: {}</caption>"#,
+            escape_xml(name1),
+            escape_xml(name2),
+            result.report.verdict()
+        )?;
+        for hunk in diff_hunks(text1, text2, &self.options) {
+            writeln!(
+                output,
+                r#"  <tr class="hunk"><td colspan="2">{}</td></tr>"#,
+                escape_xml(&hunk.header)
+            )?;
+            for line in &hunk.lines {
+                let (sign, text) = line.split_at(line.chars().next().map_or(0, char::len_utf8));
+                let class = match sign {
+                    "-" => "delete",
+                    "+" => "insert",
+                    _ => "equal",
+                };
+                writeln!(
+                    output,
+                    r#"  <tr class="{}"><td>{}</td><td><pre>{}</pre></td></tr>"#,
+                    class,
+                    sign,
+                    escape_xml(text)
+                )?;
+            }
+        }
+        writeln!(output, "</table>")?;
+        Ok(output)
+    }
+}
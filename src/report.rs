@@ -0,0 +1,423 @@
+use crate::{utils::context_lines, DiffOptions, OutputFormat};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use similar::TextDiff;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// 一次比较的结果，命令行按输出格式渲染
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    // 两边请求的名称，例如 req1 和 req3
+    pub names: [String; 2],
+    // 两边的状态码
+    pub status: [u16; 2],
+    // 两边的响应时间
+    pub elapsed: [Duration; 2],
+    // 配置了 latency 时才在文本和 Markdown 输出中显示响应时间，否则相同的响应每次输出也不同
+    pub show_elapsed: bool,
+    // 按选项渲染好的差异，没有差异时为空
+    pub diff: String,
+    // 响应时间等警告
+    pub warnings: Vec<String>,
+    // 断言失败的信息
+    pub failures: Vec<String>,
+    // 值不同的响应头名称
+    pub changed_headers: Vec<String>,
+    // 两边都是 JSON 时值不同的字段路径
+    pub changed_paths: Vec<String>,
+    // 不带颜色的按行差异
+    pub hunks: Vec<DiffHunk>,
+    // 两边文本的相似度，0 到 1
+    pub similarity: f32,
+    // 有差异但相似度不低于这个值时仍然算作通过
+    pub min_similarity: Option<f32>,
+    // 生效的 suppress 规则，这些差异不出现在 diff 中
+    pub suppressed: Vec<String>,
+}
+
+/// unified diff 中的一段差异
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiffHunk {
+    // `@@ -1,3 +1,4 @@`
+    pub header: String,
+    // 以空格、`-` 或 `+` 开头的行
+    pub lines: Vec<String>,
+}
+
+/// 按行比较两段文本，返回每一段差异
+pub fn diff_hunks(text1: &str, text2: &str, options: &DiffOptions) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(text1, text2);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(context_lines(&diff, options));
+    unified
+        .iter_hunks()
+        .map(|hunk| DiffHunk {
+            header: hunk.header().to_string(),
+            lines: hunk
+                .iter_changes()
+                .map(|change| {
+                    let line = change.value().trim_end_matches(['\r', '\n']);
+                    format!("{}{}", change.tag(), line)
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+impl DiffReport {
+    pub fn has_diff(&self) -> bool {
+        !self.diff.is_empty()
+    }
+
+    /// 断言都通过，并且没有差异或者相似度达到 min_similarity
+    pub fn passed(&self) -> bool {
+        let similar = self
+            .min_similarity
+            .is_some_and(|min| self.similarity >= min);
+        self.failures.is_empty() && (!self.has_diff() || similar)
+    }
+
+    /// 一个词的结论：pass、diff 或 fail(断言失败)
+    pub fn verdict(&self) -> &'static str {
+        match (self.failures.is_empty(), self.passed()) {
+            (false, _) => "fail",
+            (true, false) => "diff",
+            (true, true) => "pass",
+        }
+    }
+
+    /// 有差异时输出相似度，设置了 min_similarity 时一并输出
+    pub fn similarity_line(&self) -> Option<String> {
+        if !self.has_diff() {
+            return None;
+        }
+        let mut line = format!("similarity: {:.2}%", self.similarity * 100.0);
+        if let Some(min) = self.min_similarity {
+            line.push_str(&format!(" (min {:.2}%)", min * 100.0));
+        }
+        Some(line)
+    }
+
+    /// 一行结论，例如 `todo: diff (200 vs 404, similarity 80.00%)`
+    pub fn summary_line(&self, name: &str) -> String {
+        let mut line = format!("{}: {}", name, self.verdict());
+        if self.has_diff() {
+            line.push_str(&format!(
+                " ({} vs {}, similarity {:.2}%)",
+                self.status[0],
+                self.status[1],
+                self.similarity * 100.0
+            ));
+        }
+        if !self.failures.is_empty() {
+            line.push_str(&format!(", {} assertion(s) failed", self.failures.len()));
+        }
+        line
+    }
+
+    /// 不带颜色的差异文本，由 hunks 生成
+    pub fn plain_diff(&self) -> String {
+        let mut output = String::new();
+        for hunk in &self.hunks {
+            output.push_str(&hunk.header);
+            output.push('\n');
+            for line in &hunk.lines {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+        output
+    }
+
+    pub fn elapsed_line(&self) -> String {
+        format!(
+            "elapsed: {} {}ms, {} {}ms",
+            self.names[0],
+            self.elapsed[0].as_millis(),
+            self.names[1],
+            self.elapsed[1].as_millis()
+        )
+    }
+
+    /// 文本输出：差异之后是响应时间、警告和断言失败；patch 格式只输出差异本身
+    pub fn to_text(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Patch => Ok(self.diff.clone()),
+            _ => Ok(self.diff.clone() + &self.notes(format)?),
+        }
+    }
+
+    /// 输出在差异之后的响应时间、相似度、警告和断言失败，patch 格式不输出响应时间和相似度
+    pub fn notes(&self, format: OutputFormat) -> Result<String> {
+        let mut output = String::new();
+        if format != OutputFormat::Patch {
+            if self.show_elapsed {
+                writeln!(output, "{}", self.elapsed_line())?;
+            }
+            if let Some(line) = self.similarity_line() {
+                writeln!(output, "{}", line)?;
+            }
+        }
+        for warning in &self.warnings {
+            writeln!(output, "{}", warning)?;
+        }
+        for rule in &self.suppressed {
+            writeln!(output, "suppressed`已忽略: {}", rule)?;
+        }
+        for failure in &self.failures {
+            writeln!(output, "assertion failed`断言失败: {}", failure)?;
+        }
+        Ok(output)
+    }
+}
+
+/// 除了标准输出之外额外写入文件的报告格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    // JUnit XML，CI 的测试面板可以直接展示
+    Junit,
+    // Test Anything Protocol
+    Tap,
+}
+
+impl ReportFormat {
+    /// `errors` 是请求失败等没有比较结果的 profile，也会作为测试用例写入报告
+    pub fn render(
+        &self,
+        reports: &[(String, DiffReport)],
+        errors: &[(String, anyhow::Error)],
+    ) -> Result<String> {
+        match self {
+            ReportFormat::Junit => render_junit(reports, errors),
+            ReportFormat::Tap => render_tap(reports, errors),
+        }
+    }
+}
+
+/// Markdown 报告：开头是所有 profile 的汇总表，之后每个 profile 的差异放在 diff 代码块中；
+/// 请求失败的 profile 排在最后，结果为 `error`
+pub fn render_markdown(
+    reports: &[(String, DiffReport)],
+    errors: &[(String, anyhow::Error)],
+) -> Result<String> {
+    let mut output = String::new();
+    writeln!(output, "| profile | req1 | req2 | result |")?;
+    writeln!(output, "| --- | --- | --- | --- |")?;
+    for (name, report) in reports {
+        writeln!(
+            output,
+            "| {} | {} | {} | {} |",
+            name.replace('|', "\\|"),
+            report.status[0],
+            report.status[1],
+            report.verdict()
+        )?;
+    }
+    for (name, _) in errors {
+        writeln!(output, "| {} | - | - | error |", name.replace('|', "\\|"))?;
+    }
+    for (name, report) in reports {
+        writeln!(output, "\n### {}\n", name)?;
+        if report.has_diff() {
+            // 差异中可能出现 ```，使用更长的围栏
+            let fence = "`".repeat(longest_backticks(&report.diff).max(2) + 1);
+            writeln!(output, "{}diff\n{}{}", fence, report.diff, fence)?;
+        } else {
+            writeln!(output, "No differences`没有差异")?;
+        }
+        writeln!(output)?;
+        if report.show_elapsed {
+            writeln!(output, "- {}", report.elapsed_line())?;
+        }
+        if let Some(line) = report.similarity_line() {
+            writeln!(output, "- {}", line)?;
+        }
+        for warning in &report.warnings {
+            writeln!(output, "- {}", warning)?;
+        }
+        for rule in &report.suppressed {
+            writeln!(output, "- suppressed`已忽略: {}", rule)?;
+        }
+        for failure in &report.failures {
+            writeln!(output, "- assertion failed`断言失败: {}", failure)?;
+        }
+    }
+    for (name, e) in errors {
+        writeln!(output, "\n### {}\n", name)?;
+        let text = e.to_string();
+        let fence = "`".repeat(longest_backticks(&text).max(2) + 1);
+        writeln!(output, "error`出错:\n\n{}\n{}\n{}", fence, text, fence)?;
+    }
+    Ok(output)
+}
+
+fn longest_backticks(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// 机器可读的 JSON 结果，每个 profile 一个对象；请求失败的 profile 排在最后，`result` 为 `error`
+pub fn render_json(
+    reports: &[(String, DiffReport)],
+    errors: &[(String, anyhow::Error)],
+) -> Result<String> {
+    let results: Vec<_> = reports
+        .iter()
+        .map(|(name, report)| report_json(("profile", json!(name)), report))
+        .chain(errors.iter().map(|(name, e)| {
+            json!({
+                "profile": name,
+                "result": "error",
+                "passed": false,
+                "error": e.to_string(),
+            })
+        }))
+        .collect();
+    Ok(serde_json::to_string_pretty(&results)? + "\n")
+}
+
+// 一次比较的 JSON 对象，(key, value) 是排在最前面的字段
+pub(crate) fn report_json((key, value): (&str, Value), report: &DiffReport) -> Value {
+    json!({
+        key: value,
+        "result": report.verdict(),
+        "passed": report.passed(),
+        "status": report.status,
+        "elapsed_ms": report.elapsed.map(|e| e.as_millis() as u64),
+        "similarity": report.similarity,
+        "changed_headers": report.changed_headers,
+        "changed_paths": report.changed_paths,
+        "hunks": report.hunks,
+        "warnings": report.warnings,
+        "suppressed": report.suppressed,
+        "failures": report.failures,
+    })
+}
+
+/// JUnit XML 报告：每个 profile 是一个测试用例，有差异或断言失败时为 failure，内容为差异文本；
+/// 请求失败的 profile 为 error
+pub fn render_junit(
+    reports: &[(String, DiffReport)],
+    errors: &[(String, anyhow::Error)],
+) -> Result<String> {
+    let tests = reports.len() + errors.len();
+    let failures = reports.iter().filter(|(_, r)| !r.passed()).count();
+    let seconds = |r: &DiffReport| (r.elapsed[0] + r.elapsed[1]).as_secs_f64();
+    let total: f64 = reports.iter().map(|(_, r)| seconds(r)).sum();
+    let mut output = String::new();
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        output,
+        r#"<testsuites tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+        tests,
+        failures,
+        errors.len(),
+        total
+    )?;
+    writeln!(
+        output,
+        r#"  <testsuite name="xdiff" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+        tests,
+        failures,
+        errors.len(),
+        total
+    )?;
+    for (name, report) in reports {
+        write!(
+            output,
+            r#"    <testcase name="{}" classname="xdiff" time="{:.3}">"#,
+            escape_xml(name),
+            seconds(report)
+        )?;
+        if report.passed() {
+            writeln!(output, "</testcase>")?;
+            continue;
+        }
+        let message = match report.failures.is_empty() {
+            true => "responses differ",
+            false => "assertion failed",
+        };
+        let mut text = report.plain_diff();
+        for failure in &report.failures {
+            writeln!(text, "assertion failed: {}", failure)?;
+        }
+        writeln!(output)?;
+        writeln!(
+            output,
+            r#"      <failure message="{}">{}</failure>"#,
+            message,
+            escape_xml(&text)
+        )?;
+        if !report.warnings.is_empty() {
+            writeln!(
+                output,
+                "      <system-out>{}</system-out>",
+                escape_xml(&report.warnings.join("\n"))
+            )?;
+        }
+        writeln!(output, "    </testcase>")?;
+    }
+    for (name, e) in errors {
+        writeln!(
+            output,
+            r#"    <testcase name="{}" classname="xdiff" time="0.000">"#,
+            escape_xml(name)
+        )?;
+        writeln!(
+            output,
+            r#"      <error message="request failed">{}</error>"#,
+            escape_xml(&e.to_string())
+        )?;
+        writeln!(output, "    </testcase>")?;
+    }
+    writeln!(output, "  </testsuite>")?;
+    writeln!(output, "</testsuites>")?;
+    Ok(output)
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// TAP 输出：每个 profile 一行 `ok`/`not ok`，失败时差异作为 `#` 开头的诊断信息；
+/// 请求失败的 profile 排在最后，输出为 `not ok N - name # error`
+pub fn render_tap(
+    reports: &[(String, DiffReport)],
+    errors: &[(String, anyhow::Error)],
+) -> Result<String> {
+    let mut output = String::new();
+    writeln!(output, "TAP version 13")?;
+    writeln!(output, "1..{}", reports.len() + errors.len())?;
+    for (i, (name, report)) in reports.iter().enumerate() {
+        let ok = match report.passed() {
+            true => "ok",
+            false => "not ok",
+        };
+        writeln!(output, "{} {} - {}", ok, i + 1, name)?;
+        if report.passed() {
+            continue;
+        }
+        for line in report.plain_diff().lines() {
+            writeln!(output, "# {}", line)?;
+        }
+        for failure in &report.failures {
+            writeln!(output, "# assertion failed: {}", failure)?;
+        }
+    }
+    for (i, (name, e)) in errors.iter().enumerate() {
+        writeln!(
+            output,
+            "not ok {} - {} # error",
+            reports.len() + i + 1,
+            name
+        )?;
+        for line in e.to_string().lines() {
+            writeln!(output, "# {}", line)?;
+        }
+    }
+    Ok(output)
+}
@@ -1,10 +1,16 @@
-use anyhow::{Ok, Result};
-use console::{style, Style};
-use similar::{ChangeTag, TextDiff};
+use crate::JsonChange;
+use anyhow::{anyhow, Context, Ok, Result};
+use console::{pad_str, style, Alignment, Style, Term};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use similar::{ChangeTag, DiffOp, DiffTag, TextDiff};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Write};
 use std::io::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
 use syntect::easy::HighlightLines;
-use syntect::highlighting::ThemeSet;
+use syntect::highlighting::{Theme, ThemeSet};
 use syntect::parsing::SyntaxSet;
 use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
 
@@ -19,21 +25,43 @@ impl fmt::Display for Line {
     }
 }
 
+/// 按选项中的视图比较两段文本
+pub fn diff_text_with(text1: &str, text2: &str, options: &DiffOptions) -> Result<String> {
+    // patch 和 markdown 都使用不带颜色的 unified diff
+    if options.format != OutputFormat::Text {
+        return Ok(diff_patch(text1, text2, options));
+    }
+    match options.view {
+        DiffView::Unified => diff_unified(text1, text2, options),
+        DiffView::SideBySide => {
+            diff_side_by_side(text1, text2, Term::stdout().size().1 as usize, options)
+        }
+    }
+}
+
 pub fn diff_text(text1: &str, text2: &str) -> Result<String> {
+    diff_unified(text1, text2, &DiffOptions::default())
+}
+
+fn diff_unified(text1: &str, text2: &str, options: &DiffOptions) -> Result<String> {
     let mut output = String::new();
     let diff = TextDiff::from_lines(text1, text2);
 
-    for (idx, group) in diff.grouped_ops(3).iter().enumerate() {
+    for (idx, group) in diff
+        .grouped_ops(context_lines(&diff, options))
+        .iter()
+        .enumerate()
+    {
         if idx > 0 {
             writeln!(&mut output, "{:-^1$}", "-", 80)?;
         }
         for op in group {
+            if options.granularity == Granularity::Char && op.tag() == DiffTag::Replace {
+                write_char_changes(&mut output, &diff, op)?;
+                continue;
+            }
             for change in diff.iter_inline_changes(op) {
-                let (sign, s) = match change.tag() {
-                    ChangeTag::Delete => ("-", Style::new().red()),
-                    ChangeTag::Insert => ("+", Style::new().green()),
-                    ChangeTag::Equal => (" ", Style::new().dim()),
-                };
+                let (sign, s) = change_style(change.tag());
                 write!(
                     &mut output,
                     "{}{} |{}",
@@ -42,8 +70,10 @@ pub fn diff_text(text1: &str, text2: &str) -> Result<String> {
                     s.apply_to(sign).bold(),
                 )?;
                 for (emphasized, value) in change.iter_strings_lossy() {
-                    if emphasized {
-                        write!(&mut output, "{}", s.apply_to(value).underlined().on_black())?;
+                    // line 粒度时不强调行内的差异
+                    if emphasized && options.granularity != Granularity::Line {
+                        let emphasis = emphasis_style(change.tag());
+                        write!(&mut output, "{}", emphasis.apply_to(value))?;
                     } else {
                         write!(&mut output, "{}", s.apply_to(value))?;
                     }
@@ -58,17 +88,627 @@ pub fn diff_text(text1: &str, text2: &str) -> Result<String> {
     Ok(output)
 }
 
+// 差异前后显示的行数，full 时显示全部内容
+pub(crate) fn context_lines(diff: &TextDiff<str>, options: &DiffOptions) -> usize {
+    match options.context.unwrap_or_default() {
+        DiffContext::Lines(n) => n,
+        DiffContext::Full => diff.old_slices().len().max(diff.new_slices().len()),
+    }
+}
+
+fn change_style(tag: ChangeTag) -> (&'static str, Style) {
+    let colors = diff_styles();
+    match tag {
+        ChangeTag::Delete => ("-", colors.removed),
+        ChangeTag::Insert => ("+", colors.added),
+        ChangeTag::Equal => (" ", Style::new().dim()),
+    }
+}
+
+// 行内强调的部分使用的样式
+fn emphasis_style(tag: ChangeTag) -> Style {
+    let colors = diff_styles();
+    match tag {
+        ChangeTag::Delete => colors.removed_emphasis,
+        _ => colors.added_emphasis,
+    }
+}
+
+// 被替换的行按顺序两两配对，逐字符比较并强调不同的字符
+fn write_char_changes(output: &mut String, diff: &TextDiff<str>, op: &DiffOp) -> Result<()> {
+    let (_, old_range, new_range) = op.as_tag_tuple();
+    let line = |lines: &[&str], i: usize| lines[i].trim_end_matches(['\r', '\n']).to_string();
+    let old: Vec<String> = old_range
+        .clone()
+        .map(|i| line(diff.old_slices(), i))
+        .collect();
+    let new: Vec<String> = new_range
+        .clone()
+        .map(|i| line(diff.new_slices(), i))
+        .collect();
+
+    for (tag, lines, others, start) in [
+        (ChangeTag::Delete, &old, &new, old_range.start),
+        (ChangeTag::Insert, &new, &old, new_range.start),
+    ] {
+        let (sign, s) = change_style(tag);
+        for (i, text) in lines.iter().enumerate() {
+            let index = Some(start + i);
+            let (old_index, new_index) = match tag {
+                ChangeTag::Delete => (index, None),
+                _ => (None, index),
+            };
+            write!(
+                output,
+                "{}{} |{}",
+                style(Line(old_index)).dim(),
+                style(Line(new_index)).dim(),
+                s.apply_to(sign).bold(),
+            )?;
+            let segments = match (tag, others.get(i)) {
+                (ChangeTag::Delete, Some(other)) => char_segments(text, other, tag),
+                (_, Some(other)) => char_segments(other, text, tag),
+                (_, None) => vec![(false, text.clone())],
+            };
+            for (emphasized, value) in segments {
+                if emphasized {
+                    write!(output, "{}", emphasis_style(tag).apply_to(value))?;
+                } else {
+                    write!(output, "{}", s.apply_to(value))?;
+                }
+            }
+            writeln!(output)?;
+        }
+    }
+    Ok(())
+}
+
+// 逐字符比较一对行，返回 tag 这一边的文本片段以及是否需要强调
+fn char_segments(old: &str, new: &str, tag: ChangeTag) -> Vec<(bool, String)> {
+    let mut segments: Vec<(bool, String)> = vec![];
+    for change in TextDiff::from_chars(old, new).iter_all_changes() {
+        let emphasized = match change.tag() {
+            ChangeTag::Equal => false,
+            t if t == tag => true,
+            _ => continue,
+        };
+        match segments.last_mut() {
+            Some((e, text)) if *e == emphasized => text.push_str(change.value()),
+            _ => segments.push((emphasized, change.value().to_string())),
+        }
+    }
+    segments
+}
+
+/// 响应的比较方式
+/// how responses are compared
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffMode {
+    // 按行比较文本
+    #[default]
+    Text,
+    // 两边都是 JSON 时按字段比较，输出每个路径上的新增、删除和修改
+    Json,
+    // 两边都是 JSON 时输出 JSON Patch (RFC 6902) 文档
+    JsonPatch,
+}
+
+/// 只比较响应的一部分
+/// the part of responses to compare
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResponsePart {
+    // 状态行
+    Status,
+    // 响应头
+    Headers,
+    // 响应体
+    Body,
+}
+
+/// 文本差异的输出视图
+/// how text differences are displayed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffView {
+    // 统一格式，删除和新增的行上下排列
+    #[default]
+    Unified,
+    // 左右两栏，适合较宽的终端
+    SideBySide,
+}
+
+/// 统一格式中行内差异的强调粒度
+/// granularity of the intra-line emphasis in the unified view
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Granularity {
+    // 只标记整行，不强调行内差异
+    Line,
+    // 相似的行之间强调不同的单词
+    #[default]
+    Word,
+    // 被替换的行两两配对，强调不同的字符
+    Char,
+}
+
+/// 差异前后显示的上下文行数，`full` 显示完整的响应
+/// number of context lines around each change, or `full`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffContext {
+    Lines(usize),
+    Full,
+}
+
+impl Default for DiffContext {
+    fn default() -> Self {
+        DiffContext::Lines(3)
+    }
+}
+
+impl FromStr for DiffContext {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "full" => Ok(DiffContext::Full),
+            n => n.parse().map(DiffContext::Lines).map_err(|_| {
+                anyhow!(
+                    "context must be a number or `full``上下文必须是数字或 full: {}",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+impl fmt::Display for DiffContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffContext::Lines(n) => write!(f, "{}", n),
+            DiffContext::Full => write!(f, "full"),
+        }
+    }
+}
+
+impl Serialize for DiffContext {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            DiffContext::Lines(n) => serializer.serialize_u64(*n as u64),
+            DiffContext::Full => serializer.serialize_str("full"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DiffContext {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        // 配置中可以写数字或者 full
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Lines(usize),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Lines(n) => std::result::Result::Ok(DiffContext::Lines(n)),
+            Raw::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// 输出格式
+/// output format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    // 带颜色和行号的差异
+    #[default]
+    Text,
+    // 不带颜色的标准 unified diff
+    Patch,
+    // 汇总表加上 diff 代码块，可以直接贴到 PR 描述中
+    Markdown,
+    // 机器可读的结构化结果
+    Json,
+    // Test Anything Protocol
+    Tap,
+}
+
+impl OutputFormat {
+    /// 根据文件扩展名推断输出格式，无法识别时返回 None
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        match ext.to_ascii_lowercase().as_str() {
+            "txt" => Some(Self::Text),
+            "patch" | "diff" => Some(Self::Patch),
+            "md" | "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            "tap" => Some(Self::Tap),
+            _ => None,
+        }
+    }
+
+    /// 写入文件时使用的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Patch => "patch",
+            Self::Markdown => "md",
+            Self::Json => "json",
+            Self::Tap => "tap",
+        }
+    }
+}
+
+/// 命令行中控制比较和输出的选项
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffOptions {
+    pub format: OutputFormat,
+    pub mode: DiffMode,
+    pub view: DiffView,
+    pub granularity: Granularity,
+    // 不设置时使用配置中的 context，都没有时为 3 行
+    pub context: Option<DiffContext>,
+    // 有差异但相似度不低于这个值时仍然算作通过
+    pub min_similarity: Option<f32>,
+    // 差异超过这个行数时截断，None 时输出全部
+    pub max_lines: Option<usize>,
+    // 只比较响应的这一部分，None 时比较全部
+    pub only: Option<ResponsePart>,
+    // 按名称比较响应头，而不是按行比较，配置中的 semantic_headers 也会开启
+    pub semantic_headers: bool,
+    // 把原始响应保存到这个目录，之后可以用 replay 离线比较
+    pub record: Option<String>,
+    // patch 文件头中两边的名称，None 时为 req1 和 req2
+    pub labels: Option<[String; 2]>,
+}
+
+/// 只保留差异的前 max 行，并在末尾说明省略的行数
+pub(crate) fn truncate_diff(diff: String, max: usize) -> String {
+    let total = diff.lines().count();
+    if total <= max {
+        return diff;
+    }
+    let mut output: String = diff.lines().take(max).flat_map(|l| [l, "\n"]).collect();
+    output.push_str(&format!(
+        "… {} more differing lines (use --full to see all)\n",
+        thousands(total - max)
+    ));
+    output
+}
+
+// 每三位加一个逗号，例如 12,345
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut output = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            output.push(',');
+        }
+        output.push(c);
+    }
+    output
+}
+
+/// 输出 JSON 的结构差异，每个路径一行：`+` 新增，`-` 删除，`~` 修改
+pub fn diff_json(changes: &[JsonChange]) -> Result<String> {
+    let colors = diff_styles();
+    let mut output = String::new();
+    for change in changes {
+        match change {
+            JsonChange::Added { path, value } => {
+                let line = format!("+ {}: {}", path, value);
+                writeln!(&mut output, "{}", colors.added.apply_to(line))?;
+            }
+            JsonChange::Removed { path, value } => {
+                let line = format!("- {}: {}", path, value);
+                writeln!(&mut output, "{}", colors.removed.apply_to(line))?;
+            }
+            JsonChange::Changed { path, old, new } => {
+                let line = format!("~ {}: {} -> {}", path, old, new);
+                writeln!(&mut output, "{}", colors.changed.apply_to(line))?;
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// 按名称比较响应头，每个有变化的响应头一行：`+` 新增，`-` 删除，`~` 修改
+pub(crate) fn diff_header_map(
+    headers1: &BTreeMap<String, String>,
+    headers2: &BTreeMap<String, String>,
+) -> Result<String> {
+    let colors = diff_styles();
+    let mut output = String::new();
+    let names: BTreeSet<&String> = headers1.keys().chain(headers2.keys()).collect();
+    for name in names {
+        match (headers1.get(name), headers2.get(name)) {
+            (None, Some(value)) => {
+                let line = format!("+ {}: {}", name, value);
+                writeln!(&mut output, "{}", colors.added.apply_to(line))?;
+            }
+            (Some(value), None) => {
+                let line = format!("- {}: {}", name, value);
+                writeln!(&mut output, "{}", colors.removed.apply_to(line))?;
+            }
+            (Some(old), Some(new)) if old != new => {
+                let line = format!("~ {}: {} -> {}", name, old, new);
+                writeln!(&mut output, "{}", colors.changed.apply_to(line))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(output)
+}
+
+/// 不带颜色的标准 unified diff，带有 `---`/`+++` 文件头，可以直接交给 patch 等工具使用
+pub fn diff_patch(text1: &str, text2: &str, options: &DiffOptions) -> String {
+    let diff = TextDiff::from_lines(text1, text2);
+    let context = context_lines(&diff, options);
+    let (label1, label2) = match &options.labels {
+        Some([label1, label2]) => (label1.as_str(), label2.as_str()),
+        None => ("req1", "req2"),
+    };
+    diff.unified_diff()
+        .context_radius(context)
+        .header(label1, label2)
+        .to_string()
+}
+
+/// 左右两栏输出差异，修改的行左右对齐 \
+/// 行首的标记：`<` 只在左边，`>` 只在右边，`|` 两边不同
+pub fn diff_side_by_side(
+    text1: &str,
+    text2: &str,
+    width: usize,
+    options: &DiffOptions,
+) -> Result<String> {
+    let colors = diff_styles();
+    let mut output = String::new();
+    let diff = TextDiff::from_lines(text1, text2);
+    let (old, new) = (diff.old_slices(), diff.new_slices());
+    // 两个行号、分隔符和标记占用 14 列，剩下的平分给两栏
+    let column = (width.max(40) - 14) / 2;
+    let cell = |line: Option<&&str>| {
+        let line = line
+            .map(|l| l.trim_end_matches(['\r', '\n']))
+            .unwrap_or_default();
+        pad_str(
+            &line.replace('\t', "    "),
+            column,
+            Alignment::Left,
+            Some("…"),
+        )
+        .into_owned()
+    };
+
+    for (idx, group) in diff
+        .grouped_ops(context_lines(&diff, options))
+        .iter()
+        .enumerate()
+    {
+        if idx > 0 {
+            writeln!(&mut output, "{:-^1$}", "-", width.max(40))?;
+        }
+        for op in group {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            for i in 0..old_range.len().max(new_range.len()) {
+                let old_index = Some(old_range.start + i).filter(|i| old_range.contains(i));
+                let new_index = Some(new_range.start + i).filter(|i| new_range.contains(i));
+                let (sign, s) = match (tag, old_index, new_index) {
+                    (DiffTag::Equal, _, _) => (" ", Style::new().dim()),
+                    (_, Some(_), Some(_)) => ("|", colors.changed.clone()),
+                    (_, Some(_), None) => ("<", colors.removed.clone()),
+                    _ => (">", colors.added.clone()),
+                };
+                writeln!(
+                    &mut output,
+                    "{}{} {} {}{}",
+                    style(Line(old_index)).dim(),
+                    s.apply_to(cell(old_index.and_then(|i| old.get(i)))),
+                    s.apply_to(sign).bold(),
+                    style(Line(new_index)).dim(),
+                    s.apply_to(cell(new_index.and_then(|i| new.get(i)))),
+                )?;
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// 是否输出颜色
+/// when to use colors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    // 终端中输出颜色，设置了 NO_COLOR 环境变量时不输出
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+static COLOR_CHOICE: Mutex<ColorChoice> = Mutex::new(ColorChoice::Auto);
+
+/// 设置全局的颜色选项，差异、语法高亮和错误信息都按这个设置输出
+/// set the global color choice used by diffs, highlighting and errors
+pub fn set_color_choice(choice: ColorChoice) {
+    *COLOR_CHOICE.lock().unwrap() = choice;
+    console::set_colors_enabled(use_color(atty::Stream::Stdout));
+    console::set_colors_enabled_stderr(use_color(atty::Stream::Stderr));
+}
+
+/// 按全局的颜色选项判断输出到 stream 时是否使用颜色
+pub fn use_color(stream: atty::Stream) -> bool {
+    match *COLOR_CHOICE.lock().unwrap() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            !no_color && atty::is(stream)
+        }
+    }
+}
+
+/// diff 的配色预设
+/// preset of diff colors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorPreset {
+    // 红色删除，绿色新增
+    #[default]
+    Default,
+    // 橙色删除，蓝色新增，红绿色盲也可以区分
+    Colorblind,
+}
+
+/// diff 的颜色和样式配置，样式为以 `.` 分隔的属性，例如 `red.bold`、`blue.on_black`、`208.underlined`
+/// (数字为 256 色)，没有设置的部分使用预设中的样式
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffColors {
+    #[serde(skip_serializing_if = "crate::is_default", default)]
+    pub preset: ColorPreset,
+    // 新增的行
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub added: Option<String>,
+    // 删除的行
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub removed: Option<String>,
+    // 修改的行：JSON 差异中的 `~` 以及左右两栏视图中的 `|`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub changed: Option<String>,
+    // 行内不同的部分在新增/删除样式的基础上追加的样式
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub emphasis: Option<String>,
+}
+
+// 样式中可以使用的属性，其他的只能是 0-255 的数字
+const STYLE_ATTRIBUTES: &[&str] = &[
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright",
+    "bold",
+    "dim",
+    "underlined",
+    "blink",
+    "reverse",
+    "strikethrough",
+];
+
+#[derive(Debug, Clone)]
+struct DiffStyles {
+    added: Style,
+    removed: Style,
+    changed: Style,
+    added_emphasis: Style,
+    removed_emphasis: Style,
+}
+
+static DIFF_COLORS: Mutex<Option<DiffStyles>> = Mutex::new(None);
+
+// 当前的 diff 样式，没有设置时使用默认预设
+fn diff_styles() -> DiffStyles {
+    let mut styles = DIFF_COLORS.lock().unwrap();
+    styles
+        .get_or_insert_with(|| DiffColors::default().styles().unwrap())
+        .clone()
+}
+
+impl DiffColors {
+    /// 检查所有样式是否有效
+    pub fn validate(&self) -> Result<()> {
+        for style in [&self.added, &self.removed, &self.changed, &self.emphasis]
+            .into_iter()
+            .flatten()
+        {
+            parse_style(style)?;
+        }
+        Ok(())
+    }
+
+    fn styles(&self) -> Result<DiffStyles> {
+        let (added, removed, changed, emphasis) = match self.preset {
+            ColorPreset::Default => ("green", "red", "yellow", "underlined.on_black"),
+            ColorPreset::Colorblind => ("33", "208", "magenta", "bold.underlined"),
+        };
+        let added = self.added.as_deref().unwrap_or(added);
+        let removed = self.removed.as_deref().unwrap_or(removed);
+        let changed = self.changed.as_deref().unwrap_or(changed);
+        let emphasis = self.emphasis.as_deref().unwrap_or(emphasis);
+        Ok(DiffStyles {
+            added: parse_style(added)?,
+            removed: parse_style(removed)?,
+            changed: parse_style(changed)?,
+            added_emphasis: parse_style(&format!("{}.{}", added, emphasis))?,
+            removed_emphasis: parse_style(&format!("{}.{}", removed, emphasis))?,
+        })
+    }
+}
+
+// console 会忽略无法识别的属性，这里先检查一遍
+fn parse_style(s: &str) -> Result<Style> {
+    for part in s.split('.') {
+        let color = part.strip_prefix("on_").unwrap_or(part);
+        if !STYLE_ATTRIBUTES.contains(&color) && color.parse::<u8>().is_err() {
+            return Err(anyhow!(
+                "Invalid style {}, unknown attribute {}`无效的样式",
+                s,
+                part
+            ));
+        }
+    }
+    Ok(Style::from_dotted_str(s))
+}
+
+/// 设置 diff 使用的颜色和样式
+/// set the colors and styles used by diffs
+pub fn set_diff_colors(colors: &DiffColors) -> Result<()> {
+    *DIFF_COLORS.lock().unwrap() = Some(colors.styles()?);
+    Ok(())
+}
+
+// 没有设置主题时使用的语法高亮主题
+const DEFAULT_THEME: &str = "Solarized (dark)";
+
+static THEME: Mutex<Option<Theme>> = Mutex::new(None);
+
+/// 设置语法高亮主题，可以是内置主题的名称，也可以是 .tmTheme 文件的路径
+/// set the highlighting theme by built-in name or `.tmTheme` file
+pub fn set_theme(theme: &str) -> Result<()> {
+    let path = Path::new(theme);
+    let loaded = if theme.ends_with(".tmTheme") || path.is_file() {
+        ThemeSet::get_theme(path)
+            .with_context(|| format!("failed to load theme`加载主题失败: {}", theme))?
+    } else {
+        let mut themes = ThemeSet::load_defaults().themes;
+        let names = themes.keys().cloned().collect::<Vec<_>>().join(", ");
+        themes
+            .remove(theme)
+            .ok_or_else(|| anyhow!("Unknown theme {}, available: {}`未知的主题", theme, names))?
+    };
+    *THEME.lock().unwrap() = Some(loaded);
+    Ok(())
+}
+
 pub fn highlight_text(text: &str, extension: &str) -> Result<String> {
+    if !use_color(atty::Stream::Stdout) {
+        return Ok(text.to_string());
+    }
     // Load these once at the start of your program
     // 加载语法集和主题集
     let ps = SyntaxSet::load_defaults_newlines();
-    let ts = ThemeSet::load_defaults();
+    let theme = match THEME.lock().unwrap().clone() {
+        Some(theme) => theme,
+        None => ThemeSet::load_defaults().themes[DEFAULT_THEME].clone(),
+    };
 
     let syntax = ps
         .find_syntax_by_extension(extension)
         .expect("extension not found");
 
-    let mut higlin = HighlightLines::new(syntax, &ts.themes.iter().collect::<Vec<_>>()[1].1);
+    let mut higlin = HighlightLines::new(syntax, &theme);
     let mut output = String::new();
     for line in LinesWithEndings::from(text) {
         let ranges = higlin.highlight_line(line, &ps).unwrap();
@@ -79,6 +719,49 @@ pub fn highlight_text(text: &str, extension: &str) -> Result<String> {
     Ok(output)
 }
 
+/// 输出到标准输出；终端中内容超过一屏时交给 `$PAGER`(默认为 less)分页，和 git 的行为一致
+/// print to stdout, through `$PAGER` when the text does not fit in the terminal
+pub fn print_paged(text: &str, pager: bool) -> Result<()> {
+    let fits = Term::stdout()
+        .size_checked()
+        .is_none_or(|(rows, _)| text.lines().count() < rows as usize);
+    let child = match pager && !fits && atty::is(atty::Stream::Stdout) {
+        true => spawn_pager(),
+        false => None,
+    };
+    let Some(mut child) = child else {
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        write!(stdout, "{}", text)?;
+        return Ok(());
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // 用户提前退出分页程序时写入会失败，忽略这个错误
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+// 启动分页程序，找不到时返回 None
+fn spawn_pager() -> Option<std::process::Child> {
+    let command = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less".to_string());
+    let mut parts = command.split_whitespace();
+    std::process::Command::new(parts.next()?)
+        .args(parts)
+        // less 保留颜色，内容不足一屏时直接退出
+        .env(
+            "LESS",
+            std::env::var("LESS").unwrap_or_else(|_| "FRX".to_string()),
+        )
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .ok()
+}
+
 // 判断是否为默认值
 pub fn is_default<T: Default + PartialEq>(t: &T) -> bool {
     t == &T::default()
@@ -89,7 +772,7 @@ pub fn print_error(result: Result<()>) -> Result<()> {
     if let Err(e) = result {
         let stderr = std::io::stderr();
         let mut stderr = stderr.lock();
-        if atty::is(atty::Stream::Stderr) {
+        if use_color(atty::Stream::Stderr) {
             let color = Style::new().red();
             writeln!(stderr, "{}", color.apply_to(format!("{:?}", e)))?;
         } else {